@@ -0,0 +1,117 @@
+use super::{add_unit, convert, get, parse_quantity, set_out_point, set_string};
+use crate::{out_point::OutPoint, JsOutput, JsOutputArray, Output};
+use js_sys::Array;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+fn to_js_output(utxo: &JsValue) -> Option<JsOutput> {
+    // `tx_index` is the name of the index in older responses.
+    let index = match get(utxo, "output_index") {
+        index if index.is_undefined() => get(utxo, "tx_index"),
+        index => index,
+    };
+    let index = index.as_f64()?;
+
+    if index < 0.0 || index.fract() != 0.0 || index > u32::MAX as f64 {
+        return None;
+    }
+
+    let out_point = OutPoint::new(&get(utxo, "tx_hash").as_string()?, index as u32)?;
+    let amount = get(utxo, "amount");
+    let mut output = Output {
+        value: 0,
+        assets: BTreeMap::new(),
+        data: None,
+    };
+
+    if !Array::is_array(&amount) {
+        return None;
+    }
+
+    for entry in Array::from(&amount).iter() {
+        let unit = get(&entry, "unit").as_string()?;
+        add_unit(
+            &mut output,
+            &unit,
+            parse_quantity(&get(&entry, "quantity"))?,
+        )?;
+    }
+
+    let js_output: JsOutput = output.into();
+
+    if let Some(address) = get(utxo, "address").as_string() {
+        js_output.set_address(&address);
+    }
+
+    set_string(&js_output, "datumHash", &get(utxo, "data_hash"));
+    set_string(&js_output, "datum", &get(utxo, "inline_datum"));
+    set_out_point(&js_output, &out_point, utxo);
+
+    Some(js_output)
+}
+
+/**
+Convert Blockfrost UTxOs to outputs
+
+The UTxOs are the response of `/addresses/{address}/utxos`, either the JSON string or already parsed,
+i.e. `{ address, tx_hash, output_index, amount, data_hash, inline_datum }`.
+Each `unit` of the `amount` is `lovelace` or the policy ID followed by the asset name,
+and its `quantity` a decimal string. The `data_hash` and `inline_datum` are kept
+as `datumHash` and `datum`.
+
+Raises errors when any UTxO is malformed.
+*/
+#[wasm_bindgen(js_name = fromBlockfrost)]
+pub fn from_blockfrost(utxos: &JsValue) -> Result<JsOutputArray, JsError> {
+    convert(utxos, "Blockfrost", to_js_output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{adapters::from_blockfrost, JsOutput};
+    use js_sys::{Array, Reflect};
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_from_blockfrost() {
+        let utxos = r#"[
+          {
+            "address": "addr_test1vz35vu6aqmdw6uuc34gkpdymrpsd3lsuh6ffq6d9vja0s6spkenss",
+            "tx_hash": "09d34606abdcd0b10ebc89307cbfa0b469f9144194137b45b7a04b273961add8",
+            "output_index": 1,
+            "amount": [
+              { "unit": "lovelace", "quantity": "7620669" },
+              { "unit": "b0d07d45fe9514f80213f4020e5a61241458be626841cde717cb38a76d794e4654", "quantity": "2" }
+            ],
+            "block": "7eb8e27d18686c7db9a18f8bbcfe34e3fed6e047afaa2d969904d15e934847e6",
+            "data_hash": null,
+            "inline_datum": "d87980",
+            "reference_script_hash": null
+          }
+        ]"#;
+
+        let outputs: Array = from_blockfrost(&utxos.into()).unwrap().unchecked_into();
+        assert_eq!(outputs.length(), 1);
+
+        let output: JsOutput = outputs.get(0).unchecked_into();
+        assert_eq!(output.lovelace(), 7620669);
+        let assets: Array = output.assets().unchecked_into();
+        let asset = assets.get(0);
+        assert_eq!(
+            Reflect::get(&asset, &"assetName".into()).unwrap(),
+            "6d794e4654"
+        );
+        assert_eq!(Reflect::get(&output, &"datum".into()).unwrap(), "d87980");
+        assert!(Reflect::get(&output, &"datumHash".into())
+            .unwrap()
+            .is_undefined());
+
+        let invalid = r#"[{ "tx_hash": "00", "output_index": 0, "amount": [] }]"#;
+        assert!(from_blockfrost(&invalid.into()).is_err());
+        let unit = r#"[{ "tx_hash": "09d34606abdcd0b10ebc89307cbfa0b469f9144194137b45b7a04b273961add8", "output_index": 0, "amount": [{ "unit": "b0d0", "quantity": "1" }] }]"#;
+        assert!(from_blockfrost(&unit.into()).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use super::{add_asset, entries, get, parse_json, parse_quantity, set_out_point};
+use crate::{out_point::OutPoint, raise, ErrorKind, JsOutput, JsOutputArray, Output};
+use js_sys::Array;
+use std::collections::BTreeMap;
+use wasm_bindgen::{prelude::*, JsCast};
+
+/**
+Convert a Hydra head UTxO snapshot to outputs
+
+The snapshot is the UTxO map exposed by hydra-node, keyed by `hash#index`,
+either as the JSON string or already parsed.
+The `address` of each UTxO is kept, and its `value` is read from `lovelace`
+and the asset quantities keyed by policy ID and asset name.
+
+Raises errors when the snapshot is malformed.
+*/
+#[wasm_bindgen(js_name = fromHydraSnapshot)]
+pub fn from_hydra_snapshot(snapshot: &JsValue) -> Result<JsOutputArray, JsError> {
+    let snapshot = parse_json(snapshot, "Hydra snapshot")?;
+
+    if !snapshot.is_object() {
+        return Err(raise(
+            ErrorKind::BadResponse,
+            "Hydra snapshot should be an object",
+        ));
+    }
+
+    let result = Array::new();
+
+    for (key, tx_out) in entries(&snapshot) {
+        let output = to_js_output(&key, &tx_out)
+            .ok_or_else(|| raise(ErrorKind::BadResponse, &format!("Invalid Hydra UTxO {key}")))?;
+        result.push(&output);
+    }
+
+    Ok(result.unchecked_into())
+}
+
+fn to_js_output(key: &str, tx_out: &JsValue) -> Option<JsOutput> {
+    let out_point = OutPoint::from_key(key)?;
+    let value = get(tx_out, "value");
+
+    if !value.is_object() {
+        return None;
+    }
+
+    let mut output = Output {
+        value: 0,
+        assets: BTreeMap::new(),
+        data: None,
+    };
+
+    for (policy_id, quantities) in entries(&value) {
+        if policy_id == "lovelace" {
+            output.value = parse_quantity(&quantities)?;
+        } else if quantities.is_object() {
+            for (asset_name, quantity) in entries(&quantities) {
+                add_asset(
+                    &mut output,
+                    policy_id.clone(),
+                    asset_name,
+                    parse_quantity(&quantity)?,
+                )?;
+            }
+        } else {
+            return None;
+        }
+    }
+
+    let js_output: JsOutput = output.into();
+
+    if let Some(address) = get(tx_out, "address").as_string() {
+        js_output.set_address(&address);
+    }
+
+    set_out_point(&js_output, &out_point, tx_out);
+
+    Some(js_output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{adapters::from_hydra_snapshot, JsOutput};
+    use js_sys::{try_iter, Array, Reflect};
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_from_hydra_snapshot() {
+        let snapshot = r#"{
+          "09d34606abdcd0b10ebc89307cbfa0b469f9144194137b45b7a04b273961add8#1": {
+            "address": "addr_test1vz35vu6aqmdw6uuc34gkpdymrpsd3lsuh6ffq6d9vja0s6spkenss",
+            "datum": null,
+            "datumhash": null,
+            "inlineDatum": null,
+            "referenceScript": null,
+            "value": {
+              "lovelace": 7620669,
+              "b0d07d45fe9514f80213f4020e5a61241458be626841cde717cb38a7": {
+                "6d794e4654": 2
+              }
+            }
+          },
+          "1b5d6f5fa1a2167e2c8c0b2b5b1b2f5c6a3b4d5e6f708192a3b4c5d6e7f8091a#0": {
+            "address": "addr_test1vz35vu6aqmdw6uuc34gkpdymrpsd3lsuh6ffq6d9vja0s6spkenss",
+            "value": { "lovelace": "10000000" }
+          }
+        }"#;
+
+        let outputs = from_hydra_snapshot(&snapshot.into()).unwrap();
+        let outputs: Vec<JsOutput> = try_iter(&outputs)
+            .unwrap()
+            .unwrap()
+            .map(|o| o.unwrap().unchecked_into())
+            .collect();
+
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].lovelace(), 7620669);
+        assert_eq!(outputs[0].assets().unchecked_into::<Array>().length(), 1);
+        assert_eq!(outputs[1].lovelace(), 10000000);
+        assert_eq!(outputs[1].assets().unchecked_into::<Array>().length(), 0);
+
+        let data = Reflect::get(&outputs[0], &"data".into()).unwrap();
+        assert_eq!(Reflect::get(&data, &"index".into()).unwrap(), 1);
+
+        assert!(from_hydra_snapshot(&JsValue::from(1)).is_err());
+        assert!(from_hydra_snapshot(&r#"{ "nohash": { "value": {} } }"#.into()).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use super::{add_asset, convert, get, parse_quantity, set_out_point, set_string};
+use crate::{out_point::OutPoint, JsOutput, JsOutputArray, Output};
+use js_sys::Array;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+fn to_js_output(utxo: &JsValue) -> Option<JsOutput> {
+    let index = get(utxo, "tx_index").as_f64()?;
+
+    if index < 0.0 || index.fract() != 0.0 || index > u32::MAX as f64 {
+        return None;
+    }
+
+    let out_point = OutPoint::new(&get(utxo, "tx_hash").as_string()?, index as u32)?;
+    let mut output = Output {
+        value: parse_quantity(&get(utxo, "value"))?,
+        assets: BTreeMap::new(),
+        data: None,
+    };
+
+    let asset_list = get(utxo, "asset_list");
+    if Array::is_array(&asset_list) {
+        for asset in Array::from(&asset_list).iter() {
+            let policy_id = get(&asset, "policy_id").as_string()?;
+            // The asset name is null for the empty name.
+            let asset_name = get(&asset, "asset_name").as_string().unwrap_or_default();
+            add_asset(
+                &mut output,
+                policy_id,
+                asset_name,
+                parse_quantity(&get(&asset, "quantity"))?,
+            )?;
+        }
+    } else if !asset_list.is_undefined() && !asset_list.is_null() {
+        return None;
+    }
+
+    let js_output: JsOutput = output.into();
+
+    if let Some(address) = get(utxo, "address").as_string() {
+        js_output.set_address(&address);
+    }
+
+    set_string(&js_output, "datumHash", &get(utxo, "datum_hash"));
+    set_string(
+        &js_output,
+        "datum",
+        &get(&get(utxo, "inline_datum"), "bytes"),
+    );
+    set_out_point(&js_output, &out_point, utxo);
+
+    Some(js_output)
+}
+
+/**
+Convert Koios UTxOs to outputs
+
+The UTxOs are the response of `address_utxos` or `utxo_info`, either the JSON string or already parsed,
+i.e. `{ tx_hash, tx_index, address, value, asset_list, datum_hash, inline_datum }`.
+The `value` is the lovelace, and each asset of the `asset_list` has its `policy_id`,
+`asset_name` and `quantity`, all strings. The `datum_hash` and the `bytes`
+of the `inline_datum` are kept as `datumHash` and `datum`.
+
+Raises errors when any UTxO is malformed.
+*/
+#[wasm_bindgen(js_name = fromKoios)]
+pub fn from_koios(utxos: &JsValue) -> Result<JsOutputArray, JsError> {
+    convert(utxos, "Koios", to_js_output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{adapters::from_koios, JsOutput};
+    use js_sys::{Array, Reflect};
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_from_koios() {
+        let utxos = r#"[
+          {
+            "tx_hash": "09d34606abdcd0b10ebc89307cbfa0b469f9144194137b45b7a04b273961add8",
+            "tx_index": 1,
+            "address": "addr_test1vz35vu6aqmdw6uuc34gkpdymrpsd3lsuh6ffq6d9vja0s6spkenss",
+            "value": "7620669",
+            "datum_hash": "923918e403bf43c34b4ef6b48eb2ee04babed17320d8d1b9ff9ad086e86f44ec",
+            "inline_datum": { "bytes": "d87980", "value": { "fields": [], "constructor": 0 } },
+            "reference_script": null,
+            "asset_list": [
+              {
+                "policy_id": "b0d07d45fe9514f80213f4020e5a61241458be626841cde717cb38a7",
+                "asset_name": "6d794e4654",
+                "fingerprint": "asset1x",
+                "decimals": 0,
+                "quantity": "2"
+              }
+            ],
+            "is_spent": false
+          },
+          {
+            "tx_hash": "1b5d6f5fa1a2167e2c8c0b2b5b1b2f5c6a3b4d5e6f708192a3b4c5d6e7f8091a",
+            "tx_index": 0,
+            "value": "10000000",
+            "asset_list": []
+          }
+        ]"#;
+
+        let outputs: Array = from_koios(&utxos.into()).unwrap().unchecked_into();
+        assert_eq!(outputs.length(), 2);
+
+        let first: JsOutput = outputs.get(0).unchecked_into();
+        assert_eq!(first.lovelace(), 7620669);
+        assert_eq!(first.assets().unchecked_into::<Array>().length(), 1);
+        assert_eq!(Reflect::get(&first, &"datum".into()).unwrap(), "d87980");
+
+        let second: JsOutput = outputs.get(1).unchecked_into();
+        assert_eq!(second.lovelace(), 10000000);
+        assert_eq!(second.address(), None);
+
+        let invalid = r#"[{ "tx_hash": "09d34606abdcd0b10ebc89307cbfa0b469f9144194137b45b7a04b273961add8", "tx_index": 0, "value": "-1" }]"#;
+        assert!(from_koios(&invalid.into()).is_err());
+    }
+}
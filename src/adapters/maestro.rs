@@ -0,0 +1,128 @@
+use super::{add_unit, convert, get, parse_json, parse_quantity, set_out_point, set_string};
+use crate::{out_point::OutPoint, JsOutput, JsOutputArray, Output};
+use js_sys::Array;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+fn to_js_output(utxo: &JsValue) -> Option<JsOutput> {
+    let index = get(utxo, "index").as_f64()?;
+
+    if index < 0.0 || index.fract() != 0.0 || index > u32::MAX as f64 {
+        return None;
+    }
+
+    let out_point = OutPoint::new(&get(utxo, "tx_hash").as_string()?, index as u32)?;
+    let assets = get(utxo, "assets");
+    let mut output = Output {
+        value: 0,
+        assets: BTreeMap::new(),
+        data: None,
+    };
+
+    if !Array::is_array(&assets) {
+        return None;
+    }
+
+    for asset in Array::from(&assets).iter() {
+        let unit = get(&asset, "unit").as_string()?;
+        add_unit(&mut output, &unit, parse_quantity(&get(&asset, "amount"))?)?;
+    }
+
+    let js_output: JsOutput = output.into();
+
+    if let Some(address) = get(utxo, "address").as_string() {
+        js_output.set_address(&address);
+    }
+
+    let datum = get(utxo, "datum");
+    match get(&datum, "type").as_string().as_deref() {
+        Some("hash") => set_string(&js_output, "datumHash", &get(&datum, "hash")),
+        Some("inline") => set_string(&js_output, "datum", &get(&datum, "bytes")),
+        _ => {}
+    }
+
+    set_out_point(&js_output, &out_point, utxo);
+
+    Some(js_output)
+}
+
+/**
+Convert Maestro UTxOs to outputs
+
+The UTxOs are the response of `/addresses/{address}/utxos`, either the JSON string or already parsed,
+as the whole response of a page or its `data`, i.e. `{ tx_hash, index, address, assets, datum }`.
+Each `unit` of the `assets` is `lovelace` or the policy ID followed by the asset name.
+The `datum` is kept as `datumHash` or the inline `datum` by its `type`.
+
+Raises errors when any UTxO is malformed.
+*/
+#[wasm_bindgen(js_name = fromMaestro)]
+pub fn from_maestro(utxos: &JsValue) -> Result<JsOutputArray, JsError> {
+    let utxos = parse_json(utxos, "Maestro")?;
+    let data = get(&utxos, "data");
+
+    if Array::is_array(&data) {
+        convert(&data, "Maestro", to_js_output)
+    } else {
+        convert(&utxos, "Maestro", to_js_output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{adapters::from_maestro, JsOutput};
+    use js_sys::{Array, Reflect, JSON};
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_from_maestro() {
+        let response = r#"{
+          "data": [
+            {
+              "tx_hash": "09d34606abdcd0b10ebc89307cbfa0b469f9144194137b45b7a04b273961add8",
+              "index": 1,
+              "slot": 41246355,
+              "assets": [
+                { "unit": "lovelace", "amount": 7620669 },
+                { "unit": "b0d07d45fe9514f80213f4020e5a61241458be626841cde717cb38a76d794e4654", "amount": 2 }
+              ],
+              "address": "addr_test1vz35vu6aqmdw6uuc34gkpdymrpsd3lsuh6ffq6d9vja0s6spkenss",
+              "datum": {
+                "type": "hash",
+                "hash": "923918e403bf43c34b4ef6b48eb2ee04babed17320d8d1b9ff9ad086e86f44ec",
+                "bytes": null,
+                "json": null
+              },
+              "reference_script": null
+            }
+          ],
+          "last_updated": { "timestamp": "2023-06-12 10:09:17", "block_slot": 41246355 },
+          "next_cursor": null
+        }"#;
+
+        let outputs: Array = from_maestro(&response.into()).unwrap().unchecked_into();
+        assert_eq!(outputs.length(), 1);
+
+        let output: JsOutput = outputs.get(0).unchecked_into();
+        assert_eq!(output.lovelace(), 7620669);
+        assert_eq!(output.assets().unchecked_into::<Array>().length(), 1);
+        assert_eq!(
+            Reflect::get(&output, &"datumHash".into()).unwrap(),
+            "923918e403bf43c34b4ef6b48eb2ee04babed17320d8d1b9ff9ad086e86f44ec"
+        );
+
+        // The `data` of the response alone.
+        let data = Reflect::get(&JSON::parse(response).unwrap(), &"data".into()).unwrap();
+        assert_eq!(
+            from_maestro(&data)
+                .unwrap()
+                .unchecked_into::<Array>()
+                .length(),
+            1
+        );
+        assert!(from_maestro(&r#"{ "data": null }"#.into()).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+/*!
+Adapters of UTxOs from other tools
+
+Each adapter converts the UTxO shape of a tool or provider into an array of `Output`.
+The `data` of an adapted output is an `OutPoint` (`hash` and `index`)
+with the original UTxO attached as `raw`, so the selected outputs
+are enough to make the transaction inputs.
+*/
+mod blockfrost;
+mod hydra;
+mod koios;
+mod maestro;
+mod ogmios;
+mod plu_ts;
+
+pub use blockfrost::from_blockfrost;
+pub use hydra::from_hydra_snapshot;
+pub use koios::from_koios;
+pub use maestro::from_maestro;
+pub use ogmios::{from_kupo, from_ogmios};
+pub use plu_ts::{from_plu_ts_utxos, to_plu_ts_value};
+
+use crate::{
+    error_with_cause, out_point::OutPoint, raise, ErrorKind, JsOutput, JsOutputArray, Output,
+};
+use js_sys::{Array, Object, Reflect, JSON};
+use wasm_bindgen::{prelude::*, JsCast};
+
+/// Parses a quantity given as a bigint, a safe integer number or a decimal string.
+fn parse_quantity(value: &JsValue) -> Option<u64> {
+    if value.is_bigint() {
+        u64::try_from(value.clone()).ok()
+    } else if let Some(number) = value.as_f64() {
+        let safe = number >= 0.0 && number.fract() == 0.0 && number <= 9007199254740991.0;
+        safe.then_some(number as u64)
+    } else {
+        value.as_string().and_then(|text| text.parse().ok())
+    }
+}
+
+fn set_out_point(output: &JsOutput, out_point: &OutPoint, raw: &JsValue) {
+    let data = out_point.to_js();
+    Reflect::set(&data, &"raw".into(), raw).expect("Unreachable");
+    output.set_data(&data);
+}
+
+fn get(target: &JsValue, key: &str) -> JsValue {
+    Reflect::get(target, &key.into()).unwrap_or(JsValue::UNDEFINED)
+}
+
+/// The entries of an object keyed by strings, none if it is not an object.
+fn entries(object: &JsValue) -> Vec<(String, JsValue)> {
+    if !object.is_object() {
+        return Vec::new();
+    }
+
+    Object::entries(object.unchecked_ref())
+        .iter()
+        .filter_map(|entry| {
+            let entry: Array = entry.unchecked_into();
+            Some((entry.get(0).as_string()?, entry.get(1)))
+        })
+        .collect()
+}
+
+/// The response of a provider, either the JSON string or already parsed.
+fn parse_json(response: &JsValue, provider: &str) -> Result<JsValue, JsError> {
+    match response.as_string() {
+        Some(text) => JSON::parse(&text).map_err(|e| {
+            error_with_cause(ErrorKind::BadJson, &format!("Invalid {provider} JSON"), &e)
+        }),
+        None => Ok(response.clone()),
+    }
+}
+
+/// Converts the array of UTxOs of a provider, either the JSON string or already parsed.
+fn convert(
+    utxos: &JsValue,
+    provider: &str,
+    to_js_output: fn(&JsValue) -> Option<JsOutput>,
+) -> Result<JsOutputArray, JsError> {
+    let utxos = parse_json(utxos, provider)?;
+
+    if !Array::is_array(&utxos) {
+        return Err(raise(
+            ErrorKind::BadResponse,
+            &format!("{provider} UTxOs should be an array"),
+        ));
+    }
+
+    let result = Array::new();
+
+    for (index, utxo) in Array::from(&utxos).iter().enumerate() {
+        let output = to_js_output(&utxo).ok_or_else(|| {
+            raise(
+                ErrorKind::BadResponse,
+                &format!("Invalid {provider} UTxO at {index}"),
+            )
+        })?;
+        result.push(&output);
+    }
+
+    Ok(result.unchecked_into())
+}
+
+/// The bytes in hex of a policy ID, which a unit starts with.
+const POLICY_ID_LENGTH: usize = 56;
+
+/**
+Adds the quantity of a unit, the policy ID followed by the asset name in hex,
+or `lovelace` for lovelace.
+*/
+fn add_unit(output: &mut Output, unit: &str, quantity: u64) -> Option<()> {
+    if unit == "lovelace" {
+        output.value = output.value.checked_add(quantity)?;
+        return Some(());
+    }
+
+    if unit.len() < POLICY_ID_LENGTH || !unit.is_char_boundary(POLICY_ID_LENGTH) {
+        return None;
+    }
+
+    let (policy_id, asset_name) = unit.split_at(POLICY_ID_LENGTH);
+
+    add_asset(output, policy_id.into(), asset_name.into(), quantity)
+}
+
+/// Adds the quantity of the asset to what the output holds of it.
+fn add_asset(
+    output: &mut Output,
+    policy_id: String,
+    asset_name: String,
+    quantity: u64,
+) -> Option<()> {
+    let key = (policy_id, asset_name);
+    let quantity = output
+        .assets
+        .get(&key)
+        .copied()
+        .unwrap_or_default()
+        .checked_add(quantity)?;
+    output.insert_asset(key, quantity);
+
+    Some(())
+}
+
+/// Sets the field of the output to the value if it is a string, e.g. a datum in hex.
+fn set_string(output: &JsOutput, key: &str, value: &JsValue) {
+    if value.is_string() {
+        Reflect::set(output, &key.into(), value).expect("Unreachable");
+    }
+}
@@ -0,0 +1,222 @@
+use super::{add_asset, convert, entries, get, parse_quantity, set_out_point, set_string};
+use crate::{out_point::OutPoint, JsOutput, JsOutputArray, Output};
+use js_sys::Array;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+fn to_index(value: &JsValue) -> Option<u32> {
+    let index = value.as_f64()?;
+
+    (index >= 0.0 && index.fract() == 0.0 && index <= u32::MAX as f64).then_some(index as u32)
+}
+
+/// Reads `{ coins, assets }` whose assets are keyed by `policy.name`, or `policy` for the empty name.
+fn read_coins_and_assets(value: &JsValue, output: &mut Output) -> Option<()> {
+    output.value = parse_quantity(&get(value, "coins"))?;
+
+    for (unit, quantity) in entries(&get(value, "assets")) {
+        let (policy_id, asset_name) = unit.split_once('.').unwrap_or((&unit, ""));
+        add_asset(
+            output,
+            policy_id.into(),
+            asset_name.into(),
+            parse_quantity(&quantity)?,
+        )?;
+    }
+
+    Some(())
+}
+
+/// Reads a UTxO of Ogmios, `{ transaction: { id }, index, address, value }` or `[{ txId, index }, { address, value }]`.
+fn ogmios_output(utxo: &JsValue) -> Option<JsOutput> {
+    let mut output = Output {
+        value: 0,
+        assets: BTreeMap::new(),
+        data: None,
+    };
+
+    let (out_point, tx_out) = if Array::is_array(utxo) {
+        let pair = Array::from(utxo);
+        let input = pair.get(0);
+        let out_point = OutPoint::new(
+            &get(&input, "txId").as_string()?,
+            to_index(&get(&input, "index"))?,
+        )?;
+        let tx_out = pair.get(1);
+        read_coins_and_assets(&get(&tx_out, "value"), &mut output)?;
+        (out_point, tx_out)
+    } else {
+        let out_point = OutPoint::new(
+            &get(&get(utxo, "transaction"), "id").as_string()?,
+            to_index(&get(utxo, "index"))?,
+        )?;
+        let value = get(utxo, "value");
+        if !value.is_object() {
+            return None;
+        }
+
+        for (policy_id, assets) in entries(&value) {
+            if policy_id == "ada" {
+                output.value = parse_quantity(&get(&assets, "lovelace"))?;
+                continue;
+            }
+
+            if !assets.is_object() {
+                return None;
+            }
+
+            for (asset_name, quantity) in entries(&assets) {
+                add_asset(
+                    &mut output,
+                    policy_id.clone(),
+                    asset_name,
+                    parse_quantity(&quantity)?,
+                )?;
+            }
+        }
+        (out_point, utxo.clone())
+    };
+
+    let js_output: JsOutput = output.into();
+
+    if let Some(address) = get(&tx_out, "address").as_string() {
+        js_output.set_address(&address);
+    }
+
+    set_string(&js_output, "datumHash", &get(&tx_out, "datumHash"));
+    set_string(&js_output, "datum", &get(&tx_out, "datum"));
+    set_out_point(&js_output, &out_point, utxo);
+
+    Some(js_output)
+}
+
+/// Reads a match of Kupo, `{ transaction_id, output_index, address, value, datum_hash, datum_type }`.
+fn kupo_output(utxo: &JsValue) -> Option<JsOutput> {
+    let out_point = OutPoint::new(
+        &get(utxo, "transaction_id").as_string()?,
+        to_index(&get(utxo, "output_index"))?,
+    )?;
+    let mut output = Output {
+        value: 0,
+        assets: BTreeMap::new(),
+        data: None,
+    };
+
+    read_coins_and_assets(&get(utxo, "value"), &mut output)?;
+
+    let js_output: JsOutput = output.into();
+
+    if let Some(address) = get(utxo, "address").as_string() {
+        js_output.set_address(&address);
+    }
+
+    // The hash of an inline datum is not a datum hash of the output.
+    if get(utxo, "datum_type").as_string().as_deref() == Some("hash") {
+        set_string(&js_output, "datumHash", &get(utxo, "datum_hash"));
+    }
+
+    set_out_point(&js_output, &out_point, utxo);
+
+    Some(js_output)
+}
+
+/**
+Convert Ogmios UTxOs to outputs
+
+The UTxOs are the result of the local-state-query of the UTxO, either the JSON string or already parsed.
+Those of Ogmios v6 are `{ transaction: { id }, index, address, value, datumHash, datum }`
+with the lovelace at `value.ada.lovelace` and the assets keyed by policy ID and asset name.
+Those of Ogmios v5 are the pairs `[{ txId, index }, { address, value: { coins, assets } }]`
+with the assets keyed by `policy.name`.
+
+Raises errors when any UTxO is malformed.
+*/
+#[wasm_bindgen(js_name = fromOgmios)]
+pub fn from_ogmios(utxos: &JsValue) -> Result<JsOutputArray, JsError> {
+    convert(utxos, "Ogmios", ogmios_output)
+}
+
+/**
+Convert Kupo matches to outputs
+
+The matches are the response of `/matches`, either the JSON string or already parsed,
+i.e. `{ transaction_id, output_index, address, value: { coins, assets }, datum_hash, datum_type }`
+with the assets keyed by `policy.name`. The `datum_hash` is kept as `datumHash`
+only if the `datum_type` is `hash`, since an inline datum is not in the match.
+
+Raises errors when any match is malformed.
+*/
+#[wasm_bindgen(js_name = fromKupo)]
+pub fn from_kupo(matches: &JsValue) -> Result<JsOutputArray, JsError> {
+    convert(matches, "Kupo", kupo_output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        adapters::{from_kupo, from_ogmios},
+        JsOutput,
+    };
+    use js_sys::{Array, Reflect};
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_from_ogmios_and_kupo() {
+        let v6 = r#"[
+          {
+            "transaction": { "id": "09d34606abdcd0b10ebc89307cbfa0b469f9144194137b45b7a04b273961add8" },
+            "index": 1,
+            "address": "addr_test1vz35vu6aqmdw6uuc34gkpdymrpsd3lsuh6ffq6d9vja0s6spkenss",
+            "value": {
+              "ada": { "lovelace": 7620669 },
+              "b0d07d45fe9514f80213f4020e5a61241458be626841cde717cb38a7": { "6d794e4654": 2 }
+            },
+            "datum": "d87980"
+          }
+        ]"#;
+        let v5 = r#"[
+          [
+            { "txId": "09d34606abdcd0b10ebc89307cbfa0b469f9144194137b45b7a04b273961add8", "index": 1 },
+            {
+              "address": "addr_test1vz35vu6aqmdw6uuc34gkpdymrpsd3lsuh6ffq6d9vja0s6spkenss",
+              "value": { "coins": 7620669, "assets": { "b0d07d45fe9514f80213f4020e5a61241458be626841cde717cb38a7.6d794e4654": 2 } }
+            }
+          ]
+        ]"#;
+        let kupo = r#"[
+          {
+            "transaction_index": 3,
+            "transaction_id": "09d34606abdcd0b10ebc89307cbfa0b469f9144194137b45b7a04b273961add8",
+            "output_index": 1,
+            "address": "addr_test1vz35vu6aqmdw6uuc34gkpdymrpsd3lsuh6ffq6d9vja0s6spkenss",
+            "value": { "coins": 7620669, "assets": { "b0d07d45fe9514f80213f4020e5a61241458be626841cde717cb38a7.6d794e4654": 2 } },
+            "datum_hash": "923918e403bf43c34b4ef6b48eb2ee04babed17320d8d1b9ff9ad086e86f44ec",
+            "datum_type": "inline",
+            "script_hash": null,
+            "created_at": { "slot_no": 1, "header_hash": "00" },
+            "spent_at": null
+          }
+        ]"#;
+
+        for outputs in [
+            from_ogmios(&v6.into()).unwrap(),
+            from_ogmios(&v5.into()).unwrap(),
+            from_kupo(&kupo.into()).unwrap(),
+        ] {
+            let outputs: Array = outputs.unchecked_into();
+            let output: JsOutput = outputs.get(0).unchecked_into();
+            assert_eq!(output.lovelace(), 7620669);
+            assert_eq!(output.assets().unchecked_into::<Array>().length(), 1);
+            let data = Reflect::get(&output, &"data".into()).unwrap();
+            assert_eq!(Reflect::get(&data, &"index".into()).unwrap(), 1);
+            assert!(Reflect::get(&output, &"datumHash".into())
+                .unwrap()
+                .is_undefined());
+        }
+
+        assert!(from_kupo(&v6.into()).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use super::{entries, get, parse_quantity, set_out_point};
+use crate::{out_point::OutPoint, raise, ErrorKind, JsOutput, JsOutputArray, Output};
+use js_sys::{Array, Object, Reflect};
+use std::collections::BTreeMap;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type PluTsValue = Array<{
+  policy: string
+  assets: { [assetName: string]: bigint }
+}>
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "PluTsValue")]
+    pub type PluTsValue;
+}
+
+/// Adds the quantity of the asset, where the empty policy and asset name are lovelace.
+fn add(output: &mut Output, policy_id: &str, asset_name: String, quantity: u64) -> Option<()> {
+    if policy_id.is_empty() {
+        if !asset_name.is_empty() {
+            return None;
+        }
+
+        output.value = output.value.checked_add(quantity)?;
+    } else {
+        let key = (policy_id.to_string(), asset_name);
+        let quantity = output
+            .assets
+            .get(&key)
+            .copied()
+            .unwrap_or_default()
+            .checked_add(quantity)?;
+        output.insert_asset(key, quantity);
+    }
+
+    Some(())
+}
+
+/// Reads a `Value` either as the array of `{ policy, assets }` or as the object keyed by policy IDs.
+fn read_value(value: &JsValue, output: &mut Output) -> Option<()> {
+    if Array::is_array(value) {
+        for entry in Array::from(value).iter() {
+            let policy_id = get(&entry, "policy").as_string()?;
+
+            for (asset_name, quantity) in entries(&get(&entry, "assets")) {
+                add(output, &policy_id, asset_name, parse_quantity(&quantity)?)?;
+            }
+        }
+    } else if value.is_object() {
+        for (policy_id, assets) in entries(value) {
+            if policy_id == "lovelace" {
+                output.value = output.value.checked_add(parse_quantity(&assets)?)?;
+                continue;
+            }
+
+            if !assets.is_object() {
+                return None;
+            }
+
+            for (asset_name, quantity) in entries(&assets) {
+                add(output, &policy_id, asset_name, parse_quantity(&quantity)?)?;
+            }
+        }
+    } else {
+        return None;
+    }
+
+    Some(())
+}
+
+fn to_js_output(utxo: &JsValue) -> Option<JsOutput> {
+    let utxo_ref = get(utxo, "utxoRef");
+    let index = get(&utxo_ref, "index").as_f64()?;
+
+    if index < 0.0 || index.fract() != 0.0 || index > u32::MAX as f64 {
+        return None;
+    }
+
+    let out_point = OutPoint::new(&get(&utxo_ref, "id").as_string()?, index as u32)?;
+    let resolved = get(utxo, "resolved");
+    let mut output = Output {
+        value: 0,
+        assets: BTreeMap::new(),
+        data: None,
+    };
+
+    read_value(&get(&resolved, "value"), &mut output)?;
+
+    let js_output: JsOutput = output.into();
+
+    if let Some(address) = get(&resolved, "address").as_string() {
+        js_output.set_address(&address);
+    }
+
+    set_out_point(&js_output, &out_point, utxo);
+
+    Some(js_output)
+}
+
+/**
+Convert plu-ts UTxOs to outputs
+
+Each UTxO is in the JSON shape of plu-ts, i.e. `{ utxoRef: { id, index }, resolved: { address, value } }`
+as given by `UTxO.toJson()`. The `value` is either the array of `{ policy, assets }`,
+where the empty policy and asset name are lovelace, or the object keyed by policy IDs
+with `lovelace` for lovelace. Quantities may be bigints, numbers or decimal strings.
+
+Raises errors when any UTxO is malformed.
+*/
+#[wasm_bindgen(js_name = fromPluTsUtxos)]
+pub fn from_plu_ts_utxos(utxos: &JsValue) -> Result<JsOutputArray, JsError> {
+    if !Array::is_array(utxos) {
+        return Err(raise(
+            ErrorKind::BadResponse,
+            "plu-ts UTxOs should be an array",
+        ));
+    }
+
+    let result = Array::new();
+
+    for (index, utxo) in Array::from(utxos).iter().enumerate() {
+        let output = to_js_output(&utxo).ok_or_else(|| {
+            raise(
+                ErrorKind::BadResponse,
+                &format!("Invalid plu-ts UTxO at {index}"),
+            )
+        })?;
+        result.push(&output);
+    }
+
+    Ok(result.unchecked_into())
+}
+
+/**
+Convert the value of an output to a plu-ts `Value`
+
+The value is the array of `{ policy, assets }` that `new Value(...)` of plu-ts takes,
+with lovelace under the empty policy and asset name.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = toPluTsValue)]
+pub fn to_plu_ts_value(output: &JsOutput) -> Result<PluTsValue, JsError> {
+    let output: Output = output.try_into()?;
+    let entry = |policy_id: &str, assets: &Object| {
+        let entry = Object::new();
+        Reflect::set(&entry, &"policy".into(), &policy_id.into()).expect("Unreachable");
+        Reflect::set(&entry, &"assets".into(), assets).expect("Unreachable");
+        entry
+    };
+
+    let lovelace = Object::new();
+    Reflect::set(&lovelace, &"".into(), &output.value.into()).expect("Unreachable");
+    let result = Array::of1(&entry("", &lovelace));
+    let mut policy: Option<(&str, Object)> = None;
+
+    for ((policy_id, asset_name), quantity) in output.assets.iter() {
+        if policy.as_ref().is_none_or(|(id, _)| id != policy_id) {
+            if let Some((id, assets)) = policy.take() {
+                result.push(&entry(id, &assets));
+            }
+            policy = Some((policy_id, Object::new()));
+        }
+
+        if let Some((_, assets)) = policy.as_ref() {
+            Reflect::set(assets, &asset_name.into(), &(*quantity).into()).expect("Unreachable");
+        }
+    }
+
+    if let Some((id, assets)) = policy {
+        result.push(&entry(id, &assets));
+    }
+
+    Ok(result.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        adapters::{from_plu_ts_utxos, to_plu_ts_value},
+        JsOutput,
+    };
+    use js_sys::{Array, Reflect, JSON};
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_from_plu_ts_utxos() {
+        let utxos = JSON::parse(
+            r#"[
+              {
+                "utxoRef": { "id": "09d34606abdcd0b10ebc89307cbfa0b469f9144194137b45b7a04b273961add8", "index": 1 },
+                "resolved": {
+                  "address": "addr_test1vz35vu6aqmdw6uuc34gkpdymrpsd3lsuh6ffq6d9vja0s6spkenss",
+                  "value": [
+                    { "policy": "", "assets": { "": "7620669" } },
+                    { "policy": "b0d07d45fe9514f80213f4020e5a61241458be626841cde717cb38a7", "assets": { "6d794e4654": 2 } }
+                  ]
+                }
+              },
+              {
+                "utxoRef": { "id": "1b5d6f5fa1a2167e2c8c0b2b5b1b2f5c6a3b4d5e6f708192a3b4c5d6e7f8091a", "index": 0 },
+                "resolved": { "address": "addr_test1vz35vu6aqmdw6uuc34gkpdymrpsd3lsuh6ffq6d9vja0s6spkenss", "value": { "lovelace": 10000000 } }
+              }
+            ]"#,
+        )
+        .unwrap();
+
+        let outputs: Array = from_plu_ts_utxos(&utxos).unwrap().unchecked_into();
+        assert_eq!(outputs.length(), 2);
+
+        let first: JsOutput = outputs.get(0).unchecked_into();
+        assert_eq!(first.lovelace(), 7620669);
+        let data = Reflect::get(&first, &"data".into()).unwrap();
+        assert_eq!(Reflect::get(&data, &"index".into()).unwrap(), 1);
+
+        let second: JsOutput = outputs.get(1).unchecked_into();
+        assert_eq!(second.lovelace(), 10000000);
+
+        let value: Array = to_plu_ts_value(&first).unwrap().unchecked_into();
+        assert_eq!(value.length(), 2);
+        let assets = Reflect::get(&value.get(1), &"assets".into()).unwrap();
+        assert_eq!(
+            Reflect::get(&assets, &"6d794e4654".into()).unwrap(),
+            JsValue::from(2u64)
+        );
+
+        let invalid = JSON::parse(r#"[{ "utxoRef": { "id": "00", "index": 0 } }]"#).unwrap();
+        assert!(from_plu_ts_utxos(&invalid).is_err());
+    }
+}
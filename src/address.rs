@@ -0,0 +1,492 @@
+/*!
+Addresses of Cardano
+
+A Shelley address is a header byte of its type and network followed by credentials,
+written in bech32 with `addr` or `stake` and `_test` off mainnet. A Byron address is
+the CBOR of its root and attributes with a CRC32, written in base58.
+Either may also be given as the hex of its bytes, as CIP-30 wallets return them.
+*/
+use crate::{
+    cbor::{from_hex, to_hex, Error, Reader},
+    raise, ErrorKind,
+};
+use js_sys::{Object, Reflect};
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type Credential = {
+  type: "key" | "script"
+  hash: string
+}
+
+export type AddressInfo = {
+  type: "base" | "pointer" | "enterprise" | "reward" | "byron"
+  networkId: number
+  hex: string
+  paymentCredential?: Credential
+  stakeCredential?: Credential
+  pointer?: { slot: bigint, txIndex: bigint, certIndex: bigint }
+  protocolMagic?: number
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "AddressInfo")]
+    pub type AddressInfo;
+}
+
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// The bytes of a key or script hash.
+const HASH_LENGTH: usize = 28;
+
+/// The tag of CBOR data encoded in bytes.
+const ENCODED_CBOR: u64 = 24;
+
+const MAINNET: u8 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Base,
+    Pointer,
+    Enterprise,
+    Reward,
+    Byron,
+}
+
+impl Kind {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Base => "base",
+            Self::Pointer => "pointer",
+            Self::Enterprise => "enterprise",
+            Self::Reward => "reward",
+            Self::Byron => "byron",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Credential {
+    pub(crate) script: bool,
+    pub(crate) hash: Vec<u8>,
+}
+
+impl Credential {
+    fn new(script: bool, hash: &[u8]) -> Self {
+        Self {
+            script,
+            hash: hash.to_vec(),
+        }
+    }
+
+    fn to_js(&self) -> Object {
+        let result = Object::new();
+        let kind = if self.script { "script" } else { "key" };
+        Reflect::set(&result, &"type".into(), &kind.into()).expect("Unreachable");
+        Reflect::set(&result, &"hash".into(), &to_hex(&self.hash).into()).expect("Unreachable");
+        result
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Pointer {
+    pub(crate) slot: u64,
+    pub(crate) tx_index: u64,
+    pub(crate) cert_index: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Address {
+    pub(crate) kind: Kind,
+    /// `1` for mainnet, `0` for the testnets.
+    pub(crate) network_id: u8,
+    pub(crate) payment: Option<Credential>,
+    pub(crate) stake: Option<Credential>,
+    pub(crate) pointer: Option<Pointer>,
+    /// The protocol magic of a Byron address off mainnet.
+    pub(crate) protocol_magic: Option<u32>,
+    pub(crate) bytes: Vec<u8>,
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+    values.iter().fold(1, |checksum, value| {
+        let top = checksum >> 25;
+        let checksum = (checksum & 0x1ffffff) << 5 ^ u32::from(*value);
+
+        GENERATOR
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (top >> i) & 1 == 1)
+            .fold(checksum, |checksum, (_, g)| checksum ^ g)
+    })
+}
+
+/// Decodes bech32 of any length, returning the human-readable part and the bytes.
+pub(crate) fn bech32_decode(text: &str) -> Option<(String, Vec<u8>)> {
+    let has_lower = text.bytes().any(|b| b.is_ascii_lowercase());
+    let has_upper = text.bytes().any(|b| b.is_ascii_uppercase());
+    if has_lower && has_upper {
+        return None;
+    }
+
+    let text = text.to_ascii_lowercase();
+    let separator = text.rfind('1')?;
+    let (hrp, data) = (&text[..separator], &text[separator + 1..]);
+    if hrp.is_empty() || data.len() < 6 || !hrp.bytes().all(|b| (33..=126).contains(&b)) {
+        return None;
+    }
+
+    let data: Vec<u8> = data
+        .bytes()
+        .map(|c| BECH32_CHARSET.iter().position(|b| *b == c).map(|i| i as u8))
+        .collect::<Option<_>>()?;
+    let mut values: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|b| b & 31));
+    values.extend(&data);
+    if polymod(&values) != 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::new();
+    let (mut accumulator, mut bits) = (0u32, 0u32);
+    for value in &data[..data.len() - 6] {
+        accumulator = accumulator << 5 | u32::from(*value);
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((accumulator >> bits) as u8);
+            accumulator &= (1 << bits) - 1;
+        }
+    }
+
+    (bits < 5 && accumulator == 0).then(|| (hrp.to_string(), bytes))
+}
+
+pub(crate) fn base58_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::new();
+
+    for c in text.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|b| *b == c)? as u32;
+
+        for byte in bytes.iter_mut() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let zeros = text.bytes().take_while(|b| *b == b'1').count();
+    bytes.extend(std::iter::repeat_n(0, zeros));
+    bytes.reverse();
+
+    Some(bytes)
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb88320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+/// Reads a natural number of 7 bits a byte, the high bit set on all but the last.
+fn variable_nat(bytes: &[u8], position: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+
+    loop {
+        let byte = *bytes.get(*position)?;
+        *position += 1;
+        value = value.checked_mul(128)? | u64::from(byte & 0x7f);
+
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+}
+
+/// Reads the payload and CRC32 of a Byron address, `[#6.24(bytes), uint]`.
+fn byron_payload(bytes: &[u8]) -> Result<(Vec<u8>, u64), Error> {
+    let invalid = Error::Invalid("Invalid Byron address");
+    let mut reader = Reader::new(bytes);
+
+    if reader.array()? != Some(2) || reader.tag()? != ENCODED_CBOR {
+        return Err(invalid);
+    }
+
+    let payload = reader.bytes()?;
+    let crc = reader.uint()?;
+
+    reader.is_end().then_some((payload, crc)).ok_or(invalid)
+}
+
+/// Reads the protocol magic of a payload, `[root, attributes, type]`.
+fn byron_protocol_magic(payload: &[u8]) -> Result<Option<u32>, Error> {
+    let invalid = Error::Invalid("Invalid Byron address");
+    let mut reader = Reader::new(payload);
+    let mut protocol_magic = None;
+
+    if reader.array()? != Some(3) || reader.bytes()?.len() != HASH_LENGTH {
+        return Err(invalid);
+    }
+
+    let attributes = reader.map()?;
+    let mut i = 0;
+    while reader.next(attributes, i)? {
+        match reader.uint()? {
+            // The magic is CBOR in bytes.
+            2 => {
+                let magic = Reader::new(&reader.bytes()?).uint()?;
+                protocol_magic = Some(u32::try_from(magic).map_err(|_| invalid)?);
+            }
+            _ => reader.skip()?,
+        }
+        i += 1;
+    }
+
+    reader.uint()?;
+
+    reader.is_end().then_some(protocol_magic).ok_or(invalid)
+}
+
+fn byron(bytes: &[u8]) -> Result<Address, &'static str> {
+    let (payload, crc) = byron_payload(bytes).map_err(|_| "Invalid Byron address")?;
+
+    if u64::from(crc32(&payload)) != crc {
+        return Err("Invalid checksum of Byron address");
+    }
+
+    let protocol_magic = byron_protocol_magic(&payload).map_err(|_| "Invalid Byron address")?;
+
+    Ok(Address {
+        kind: Kind::Byron,
+        network_id: if protocol_magic.is_some() { 0 } else { MAINNET },
+        payment: None,
+        stake: None,
+        pointer: None,
+        protocol_magic,
+        bytes: bytes.to_vec(),
+    })
+}
+
+/// Reads an address from its bytes.
+pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Address, &'static str> {
+    let header = *bytes.first().ok_or("Empty address")?;
+    let (kind, network_id) = (header >> 4, header & 0x0f);
+    let hash = |start: usize| bytes.get(start..start + HASH_LENGTH);
+    let mut address = Address {
+        kind: Kind::Base,
+        network_id,
+        payment: None,
+        stake: None,
+        pointer: None,
+        protocol_magic: None,
+        bytes: bytes.to_vec(),
+    };
+    let length = match kind {
+        0..=3 => {
+            address.payment = hash(1).map(|h| Credential::new(kind & 1 == 1, h));
+            address.stake = hash(1 + HASH_LENGTH).map(|h| Credential::new(kind & 2 == 2, h));
+            1 + 2 * HASH_LENGTH
+        }
+        4 | 5 => {
+            address.kind = Kind::Pointer;
+            address.payment = hash(1).map(|h| Credential::new(kind & 1 == 1, h));
+            let mut position = 1 + HASH_LENGTH;
+            let mut nat = || variable_nat(bytes, &mut position).ok_or("Invalid pointer");
+            address.pointer = Some(Pointer {
+                slot: nat()?,
+                tx_index: nat()?,
+                cert_index: nat()?,
+            });
+            position
+        }
+        6 | 7 => {
+            address.kind = Kind::Enterprise;
+            address.payment = hash(1).map(|h| Credential::new(kind & 1 == 1, h));
+            1 + HASH_LENGTH
+        }
+        8 => return byron(bytes),
+        14 | 15 => {
+            address.kind = Kind::Reward;
+            address.stake = hash(1).map(|h| Credential::new(kind & 1 == 1, h));
+            1 + HASH_LENGTH
+        }
+        _ => return Err("Unknown type of address"),
+    };
+
+    (bytes.len() == length)
+        .then_some(address)
+        .ok_or("Invalid length of address")
+}
+
+/// Reads an address in bech32, base58 or the hex of its bytes.
+pub(crate) fn parse(text: &str) -> Result<Address, &'static str> {
+    if let Some(bytes) = from_hex(text) {
+        return from_bytes(&bytes);
+    }
+
+    if let Some((hrp, bytes)) = bech32_decode(text) {
+        let address = from_bytes(&bytes)?;
+        let prefix = match address.kind {
+            Kind::Reward => "stake",
+            Kind::Byron => return Err("Byron address in bech32"),
+            _ => "addr",
+        };
+        let suffix = if address.network_id == MAINNET {
+            ""
+        } else {
+            "_test"
+        };
+
+        return (hrp == format!("{}{}", prefix, suffix))
+            .then_some(address)
+            .ok_or("Prefix of address mismatching its type or network");
+    }
+
+    let bytes = base58_decode(text).ok_or("Invalid address")?;
+    match from_bytes(&bytes)? {
+        address if address.kind == Kind::Byron => Ok(address),
+        _ => Err("Shelley address in base58"),
+    }
+}
+
+impl Address {
+    fn to_js(&self) -> AddressInfo {
+        let result = Object::new();
+        let set = |key: &str, value: &JsValue| {
+            Reflect::set(&result, &key.into(), value).expect("Unreachable");
+        };
+        set("type", &self.kind.name().into());
+        set("networkId", &self.network_id.into());
+        set("hex", &to_hex(&self.bytes).into());
+
+        if let Some(payment) = &self.payment {
+            set("paymentCredential", &payment.to_js());
+        }
+
+        if let Some(stake) = &self.stake {
+            set("stakeCredential", &stake.to_js());
+        }
+
+        if let Some(pointer) = &self.pointer {
+            let value = Object::new();
+            for (key, n) in [
+                ("slot", pointer.slot),
+                ("txIndex", pointer.tx_index),
+                ("certIndex", pointer.cert_index),
+            ] {
+                Reflect::set(&value, &key.into(), &n.into()).expect("Unreachable");
+            }
+            set("pointer", &value);
+        }
+
+        if let Some(protocol_magic) = self.protocol_magic {
+            set("protocolMagic", &protocol_magic.into());
+        }
+
+        result.unchecked_into()
+    }
+}
+
+/**
+Parse an address
+
+Reads a Shelley address in bech32 or a Byron address in base58, or either in the hex of its bytes,
+and returns its `type`, `networkId`, `hex`, and the `paymentCredential` and `stakeCredential`
+it has, each the `type` of `"key"` or `"script"` and the `hash` in hex.
+A pointer address has its `pointer`, and a Byron address off mainnet its `protocolMagic`.
+
+Raises errors when the address is invalid, e.g. its checksum or the prefix of its network.
+*/
+#[wasm_bindgen(js_name = parseAddress)]
+pub fn parse_address(address: &str) -> Result<AddressInfo, JsError> {
+    parse(address)
+        .map(|address| address.to_js())
+        .map_err(|message| raise(ErrorKind::BadAddress, message))
+}
+
+/**
+Validate an address
+
+Returns `true` if `parseAddress` reads the address.
+*/
+#[wasm_bindgen(js_name = isValidAddress)]
+pub fn is_valid_address(address: &str) -> bool {
+    parse(address).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        address::{bech32_decode, parse, Kind, Pointer},
+        cbor::to_hex,
+    };
+    use wasm_bindgen_test::*;
+
+    const PAYMENT: &str = "9493315cd92eb5d8c4304e67b7e16ae36d61d34502694657811a2c8e";
+    const STAKE: &str = "337b62cfff6403a06a3acbc34f8c46003c69fe79a3628cefa9c47251";
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_parse() {
+        assert!(bech32_decode("a12uel5l").is_some());
+        assert!(bech32_decode("A12UEL5L").is_some());
+        assert!(bech32_decode("A12uel5l").is_none());
+
+        let base = parse("addr1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgse35a3x").unwrap();
+        assert_eq!(base.kind, Kind::Base);
+        assert_eq!(base.network_id, 1);
+        assert_eq!(to_hex(&base.payment.as_ref().unwrap().hash), PAYMENT);
+        assert_eq!(to_hex(&base.stake.as_ref().unwrap().hash), STAKE);
+        assert_eq!(parse(&to_hex(&base.bytes)), Ok(base));
+
+        let pointer =
+            parse("addr1gx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer5pnz75xxcrzqf96k").unwrap();
+        assert_eq!(
+            pointer.pointer,
+            Some(Pointer {
+                slot: 2498243,
+                tx_index: 27,
+                cert_index: 3
+            })
+        );
+
+        let enterprise =
+            parse("addr_test1vz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzerspjrlsz").unwrap();
+        assert_eq!(enterprise.kind, Kind::Enterprise);
+        assert_eq!(enterprise.network_id, 0);
+        assert_eq!(enterprise.stake, None);
+
+        let reward = parse("stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw").unwrap();
+        assert_eq!(reward.kind, Kind::Reward);
+        assert_eq!(to_hex(&reward.stake.unwrap().hash), STAKE);
+
+        let byron = parse("Ae2tdPwUPEZFRbyhz3cpfC2CumGzNkFBN2L42rcUc2yjQpEkxDbkPodpMAi").unwrap();
+        assert_eq!(byron.kind, Kind::Byron);
+        assert_eq!(byron.network_id, 1);
+        assert_eq!(byron.protocol_magic, None);
+
+        // A changed character, and the prefix of the other network.
+        assert!(parse("stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgq").is_err());
+        assert!(parse("addr_test1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgse35a3x").is_err());
+        assert!(parse("Ae2tdPwUPEZFRbyhz3cpfC2CumGzNkFBN2L42rcUc2yjQpEkxDbkPodpMAj").is_err());
+    }
+}
@@ -0,0 +1,276 @@
+/*!
+Balancing draft transactions
+
+A draft is balanced by adding inputs until nothing is missing, then a change output
+of what is left over. Both change the size of the transaction and so its fee,
+which is estimated again until the draft balances with it.
+*/
+use crate::{
+    collect_js_outputs,
+    delta::Delta,
+    draft::{Draft, JsDraft},
+    fee::TxShape,
+    min_ada::min_ada,
+    out_point::OutPoint,
+    protocol::{JsProtocolParameters, ProtocolParameters},
+    raise,
+    scenario::Value,
+    selection::select_fast,
+    to_outputs, ErrorKind, JsOutput, JsOutputArray, Output,
+};
+use js_sys::{Array, Object, Reflect};
+use std::collections::BTreeSet;
+use utxo::ExtOutput;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type BalancedDraft = {
+  inputs: Array<Output>
+  outputs: Array<Output>
+  fee: bigint
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "BalancedDraft")]
+    pub type BalancedDraft;
+}
+
+/// The times the fee is estimated again before giving up.
+const BALANCE_ATTEMPTS: usize = 8;
+
+#[derive(Clone)]
+pub(crate) struct Balanced<'o> {
+    /// The inputs added to the draft.
+    pub(crate) added: Vec<Output<'o>>,
+    /// The change output, `None` if nothing is left over for it.
+    pub(crate) change: Option<Output<'o>>,
+    pub(crate) fee: u64,
+}
+
+fn lovelace<'o>(value: u64) -> Output<'o> {
+    ExtOutput {
+        value,
+        assets: Default::default(),
+        data: None,
+    }
+}
+
+/**
+Balance the draft of the shape by adding inputs of those available and a change output.
+
+The fee is at least that of the draft. Lovelace left over too little for a change output
+is added to the fee. Returns `None` if the available inputs are not enough.
+*/
+pub(crate) fn balance<'o>(
+    draft: &Draft,
+    shape: TxShape,
+    mut available: Vec<Output<'o>>,
+    params: &ProtocolParameters,
+) -> Option<Balanced<'o>> {
+    let mut added: Vec<Output<'o>> = Vec::new();
+    let mut inputs = draft.inputs.clone();
+
+    for _ in 0..BALANCE_ATTEMPTS {
+        let unpaid = Draft {
+            inputs: inputs.clone(),
+            fee: 0,
+            ..draft.clone()
+        };
+        let change_assets = unpaid.imbalance()?.surplus().assets.len() as u64;
+        let size = TxShape {
+            inputs: shape.inputs + added.len() as u64,
+            outputs: shape.outputs + 1,
+            assets: shape.assets + change_assets,
+            witnesses: shape.witnesses + added.len() as u64,
+        }
+        .size();
+        let fee = params.fee.fee(size)?.max(draft.fee);
+        let imbalance = Draft { fee, ..unpaid }.imbalance()?;
+
+        let missing = if imbalance.deficit() == Value::from(0) {
+            if imbalance.is_zero() {
+                return Some(Balanced {
+                    added,
+                    change: None,
+                    fee,
+                });
+            }
+
+            let change: Output = imbalance.to_output()?;
+            let min = min_ada(&change, params.coins_per_utxo_byte);
+
+            if change.value >= min {
+                return Some(Balanced {
+                    added,
+                    change: Some(change),
+                    fee,
+                });
+            }
+
+            if change.assets.is_empty() {
+                return Some(Balanced {
+                    added,
+                    change: None,
+                    fee: fee.checked_add(change.value)?,
+                });
+            }
+
+            lovelace(min - change.value)
+        } else {
+            Delta::from(&imbalance.deficit()).to_output()?
+        };
+
+        // Enough more for a change output of lovelace, to settle in fewer attempts.
+        let threshold = lovelace(min_ada(&lovelace(0), params.coins_per_utxo_byte));
+        let (selected, unselected, _) = select_fast(&mut available[..], &missing, &threshold)?;
+        let selected = selected.to_vec();
+        available = unselected.to_vec();
+
+        for input in selected.iter() {
+            inputs = inputs.checked_add(&input.into())?;
+        }
+
+        added.extend(selected);
+    }
+
+    None
+}
+
+/**
+Balance a draft transaction with the available UTxOs
+
+Adds inputs of `availableUtxos` until nothing of the outputs, fee and deposits is missing,
+then a change output to `changeAddress` of what is left over, estimating the fee again
+for every input and output added. The fee is at least that of the draft, and lovelace
+left over too little for a change output is added to the fee instead.
+The available UTxOs already in the inputs of the draft, by their `OutPoint`s, are skipped.
+Returns the finished inputs and outputs, the change last, with the fee.
+
+Raises errors when the types used are wrong, or when the available UTxOs are not enough.
+*/
+#[wasm_bindgen(js_name = balanceDraft)]
+pub fn balance_draft(
+    draft: &JsDraft,
+    available_utxos: &JsOutputArray,
+    params: &JsProtocolParameters,
+    change_address: &str,
+) -> Result<BalancedDraft, JsError> {
+    let (parsed, js_inputs, js_outputs) = Draft::from_js(draft)?;
+    let spent: BTreeSet<OutPoint> = js_inputs.iter().filter_map(OutPoint::of).collect();
+    let js_available: Vec<JsOutput> = collect_js_outputs(available_utxos)?
+        .into_iter()
+        .filter(|utxo| OutPoint::of(utxo).is_none_or(|out_point| !spent.contains(&out_point)))
+        .collect();
+    let shape = TxShape {
+        inputs: js_inputs.len() as u64,
+        outputs: js_outputs.len() as u64,
+        assets: parsed.outputs.assets.len() as u64,
+        witnesses: js_inputs.len() as u64,
+    };
+    let balanced = balance(
+        &parsed,
+        shape,
+        to_outputs(&js_available)?,
+        &params.try_into()?,
+    )
+    .ok_or_else(|| {
+        raise(
+            ErrorKind::InsufficientInput,
+            "UTxOs are not enough to balance the draft",
+        )
+    })?;
+
+    let inputs: Array = js_inputs.iter().collect();
+    for input in balanced.added.iter() {
+        inputs.push(input.data.expect("Unreachable"));
+    }
+
+    let outputs: Array = js_outputs.iter().collect();
+    if let Some(change) = balanced.change {
+        let change: JsOutput = change.into();
+        change.set_address(change_address);
+        outputs.push(&change);
+    }
+
+    let result = Object::new();
+    Reflect::set(&result, &"inputs".into(), &inputs).expect("Unreachable");
+    Reflect::set(&result, &"outputs".into(), &outputs).expect("Unreachable");
+    Reflect::set(&result, &"fee".into(), &balanced.fee.into()).expect("Unreachable");
+
+    Ok(result.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        balance::balance, delta::Delta, draft::Draft, fee::TxShape, min_ada::min_ada,
+        protocol::tests::mainnet, Output,
+    };
+    use std::collections::BTreeMap;
+    use wasm_bindgen_test::*;
+
+    fn output(value: u64, quantity: u64) -> Output<'static> {
+        let mut output = Output {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        if quantity > 0 {
+            output.insert_asset(("ab".repeat(28), "cd".repeat(4)), quantity);
+        }
+        output
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_balance() {
+        let params = mainnet();
+        let draft = Draft {
+            outputs: Delta::from(&output(5_000_000, 4)),
+            ..Draft::default()
+        };
+        let shape = TxShape {
+            outputs: 1,
+            assets: 1,
+            ..TxShape::default()
+        };
+        let available = vec![
+            output(3_000_000, 0),
+            output(4_000_000, 10),
+            output(1_000_000, 0),
+        ];
+
+        let balanced = balance(&draft, shape, available.clone(), &params).unwrap();
+        assert_eq!(balanced.added.len(), 2);
+        let change = balanced.change.unwrap();
+        assert_eq!(change.assets, output(0, 6).assets);
+        assert!(change.value >= min_ada(&change, params.coins_per_utxo_byte));
+
+        let brought: u64 = balanced.added.iter().map(|input| input.value).sum();
+        assert_eq!(brought, 5_000_000 + balanced.fee + change.value);
+
+        // Too little is left over for a change output, it goes to the fee.
+        let draft = Draft {
+            inputs: Delta::from(&output(5_300_000, 0)),
+            outputs: Delta::from(&output(5_000_000, 0)),
+            ..Draft::default()
+        };
+        let balanced = balance(&draft, TxShape::default(), vec![], &params).unwrap();
+        assert!(balanced.added.is_empty());
+        assert!(balanced.change.is_none());
+        assert_eq!(balanced.fee, 300_000);
+
+        assert!(balance(
+            &Draft {
+                outputs: Delta::from(&output(100_000_000, 0)),
+                ..Draft::default()
+            },
+            TxShape::default(),
+            available,
+            &params,
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,191 @@
+/*!
+Micro-benchmarks of selection
+
+Runs the selection on synthetic UTxO sets inside WASM, so integrators
+can measure it on their target devices without the cost of crossing
+the boundary of JS.
+*/
+use crate::{
+    clock::now,
+    fixtures::{generate, out_of_memory, AssetProfile, Fixture, FixtureOptions},
+    get_amount, get_count, get_field, raise, ErrorCode, ErrorKind,
+};
+use js_sys::{Object, Reflect};
+use std::collections::BTreeMap;
+use utxo::{try_sum, Select};
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type BenchmarkOptions = {
+  utxoCount: number
+  assetDensity?: number
+  seed: bigint
+  iterations?: number
+}
+
+export type BenchmarkResult = {
+  generateMs: number
+  selectMs: number
+  minSelectMs: number
+  maxSelectMs: number
+  iterations: number
+  selectedCount: number
+  feasible: boolean
+  memoryBytes: number
+  memoryGrowthBytes: number
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "BenchmarkOptions")]
+    pub type JsBenchmarkOptions;
+
+    #[wasm_bindgen(typescript_type = "BenchmarkResult")]
+    pub type BenchmarkResult;
+}
+
+/// The number of distinct assets in a benchmark set.
+const ASSET_KINDS: usize = 20;
+
+fn memory_bytes() -> f64 {
+    Reflect::get(&wasm_bindgen::memory(), &"buffer".into())
+        .and_then(|buffer| Reflect::get(&buffer, &"byteLength".into()))
+        .ok()
+        .and_then(|length| length.as_f64())
+        .unwrap_or_default()
+}
+
+/// The target of a benchmark: half the lovelace and one of each of the first assets.
+fn target(fixtures: &[Fixture]) -> Fixture {
+    let total = try_sum(fixtures).unwrap_or_else(Fixture::zero);
+    let mut target = Fixture {
+        value: total.value / 2,
+        assets: BTreeMap::new(),
+        data: None,
+    };
+
+    for (key, _) in total.assets.into_iter().take(3) {
+        target.insert_asset(key, 1);
+    }
+
+    target
+}
+
+/**
+Benchmark the selection on a synthetic UTxO set
+
+Generates `utxoCount` UTxOs from the `seed` (see `generateUtxos`), each holding
+`assetDensity` (default 1, up to 20) assets on average, then selects half of their lovelace
+plus a few of their assets `iterations` (default 10) times.
+
+Returns the time to generate the set, the mean, minimum and maximum time of the selections,
+the resulting WASM memory size and how much it has grown during the benchmark.
+
+Raises errors when the options are invalid, or the memory for the UTxOs cannot be allocated.
+*/
+#[wasm_bindgen(js_name = benchmarkSelect)]
+pub fn benchmark_select(options: &JsBenchmarkOptions) -> Result<BenchmarkResult, JsError> {
+    let density = get_field(options, "assetDensity")?;
+    let density = match density.is_undefined() {
+        true => 1.0,
+        false => density.as_f64().ok_or(ErrorCode::InvalidType)?,
+    };
+
+    if !(0.0..=ASSET_KINDS as f64).contains(&density) {
+        return Err(raise(
+            ErrorKind::BadOptions,
+            "Asset density should be between 0 and 20",
+        ));
+    }
+
+    let iterations = get_count(options, "iterations")?.unwrap_or(10).max(1);
+    let fixture_options = FixtureOptions {
+        count: get_count(options, "utxoCount")?.ok_or(ErrorCode::InvalidType)? as usize,
+        seed: get_amount(options, "seed")?.ok_or(ErrorCode::InvalidType)?,
+        asset_profiles: (0..ASSET_KINDS)
+            .map(|i| AssetProfile {
+                policy_id: format!("{i:056x}"),
+                asset_name: format!("{i:02x}"),
+                probability: density / ASSET_KINDS as f64,
+                min_quantity: 1,
+                max_quantity: 1_000_000,
+            })
+            .collect(),
+        min_lovelace: FixtureOptions::MIN_LOVELACE,
+        max_lovelace: FixtureOptions::MAX_LOVELACE,
+    };
+
+    let memory_before = memory_bytes();
+    let start = now();
+    let fixtures = generate(&fixture_options).ok_or_else(out_of_memory)?;
+    let generate_ms = now() - start;
+    let target = target(&fixtures);
+    let threshold = Fixture::zero();
+    let mut timings = Vec::with_capacity(iterations as usize);
+    let mut selected_count = None;
+
+    for _ in 0..iterations {
+        let mut inputs = fixtures.clone();
+        let start = now();
+        let result = utxo::select(&mut inputs[..], &target, &threshold);
+        timings.push(now() - start);
+        selected_count = result.map(|(selected, _, _)| selected.len());
+    }
+
+    let memory_after = memory_bytes();
+    let result = Object::new();
+    let set = |key: &str, value: JsValue| {
+        Reflect::set(&result, &key.into(), &value).expect("Unreachable");
+    };
+
+    set("generateMs", generate_ms.into());
+    set(
+        "selectMs",
+        (timings.iter().sum::<f64>() / timings.len() as f64).into(),
+    );
+    set(
+        "minSelectMs",
+        timings.iter().copied().fold(f64::INFINITY, f64::min).into(),
+    );
+    set(
+        "maxSelectMs",
+        timings.iter().copied().fold(0.0, f64::max).into(),
+    );
+    set("iterations", iterations.into());
+    set("selectedCount", (selected_count.unwrap_or(0) as u32).into());
+    set("feasible", selected_count.is_some().into());
+    set("memoryBytes", memory_after.into());
+    set("memoryGrowthBytes", (memory_after - memory_before).into());
+
+    Ok(result.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bench::{benchmark_select, BenchmarkResult};
+    use js_sys::{Object, Reflect};
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_benchmark_select() {
+        let options = Object::new();
+        Reflect::set(&options, &"utxoCount".into(), &200.into()).unwrap();
+        Reflect::set(&options, &"assetDensity".into(), &2.into()).unwrap();
+        Reflect::set(&options, &"seed".into(), &42u64.into()).unwrap();
+        Reflect::set(&options, &"iterations".into(), &3.into()).unwrap();
+
+        let result: BenchmarkResult = benchmark_select(&options.unchecked_into()).unwrap();
+        let get = |key: &str| Reflect::get(&result, &key.into()).unwrap();
+
+        assert_eq!(get("iterations"), 3);
+        assert_eq!(get("feasible"), true);
+        assert!(get("selectedCount").as_f64().unwrap() >= 1.0);
+        assert!(get("selectMs").as_f64().unwrap() >= 0.0);
+        assert!(get("memoryBytes").as_f64().unwrap() > 0.0);
+    }
+}
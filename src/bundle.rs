@@ -0,0 +1,281 @@
+/*!
+Splitting of token bundles
+
+The value of an output is at most `maxValueSize` bytes, so a bundle of many assets,
+e.g. change collecting the tokens of many UTxOs, may have to be split into outputs.
+The assets are taken in order, so those of a policy stay together where they fit,
+and every output holds the minimum lovelace of its assets.
+*/
+use crate::{
+    min_ada::{min_ada, value_size},
+    protocol::ProtocolParameters,
+    raise,
+    selection::Thresholded,
+    to_amount, ErrorKind, JsOutput, JsOutputArray, Output,
+};
+use js_sys::Array;
+use std::collections::BTreeMap;
+use utxo::ExtOutput;
+use wasm_bindgen::{prelude::*, JsCast};
+
+type Assets = BTreeMap<(String, String), u64>;
+
+/// Groups the assets into bundles whose values are at most `max_value_size` bytes,
+/// `None` if an asset alone is too large.
+fn bundles(assets: &Assets, max_value_size: u64) -> Option<Vec<Assets>> {
+    // The value of a bundle is sized for the most lovelace, whatever it will hold.
+    let mut probe: ExtOutput<(), (String, String)> = ExtOutput {
+        value: u64::MAX,
+        assets: BTreeMap::new(),
+        data: None,
+    };
+    let mut bundles = Vec::new();
+
+    for (key, quantity) in assets.iter().filter(|(_, q)| **q > 0) {
+        probe.assets.insert(key.clone(), *quantity);
+
+        if value_size(&probe) > max_value_size {
+            probe.assets.remove(key);
+
+            if probe.assets.is_empty() {
+                return None;
+            }
+
+            bundles.push(std::mem::take(&mut probe.assets));
+            probe.assets.insert(key.clone(), *quantity);
+
+            if value_size(&probe) > max_value_size {
+                return None;
+            }
+        }
+    }
+
+    if !probe.assets.is_empty() || bundles.is_empty() {
+        bundles.push(probe.assets);
+    }
+
+    Some(bundles)
+}
+
+/// The lovelace the output must hold at least to be split, `None` if it cannot be.
+pub(crate) fn min_ada_of_bundles<D>(
+    output: &ExtOutput<D, (String, String)>,
+    max_value_size: u64,
+    coins_per_utxo_byte: u64,
+) -> Option<u64> {
+    bundles(&output.assets, max_value_size)?
+        .into_iter()
+        .map(|assets| {
+            let bundle: ExtOutput<(), (String, String)> = ExtOutput {
+                value: 0,
+                assets,
+                data: None,
+            };
+            min_ada(&bundle, coins_per_utxo_byte)
+        })
+        .try_fold(0u64, |total, min| total.checked_add(min))
+}
+
+/**
+Splits the output into outputs whose values are at most `max_value_size` bytes.
+
+Each output holds the minimum lovelace of its assets, and the first the rest of the lovelace.
+An output that fits is returned as one output of its lovelace and assets, whatever its lovelace.
+Returns `None` if an asset alone is too large, or the lovelace is not enough for the minimums.
+*/
+pub(crate) fn split_bundle<D>(
+    output: &ExtOutput<D, (String, String)>,
+    max_value_size: u64,
+    coins_per_utxo_byte: u64,
+) -> Option<Vec<ExtOutput<D, (String, String)>>> {
+    let bundles = bundles(&output.assets, max_value_size)?;
+
+    if let [assets] = &bundles[..] {
+        return Some(vec![ExtOutput {
+            value: output.value,
+            assets: assets.clone(),
+            data: None,
+        }]);
+    }
+
+    let mut outputs: Vec<ExtOutput<D, (String, String)>> = bundles
+        .into_iter()
+        .map(|assets| {
+            let mut output = ExtOutput {
+                value: 0,
+                assets,
+                data: None,
+            };
+            output.value = min_ada(&output, coins_per_utxo_byte);
+            output
+        })
+        .collect();
+
+    let total = outputs
+        .iter()
+        .try_fold(0u64, |total, output| total.checked_add(output.value))?;
+    outputs[0].value += output.value.checked_sub(total)?;
+
+    Some(outputs)
+}
+
+/**
+The change of the selection, split into outputs whose values are at most `max_value_size` bytes.
+
+The change is what the excess holds beyond the threshold, its minimum lovelace included,
+and none if it is donated to the fee. Change that cannot be split is one output.
+*/
+pub(crate) fn split_change<'o>(
+    thresholded: &Thresholded<'o>,
+    params: &ProtocolParameters,
+) -> Vec<Output<'o>> {
+    let change = utxo::Select::saturating_sub(&thresholded.selection.excess, &thresholded.base);
+
+    match thresholded.donated {
+        0 if change.value > 0 || !change.assets.is_empty() => {
+            split_bundle(&change, params.max_value_size, params.coins_per_utxo_byte)
+                .unwrap_or_else(|| vec![change])
+        }
+        _ => Vec::new(),
+    }
+}
+
+/**
+Split an oversized token bundle into valid outputs
+
+Splits an output whose value is larger than `maxValueSize` bytes into outputs of at most that,
+each holding the minimum lovelace of its assets by `coinsPerUtxoByte`, and the first the rest
+of the lovelace, e.g. for airdrops and migrations. An output that fits is returned as it is,
+even one of less lovelace than its minimum.
+Any address of the output is copied to the outputs.
+
+Raises errors when the types used are wrong, an asset alone is too large,
+or the lovelace is not enough for the minimum of every output.
+*/
+#[wasm_bindgen(js_name = splitBundle)]
+pub fn split_bundle_of(
+    output: &JsOutput,
+    max_value_size: u32,
+    #[wasm_bindgen(unchecked_param_type = "bigint")] coins_per_utxo_byte: &JsValue,
+) -> Result<JsOutputArray, JsError> {
+    let source: Output = output.try_into()?;
+    let coins_per_utxo_byte = to_amount(coins_per_utxo_byte)?;
+    let outputs =
+        split_bundle(&source, max_value_size.into(), coins_per_utxo_byte).ok_or_else(|| {
+            raise(
+                ErrorKind::Unsplittable,
+                "Output cannot be split within maxValueSize",
+            )
+        })?;
+    let address = output.address();
+    let result = Array::new();
+
+    for output in outputs {
+        let output: JsOutput = output.into();
+
+        if let Some(address) = address.as_deref() {
+            output.set_address(address);
+        }
+
+        result.push(&output);
+    }
+
+    Ok(result.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bundle::{min_ada_of_bundles, split_bundle, split_change},
+        min_ada::{min_ada, value_size},
+        options::{MinChange, SelectOptions},
+        protocol::PRESET,
+        selection::{select_with_threshold, Threshold},
+        Output,
+    };
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_split_bundle() {
+        let mut output: ExtOutput<(), (String, String)> = ExtOutput {
+            value: 100_000_000,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        for policy in 0..20u8 {
+            let policy_id = format!("{:02x}", policy).repeat(28);
+            for name in 0..10u8 {
+                let asset_name = format!("{:02x}", name).repeat(16);
+                output.insert_asset((policy_id.clone(), asset_name), 1_000_000);
+            }
+        }
+        assert!(value_size(&output) > 5000);
+
+        let outputs = split_bundle(&output, 5000, 4310).unwrap();
+        assert!(outputs.len() > 1);
+        assert!(outputs.iter().all(|o| value_size(o) <= 5000));
+        assert!(outputs.iter().all(|o| o.value >= min_ada(o, 4310)));
+        assert_eq!(outputs.iter().map(|o| o.value).sum::<u64>(), output.value);
+        assert_eq!(
+            outputs.iter().map(|o| o.assets.len()).sum::<usize>(),
+            output.assets.len()
+        );
+        let min = min_ada_of_bundles(&output, 5000, 4310).unwrap();
+        assert_eq!(min, outputs.iter().map(|o| min_ada(o, 4310)).sum::<u64>());
+
+        // Too little lovelace, or an asset alone too large.
+        output.value = min - 1;
+        assert!(split_bundle(&output, 5000, 4310).is_none());
+        assert!(split_bundle(&output, 40, 4310).is_none());
+
+        // An output that fits is kept as it is, even below its minimum.
+        let key = output.assets.keys().next().unwrap().clone();
+        output.assets.retain(|k, _| *k == key);
+        output.value = 1000;
+        let outputs = split_bundle(&output, 5000, 4310).unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].value, 1000);
+        assert_eq!(outputs[0].assets, output.assets);
+
+        output.assets.clear();
+        for value in [5_000_000, 1000, 0] {
+            output.value = value;
+            let outputs = split_bundle(&output, 5000, 4310).unwrap();
+            assert_eq!(outputs.len(), 1);
+            assert_eq!(outputs[0].value, value);
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_split_change() {
+        let lovelace = |value| Output {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        let options = SelectOptions {
+            min_change: Some(MinChange::Lovelace(1_000_000)),
+            ..SelectOptions::default()
+        };
+        let select = |inputs: &[u64]| {
+            select_with_threshold(
+                inputs.iter().map(|value| lovelace(*value)).collect(),
+                &lovelace(10_000_000),
+                &Threshold::Output(lovelace(200_000)),
+                &options,
+            )
+            .unwrap()
+        };
+
+        // The excess covers the minimum of the change, which is part of the change.
+        let thresholded = select(&[10_500_000, 700_000]);
+        assert_eq!(thresholded.selection.excess.value, 1_200_000);
+        let change = split_change(&thresholded, &PRESET);
+        assert_eq!(change.len(), 1);
+        assert_eq!(change[0].value, 1_000_000);
+
+        assert!(split_change(&select(&[10_200_000]), &PRESET).is_empty());
+    }
+}
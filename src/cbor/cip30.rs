@@ -0,0 +1,451 @@
+use crate::{
+    cbor::{from_hex, output::output, to_error, to_hex, Error, Reader, MAJOR_ARRAY},
+    cli::StringArray,
+    iterate,
+    out_point::OutPoint,
+    raise, ErrorKind, JsOutput, JsOutputArray, Output,
+};
+use js_sys::Array;
+use utxo::ExtOutput;
+use wasm_bindgen::{prelude::*, JsCast};
+
+/// A UTxO decoded from `TransactionUnspentOutput`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Utxo {
+    pub(crate) out_point: OutPoint,
+    pub(crate) address: Vec<u8>,
+    pub(crate) value: ExtOutput<(), (String, String)>,
+}
+
+/// Reads a `TransactionUnspentOutput`, i.e. `[TransactionInput, TransactionOutput]`.
+pub(crate) fn utxo(reader: &mut Reader) -> Result<Utxo, Error> {
+    let invalid = Error::Invalid("Invalid TransactionUnspentOutput");
+    let length = reader.array()?;
+    reader.next(length, 0)?.then_some(()).ok_or(invalid)?;
+
+    let input = reader.array()?;
+    reader.next(input, 0)?.then_some(()).ok_or(invalid)?;
+    let hash = reader.bytes()?;
+    reader.next(input, 1)?.then_some(()).ok_or(invalid)?;
+    let index = u32::try_from(reader.uint()?).map_err(|_| invalid)?;
+    if reader.next(input, 2)? {
+        return Err(invalid);
+    }
+    let out_point = (hash.len() == 32)
+        .then(|| OutPoint::new(&to_hex(&hash), index))
+        .flatten()
+        .ok_or(invalid)?;
+
+    reader.next(length, 1)?.then_some(()).ok_or(invalid)?;
+    let output = output(reader)?;
+
+    if reader.next(length, 2)? {
+        return Err(invalid);
+    }
+
+    Ok(Utxo {
+        out_point,
+        address: output.address,
+        value: output.value,
+    })
+}
+
+/// Reads exactly one `TransactionUnspentOutput`, returning the error and its offset otherwise.
+pub(crate) fn decode_utxo(bytes: &[u8]) -> Result<Utxo, (Error, usize)> {
+    let mut reader = Reader::new(bytes);
+
+    utxo(&mut reader)
+        .and_then(|utxo| {
+            reader
+                .is_end()
+                .then_some(utxo)
+                .ok_or(Error::Invalid("Trailing bytes after UTxO"))
+        })
+        .map_err(|error| (error, reader.position))
+}
+
+/**
+Parse the UTxOs of a CIP-30 wallet
+
+Decodes the hex of each `TransactionUnspentOutput` of `api.getUtxos()` to an output
+to pass to `select`, whose `address` is in hex and `data` is its `OutPoint`,
+i.e. the transaction ID as `hash` and the `index`.
+Use `Cip30Decoder` instead to decode many UTxOs across event-loop turns.
+
+Raises errors when the types used are wrong, or the hex or the CBOR of a UTxO is invalid,
+caused by `{ message, offset }` in the bytes of that UTxO.
+*/
+#[wasm_bindgen(js_name = parseCip30Utxos)]
+pub fn parse_cip30_utxos(utxos: &StringArray) -> Result<JsOutputArray, JsError> {
+    let result = Array::new();
+
+    for (index, hex) in iterate(utxos)?.iter().enumerate() {
+        let bytes = hex
+            .as_string()
+            .and_then(|hex| from_hex(&hex))
+            .ok_or_else(|| raise(ErrorKind::BadHex, &format!("Invalid hex of UTxO {}", index)))?;
+        let utxo = decode_utxo(&bytes).map_err(|(error, offset)| {
+            to_error(
+                &format!("Invalid CBOR of UTxO {}", index),
+                error,
+                offset as u64,
+            )
+        })?;
+
+        result.push(&JsOutput::from(utxo));
+    }
+
+    Ok(result.unchecked_into())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Nothing is read yet.
+    Start,
+    /// In an array of UTxOs, with the number remaining, `None` for indefinite.
+    Array(Option<u64>),
+    /// In UTxOs not enclosed in an array.
+    Sequence,
+    /// The array of UTxOs ended.
+    End,
+}
+
+/**
+Decodes UTxOs from chunks of CBOR.
+
+Only the bytes of the UTxO being decoded are buffered,
+so the memory is bounded by the chunk size rather than the whole input.
+*/
+#[derive(Clone, Debug)]
+pub(crate) struct StreamDecoder {
+    buffer: Vec<u8>,
+    state: State,
+    /// The bytes decoded and dropped from the buffer.
+    consumed: u64,
+}
+
+/// An error of decoding, at the offset of the bytes of the whole input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct StreamError {
+    pub(crate) error: Error,
+    pub(crate) offset: u64,
+}
+
+impl StreamError {
+    fn to_js(self) -> JsError {
+        to_error("Invalid CBOR of UTxOs", self.error, self.offset)
+    }
+}
+
+impl Default for StreamDecoder {
+    fn default() -> Self {
+        Self {
+            buffer: Vec::new(),
+            state: State::Start,
+            consumed: 0,
+        }
+    }
+}
+
+impl StreamDecoder {
+    /// Reads the next UTxO, `Ok(None)` if there is none to read.
+    fn step(&mut self, reader: &mut Reader) -> Result<Option<Utxo>, Error> {
+        loop {
+            match self.state {
+                State::Start if reader.is_end() => return Ok(None),
+                State::Start => {
+                    // An array of UTxOs starts with three array headers, a UTxO with two,
+                    // unless the array is empty, as a UTxO never is.
+                    let mut probe = reader.clone();
+                    let length = probe.array()?;
+
+                    if !probe.next(length, 0)? {
+                        self.state = State::Array(reader.array()?);
+                        continue;
+                    }
+
+                    probe.array()?;
+
+                    self.state = if probe.peek()? == MAJOR_ARRAY {
+                        State::Array(reader.array()?)
+                    } else {
+                        State::Sequence
+                    };
+                }
+                State::Array(remaining) => {
+                    if !reader.next(remaining, 0)? {
+                        self.state = State::End;
+                        continue;
+                    }
+
+                    let utxo = utxo(reader)?;
+                    self.state = State::Array(remaining.map(|r| r - 1));
+                    return Ok(Some(utxo));
+                }
+                State::Sequence if reader.is_end() => return Ok(None),
+                State::Sequence => return utxo(reader).map(Some),
+                State::End if reader.is_end() => return Ok(None),
+                State::End => return Err(Error::Invalid("Trailing bytes after UTxOs")),
+            }
+        }
+    }
+
+    /// Decodes the chunk, returning the UTxOs it completes.
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Result<Vec<Utxo>, StreamError> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.extend_from_slice(chunk);
+
+        let mut reader = Reader::new(&buffer);
+        let mut utxos = Vec::new();
+
+        loop {
+            let (position, state) = (reader.position, self.state);
+
+            match self.step(&mut reader) {
+                Ok(Some(utxo)) => utxos.push(utxo),
+                Ok(None) => break,
+                Err(Error::Incomplete) => {
+                    reader.position = position;
+                    self.state = state;
+                    break;
+                }
+                Err(error) => {
+                    return Err(StreamError {
+                        error,
+                        offset: self.consumed + reader.position as u64,
+                    })
+                }
+            }
+        }
+
+        let consumed = reader.position;
+        buffer.drain(..consumed);
+        self.buffer = buffer;
+        self.consumed += consumed as u64;
+
+        Ok(utxos)
+    }
+
+    /// Checks that the input ended with a complete UTxO.
+    pub(crate) fn finish(&self) -> Result<(), StreamError> {
+        match self.state {
+            State::Array(_) => Err(Error::Incomplete),
+            _ if !self.buffer.is_empty() => Err(Error::Incomplete),
+            _ => Ok(()),
+        }
+        .map_err(|error| StreamError {
+            error,
+            offset: self.consumed + self.buffer.len() as u64,
+        })
+    }
+}
+
+impl From<Utxo> for JsOutput {
+    fn from(utxo: Utxo) -> Self {
+        let output: Output = ExtOutput {
+            value: utxo.value.value,
+            assets: utxo.value.assets,
+            data: None,
+        };
+        let output: JsOutput = output.into();
+
+        output.set_address(&to_hex(&utxo.address));
+        output.set_data(&utxo.out_point.to_js());
+        output
+    }
+}
+
+/**
+Decoder of the UTxOs from CIP-30 wallets, chunk by chunk
+
+The input is the CBOR of `TransactionUnspentOutput`s, either one after another
+or in an array. Each chunk pushed returns the UTxOs it completes,
+so a large input can be decoded across event-loop turns without holding all of it.
+The `address` of each UTxO is in hex, and its `data` is its `OutPoint`.
+*/
+#[wasm_bindgen]
+pub struct Cip30Decoder {
+    decoder: StreamDecoder,
+    nibble: Option<u8>,
+}
+
+impl Default for Cip30Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Cip30Decoder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            decoder: StreamDecoder::default(),
+            nibble: None,
+        }
+    }
+
+    fn decode(&mut self, chunk: &[u8]) -> Result<JsOutputArray, JsError> {
+        let utxos = self.decoder.push(chunk).map_err(StreamError::to_js)?;
+
+        Ok(utxos
+            .into_iter()
+            .map(JsOutput::from)
+            .collect::<Array>()
+            .unchecked_into())
+    }
+
+    /**
+    Decode a chunk of bytes
+
+    Raises errors when the CBOR is invalid, caused by `{ message, offset }`
+    where the offset is of the byte in the whole input.
+    */
+    pub fn push(&mut self, chunk: &[u8]) -> Result<JsOutputArray, JsError> {
+        self.decode(chunk)
+    }
+
+    /**
+    Decode a chunk of hex, which may end in the middle of a byte
+
+    Raises errors when the hex or the CBOR is invalid.
+    */
+    #[wasm_bindgen(js_name = pushHex)]
+    pub fn push_hex(&mut self, chunk: &str) -> Result<JsOutputArray, JsError> {
+        let mut bytes = Vec::with_capacity(chunk.len() / 2 + 1);
+
+        for c in chunk.chars() {
+            let digit =
+                c.to_digit(16)
+                    .ok_or_else(|| raise(ErrorKind::BadHex, "Invalid hex"))? as u8;
+
+            match self.nibble.take() {
+                Some(high) => bytes.push(high << 4 | digit),
+                None => self.nibble = Some(digit),
+            }
+        }
+
+        self.decode(&bytes)
+    }
+
+    /**
+    Finish decoding
+
+    Raises errors when the input ended in the middle of a UTxO or an array.
+    */
+    pub fn finish(&self) -> Result<(), JsError> {
+        if self.nibble.is_some() {
+            return Err(raise(ErrorKind::BadHex, "Invalid hex"));
+        }
+
+        self.decoder.finish().map_err(StreamError::to_js)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cbor::{
+        cip30::{decode_utxo, StreamDecoder, StreamError},
+        Error,
+    };
+    use wasm_bindgen_test::*;
+
+    fn utxo_bytes(index: u8, legacy: bool) -> Vec<u8> {
+        let mut bytes = vec![0x82, 0x82, 0x58, 0x20];
+        bytes.extend([0xaa; 32]);
+        bytes.push(index);
+
+        if legacy {
+            // [h'01', 2000000]
+            bytes.extend([0x82, 0x41, 0x01, 0x1a, 0x00, 0x1e, 0x84, 0x80]);
+        } else {
+            // {0: h'01', 1: [1500000, {h'bb': {h'cc': 7}}], 2: [0, h'dd']}
+            bytes.extend([
+                0xa3, 0x00, 0x41, 0x01, 0x01, 0x82, 0x1a, 0x00, 0x16, 0xe3, 0x60,
+            ]);
+            bytes.extend([0xa1, 0x41, 0xbb, 0xa1, 0x41, 0xcc, 0x07]);
+            bytes.extend([0x02, 0x82, 0x00, 0x41, 0xdd]);
+        }
+
+        bytes
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_stream_decoder() {
+        let mut array = vec![0x9f];
+        array.extend(utxo_bytes(0, true));
+        array.extend(utxo_bytes(1, false));
+        array.push(0xff);
+
+        let mut decoder = StreamDecoder::default();
+        let mut utxos = Vec::new();
+        for byte in array.iter() {
+            utxos.extend(decoder.push(&[*byte]).unwrap());
+            assert!(decoder.buffer.len() < 60);
+        }
+        assert_eq!(decoder.finish(), Ok(()));
+
+        assert_eq!(utxos.len(), 2);
+        assert_eq!(utxos[0].out_point.hash, "aa".repeat(32));
+        assert_eq!(utxos[0].address, vec![0x01]);
+        assert_eq!(utxos[0].value.value, 2_000_000);
+        assert_eq!(utxos[1].out_point.index, 1);
+        assert_eq!(utxos[1].value.value, 1_500_000);
+        assert_eq!(
+            utxos[1].value.assets.get(&("bb".into(), "cc".into())),
+            Some(&7)
+        );
+
+        let mut sequence = utxo_bytes(0, false);
+        sequence.extend(utxo_bytes(2, true));
+        let mut decoder = StreamDecoder::default();
+        let utxos = decoder.push(&sequence).unwrap();
+        assert_eq!(utxos.len(), 2);
+        assert_eq!(utxos[1].out_point.index, 2);
+
+        let mut decoder = StreamDecoder::default();
+        assert_eq!(decoder.push(&array[..50]).unwrap().len(), 1);
+        assert_eq!(
+            decoder.finish(),
+            Err(StreamError {
+                error: Error::Incomplete,
+                offset: 50
+            })
+        );
+
+        // An empty wallet, of either encoding.
+        for empty in [&[0x80][..], &[0x9f, 0xff]] {
+            let mut decoder = StreamDecoder::default();
+            for byte in empty {
+                assert!(decoder.push(&[*byte]).unwrap().is_empty());
+            }
+            assert_eq!(decoder.finish(), Ok(()));
+        }
+
+        let mut decoder = StreamDecoder::default();
+        assert_eq!(decoder.push(&utxo_bytes(0, true)).unwrap().len(), 1);
+        assert_eq!(
+            decoder.push(&[0x81, 0x82, 0x01]),
+            Err(StreamError {
+                error: Error::Invalid("Expected CBOR bytes"),
+                offset: 48
+            })
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_decode_utxo() {
+        let utxo = decode_utxo(&utxo_bytes(3, false)).unwrap();
+        assert_eq!(utxo.out_point.hash, "aa".repeat(32));
+        assert_eq!(utxo.out_point.index, 3);
+        assert_eq!(utxo.value.value, 1_500_000);
+
+        let mut bytes = utxo_bytes(0, true);
+        bytes.push(0x00);
+        assert_eq!(
+            decode_utxo(&bytes),
+            Err((Error::Invalid("Trailing bytes after UTxO"), 45))
+        );
+        assert_eq!(decode_utxo(&bytes[..40]), Err((Error::Incomplete, 40)));
+    }
+}
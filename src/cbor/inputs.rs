@@ -0,0 +1,84 @@
+use crate::{
+    cbor::{from_hex, to_hex, Writer},
+    collect_js_outputs, get_field,
+    out_point::OutPoint,
+    raise, ErrorKind, SelectResult,
+};
+use std::collections::BTreeSet;
+use wasm_bindgen::{prelude::*, JsCast};
+
+/**
+Writes the inputs as an array of `TransactionInput`, i.e. `[hash, index]`,
+in the order of the ledger: by the bytes of the hash, then the index.
+*/
+pub(crate) fn write_inputs(writer: &mut Writer, inputs: &BTreeSet<OutPoint>) {
+    writer.array(inputs.len());
+
+    for input in inputs {
+        writer.array(2);
+        writer.bytes(&from_hex(&input.hash).expect("Unreachable"));
+        writer.uint(input.index.into());
+    }
+}
+
+/**
+Encode the selected inputs in CBOR
+
+Returns the CBOR hex of the array of `TransactionInput` of the selected UTxOs,
+sorted as the ledger orders them, to splice into a transaction body as its inputs.
+
+Raises errors when the types used are wrong, or any selected UTxO has no `OutPoint` as `data`.
+*/
+#[wasm_bindgen(js_name = selectedInputsToCbor)]
+pub fn selected_inputs_to_cbor(result: &SelectResult) -> Result<String, JsError> {
+    let selected = collect_js_outputs(get_field(result, "selected")?.unchecked_ref())?;
+    let inputs = selected
+        .iter()
+        .map(OutPoint::of)
+        .collect::<Option<BTreeSet<OutPoint>>>()
+        .ok_or_else(|| {
+            raise(
+                ErrorKind::MissingOutPoint,
+                "Selected UTxOs should have their OutPoint as data",
+            )
+        })?;
+    let mut writer = Writer::default();
+
+    write_inputs(&mut writer, &inputs);
+
+    Ok(to_hex(&writer.bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cbor::{inputs::write_inputs, to_hex, Writer},
+        out_point::OutPoint,
+    };
+    use std::collections::BTreeSet;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_write_inputs() {
+        let inputs: BTreeSet<OutPoint> = [
+            OutPoint::new(&"bb".repeat(32), 0).unwrap(),
+            OutPoint::new(&"AA".repeat(32), 24).unwrap(),
+            OutPoint::new(&"aa".repeat(32), 1).unwrap(),
+        ]
+        .into_iter()
+        .collect();
+        let mut writer = Writer::default();
+        write_inputs(&mut writer, &inputs);
+
+        // By the hash, then the index, the same hash in either case.
+        assert_eq!(
+            to_hex(&writer.bytes),
+            format!(
+                "83825820{}01825820{}1818825820{}00",
+                "aa".repeat(32),
+                "aa".repeat(32),
+                "bb".repeat(32)
+            )
+        );
+    }
+}
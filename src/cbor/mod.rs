@@ -0,0 +1,365 @@
+/*!
+CBOR of Cardano
+
+A minimal reader and writer of the CBOR that Cardano serializes its ledger types into,
+enough to exchange UTxOs and values with wallets and serialization libraries without depending on one.
+*/
+mod cip30;
+mod inputs;
+mod output;
+mod result;
+mod value;
+
+use crate::{error_with_cause, with_detail, ErrorKind};
+use js_sys::{Object, Reflect};
+use wasm_bindgen::JsError;
+
+pub use cip30::{parse_cip30_utxos, Cip30Decoder};
+pub use inputs::selected_inputs_to_cbor;
+pub use output::{output_from_cbor, output_to_cbor};
+pub use result::{select_result_from_cbor, select_result_to_cbor};
+pub use value::{from_csl_value_hex, to_csl_value_hex};
+
+pub(crate) const MAJOR_UINT: u8 = 0;
+pub(crate) const MAJOR_BYTES: u8 = 2;
+pub(crate) const MAJOR_TEXT: u8 = 3;
+pub(crate) const MAJOR_ARRAY: u8 = 4;
+pub(crate) const MAJOR_MAP: u8 = 5;
+pub(crate) const MAJOR_TAG: u8 = 6;
+
+/// The byte ending indefinite strings and collections.
+const BREAK: u8 = 0xff;
+
+/// The maximum nesting of items to skip, deeper items are rejected.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Error {
+    /// More bytes are needed to read the item.
+    Incomplete,
+    /// The bytes are not the CBOR expected.
+    Invalid(&'static str),
+}
+
+impl Error {
+    pub(crate) fn message(&self) -> &'static str {
+        match self {
+            Self::Incomplete => "Incomplete CBOR",
+            Self::Invalid(message) => message,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Reader<'b> {
+    bytes: &'b [u8],
+    pub(crate) position: usize,
+}
+
+impl<'b> Reader<'b> {
+    pub(crate) fn new(bytes: &'b [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    pub(crate) fn is_end(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn take(&mut self, length: u64) -> Result<&'b [u8], Error> {
+        let length = usize::try_from(length).map_err(|_| Error::Invalid("CBOR too long"))?;
+        let end = self
+            .position
+            .checked_add(length)
+            .ok_or(Error::Invalid("CBOR too long"))?;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(Error::Incomplete)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// The major type of the next item, without reading it.
+    pub(crate) fn peek(&self) -> Result<u8, Error> {
+        self.bytes
+            .get(self.position)
+            .map(|b| b >> 5)
+            .ok_or(Error::Incomplete)
+    }
+
+    /// Reads the major type and the argument of an item, `None` for indefinite length.
+    pub(crate) fn header(&mut self) -> Result<(u8, Option<u64>), Error> {
+        let initial = self.byte()?;
+        let major = initial >> 5;
+        let argument = match initial & 0x1f {
+            info @ 0..=23 => Some(info.into()),
+            24 => Some(self.byte()?.into()),
+            25 => Some(u16::from_be_bytes(self.take(2)?.try_into().expect("Unreachable")).into()),
+            26 => Some(u32::from_be_bytes(self.take(4)?.try_into().expect("Unreachable")).into()),
+            27 => Some(u64::from_be_bytes(
+                self.take(8)?.try_into().expect("Unreachable"),
+            )),
+            31 if matches!(major, MAJOR_BYTES..=MAJOR_MAP) => None,
+            _ => return Err(Error::Invalid("Invalid CBOR header")),
+        };
+
+        Ok((major, argument))
+    }
+
+    fn expect(&mut self, major: u8, message: &'static str) -> Result<Option<u64>, Error> {
+        match self.header()? {
+            (m, argument) if m == major => Ok(argument),
+            _ => Err(Error::Invalid(message)),
+        }
+    }
+
+    pub(crate) fn uint(&mut self) -> Result<u64, Error> {
+        self.expect(MAJOR_UINT, "Expected CBOR unsigned integer")?
+            .ok_or(Error::Invalid("Expected CBOR unsigned integer"))
+    }
+
+    pub(crate) fn bytes(&mut self) -> Result<Vec<u8>, Error> {
+        match self.expect(MAJOR_BYTES, "Expected CBOR bytes")? {
+            Some(length) => Ok(self.take(length)?.to_vec()),
+            None => {
+                let mut bytes = Vec::new();
+
+                while self.next(None, 0)? {
+                    let length = self
+                        .expect(MAJOR_BYTES, "Expected CBOR bytes")?
+                        .ok_or(Error::Invalid("Nested indefinite CBOR bytes"))?;
+                    bytes.extend_from_slice(self.take(length)?);
+                }
+
+                Ok(bytes)
+            }
+        }
+    }
+
+    pub(crate) fn text(&mut self) -> Result<String, Error> {
+        let length = self
+            .expect(MAJOR_TEXT, "Expected CBOR text")?
+            .ok_or(Error::Invalid("Indefinite CBOR text"))?;
+
+        String::from_utf8(self.take(length)?.to_vec()).map_err(|_| Error::Invalid("Invalid UTF-8"))
+    }
+
+    /// Reads a tag, returning its number.
+    pub(crate) fn tag(&mut self) -> Result<u64, Error> {
+        self.expect(MAJOR_TAG, "Expected CBOR tag")?
+            .ok_or(Error::Invalid("Expected CBOR tag"))
+    }
+
+    /// Reads the header of an array, returning its length, `None` for indefinite.
+    pub(crate) fn array(&mut self) -> Result<Option<u64>, Error> {
+        self.expect(MAJOR_ARRAY, "Expected CBOR array")
+    }
+
+    /// Reads the header of a map, returning its number of entries, `None` for indefinite.
+    pub(crate) fn map(&mut self) -> Result<Option<u64>, Error> {
+        self.expect(MAJOR_MAP, "Expected CBOR map")
+    }
+
+    /**
+    Whether there is an item at the index of a collection of the length.
+
+    The break of an indefinite collection is read when it ends.
+    */
+    pub(crate) fn next(&mut self, length: Option<u64>, index: u64) -> Result<bool, Error> {
+        match length {
+            Some(length) => Ok(index < length),
+            None => {
+                let end = *self.bytes.get(self.position).ok_or(Error::Incomplete)? == BREAK;
+
+                if end {
+                    self.position += 1;
+                }
+
+                Ok(!end)
+            }
+        }
+    }
+
+    /// Skips the next item, whatever it is.
+    pub(crate) fn skip(&mut self) -> Result<(), Error> {
+        self.skip_nested(0)
+    }
+
+    fn skip_nested(&mut self, depth: usize) -> Result<(), Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::Invalid("CBOR nested too deep"));
+        }
+
+        let (major, argument) = self.header()?;
+        let items = match (major, argument) {
+            (MAJOR_BYTES | MAJOR_TEXT, Some(length)) => {
+                self.take(length)?;
+                return Ok(());
+            }
+            (MAJOR_ARRAY | MAJOR_BYTES | MAJOR_TEXT, length) => length,
+            (MAJOR_MAP, Some(length)) => Some(
+                length
+                    .checked_mul(2)
+                    .ok_or(Error::Invalid("CBOR too long"))?,
+            ),
+            (MAJOR_MAP, None) => None,
+            (MAJOR_TAG, _) => Some(1),
+            _ => return Ok(()),
+        };
+
+        let mut index = 0;
+        while self.next(items, index)? {
+            self.skip_nested(depth + 1)?;
+            index += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes CBOR in the canonical form, i.e. the shortest headers of definite lengths.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Writer {
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub(crate) fn header(&mut self, major: u8, argument: u64) {
+        let major = major << 5;
+
+        match argument {
+            0..=23 => self.bytes.push(major | argument as u8),
+            24..=0xff => self.bytes.extend([major | 24, argument as u8]),
+            0x100..=0xffff => {
+                self.bytes.push(major | 25);
+                self.bytes.extend((argument as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                self.bytes.push(major | 26);
+                self.bytes.extend((argument as u32).to_be_bytes());
+            }
+            _ => {
+                self.bytes.push(major | 27);
+                self.bytes.extend(argument.to_be_bytes());
+            }
+        }
+    }
+
+    pub(crate) fn uint(&mut self, value: u64) {
+        self.header(MAJOR_UINT, value);
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) {
+        self.header(MAJOR_BYTES, bytes.len() as u64);
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub(crate) fn text(&mut self, text: &str) {
+        self.header(MAJOR_TEXT, text.len() as u64);
+        self.bytes.extend_from_slice(text.as_bytes());
+    }
+
+    pub(crate) fn array(&mut self, length: usize) {
+        self.header(MAJOR_ARRAY, length as u64);
+    }
+
+    pub(crate) fn map(&mut self, length: usize) {
+        self.header(MAJOR_MAP, length as u64);
+    }
+
+    pub(crate) fn tag(&mut self, number: u64) {
+        self.header(MAJOR_TAG, number);
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decodes hex of either case, `None` if it is not valid hex.
+pub(crate) fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// A JS error of the message, caused by `{ message, offset }` of the CBOR error.
+pub(crate) fn to_error(message: &str, error: Error, offset: u64) -> JsError {
+    let cause = Object::new();
+    Reflect::set(&cause, &"message".into(), &error.message().into()).expect("Unreachable");
+    Reflect::set(&cause, &"offset".into(), &(offset as f64).into()).expect("Unreachable");
+
+    with_detail(
+        error_with_cause(ErrorKind::BadCbor, message, &cause),
+        &cause,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cbor::{from_hex, to_hex, Error, Reader, Writer};
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_reader() {
+        // [1, h'abcd', {_ 0: "a"}, 1000000, [_ 24]]
+        let bytes = [
+            0x85, 0x01, 0x42, 0xab, 0xcd, 0xbf, 0x00, 0x61, 0x61, 0xff, 0x1a, 0x00, 0x0f, 0x42,
+            0x40, 0x9f, 0x18, 0x18, 0xff,
+        ];
+        let mut reader = Reader::new(&bytes);
+
+        assert_eq!(reader.array(), Ok(Some(5)));
+        assert_eq!(reader.uint(), Ok(1));
+        assert_eq!(reader.bytes(), Ok(vec![0xab, 0xcd]));
+        assert_eq!(reader.skip(), Ok(()));
+        assert_eq!(reader.uint(), Ok(1_000_000));
+        assert_eq!(reader.array(), Ok(None));
+        assert_eq!(reader.next(None, 0), Ok(true));
+        assert_eq!(reader.uint(), Ok(24));
+        assert_eq!(reader.next(None, 1), Ok(false));
+        assert!(reader.is_end());
+
+        assert_eq!(Reader::new(&bytes[..12]).skip(), Err(Error::Incomplete));
+        assert_eq!(
+            Reader::new(&[0x1f]).uint(),
+            Err(Error::Invalid("Invalid CBOR header"))
+        );
+        assert_eq!(
+            Reader::new(&[0x81; 100]).skip(),
+            Err(Error::Invalid("CBOR nested too deep"))
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_writer() {
+        let mut writer = Writer::default();
+        writer.array(2);
+        writer.uint(1_000_000);
+        writer.map(1);
+        writer.bytes(&[0xab, 0xcd]);
+        writer.uint(u64::MAX);
+
+        let mut reader = Reader::new(&writer.bytes);
+        assert_eq!(reader.array(), Ok(Some(2)));
+        assert_eq!(reader.uint(), Ok(1_000_000));
+        assert_eq!(reader.map(), Ok(Some(1)));
+        assert_eq!(reader.bytes(), Ok(vec![0xab, 0xcd]));
+        assert_eq!(reader.uint(), Ok(u64::MAX));
+        assert!(reader.is_end());
+
+        assert_eq!(to_hex(&writer.bytes[..5]), "821a000f42");
+        assert_eq!(from_hex("0aBc"), Some(vec![0x0a, 0xbc]));
+        assert_eq!(from_hex("0ab"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{
+    address,
+    cbor::{
+        from_hex, to_error, to_hex,
+        value::{value, write_value},
+        Error, Reader, Writer, MAJOR_ARRAY, MAJOR_MAP,
+    },
+    get_field, raise, ErrorCode, ErrorKind, JsOutput, Output,
+};
+use js_sys::Reflect;
+use utxo::ExtOutput;
+use wasm_bindgen::prelude::*;
+
+/// The tag of CBOR data encoded in bytes.
+const ENCODED_CBOR: u64 = 24;
+
+/// A `TransactionOutput` of Babbage, the datum and script in the bytes of their CBOR.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TxOutput {
+    pub(crate) address: Vec<u8>,
+    pub(crate) value: ExtOutput<(), (String, String)>,
+    pub(crate) datum_hash: Option<Vec<u8>>,
+    pub(crate) datum: Option<Vec<u8>>,
+    pub(crate) script_ref: Option<Vec<u8>>,
+}
+
+/// Reads `#6.24(bytes)`, returning the bytes.
+fn encoded_cbor(reader: &mut Reader) -> Result<Vec<u8>, Error> {
+    if reader.tag()? != ENCODED_CBOR {
+        return Err(Error::Invalid("Expected CBOR in bytes"));
+    }
+
+    reader.bytes()
+}
+
+/// Reads a `TransactionOutput`, either the legacy array or the map of Babbage.
+pub(crate) fn output(reader: &mut Reader) -> Result<TxOutput, Error> {
+    let invalid = Error::Invalid("Invalid TransactionOutput");
+    let mut address = None;
+    let mut amount = None;
+    let mut datum_hash = None;
+    let mut datum = None;
+    let mut script_ref = None;
+
+    match reader.peek()? {
+        MAJOR_ARRAY => {
+            let length = reader.array()?;
+            let mut i = 0;
+            while reader.next(length, i)? {
+                match i {
+                    0 => address = Some(reader.bytes()?),
+                    1 => amount = Some(value(reader)?),
+                    2 => datum_hash = Some(reader.bytes()?),
+                    _ => return Err(invalid),
+                }
+                i += 1;
+            }
+        }
+        MAJOR_MAP => {
+            let length = reader.map()?;
+            let mut i = 0;
+            while reader.next(length, i)? {
+                match reader.uint()? {
+                    0 => address = Some(reader.bytes()?),
+                    1 => amount = Some(value(reader)?),
+                    2 => {
+                        // [0, hash] or [1, #6.24(bytes)]
+                        if reader.array()? != Some(2) {
+                            return Err(invalid);
+                        }
+                        match reader.uint()? {
+                            0 => datum_hash = Some(reader.bytes()?),
+                            1 => datum = Some(encoded_cbor(reader)?),
+                            _ => return Err(invalid),
+                        }
+                    }
+                    3 => script_ref = Some(encoded_cbor(reader)?),
+                    _ => reader.skip()?,
+                }
+                i += 1;
+            }
+        }
+        _ => return Err(invalid),
+    }
+
+    Ok(TxOutput {
+        address: address.ok_or(invalid)?,
+        value: amount.ok_or(invalid)?,
+        datum_hash,
+        datum,
+        script_ref,
+    })
+}
+
+/**
+Writes a `TransactionOutput`, the legacy array unless it has an inline datum or a script reference.
+
+Returns `None` if any policy ID or asset name is not hex.
+*/
+pub(crate) fn write_output(writer: &mut Writer, output: &TxOutput) -> Option<()> {
+    if output.datum.is_none() && output.script_ref.is_none() {
+        writer.array(2 + usize::from(output.datum_hash.is_some()));
+        writer.bytes(&output.address);
+        write_value(writer, &output.value)?;
+
+        if let Some(datum_hash) = &output.datum_hash {
+            writer.bytes(datum_hash);
+        }
+
+        return Some(());
+    }
+
+    let datum_option = output.datum_hash.is_some() || output.datum.is_some();
+    writer.map(2 + usize::from(datum_option) + usize::from(output.script_ref.is_some()));
+    writer.uint(0);
+    writer.bytes(&output.address);
+    writer.uint(1);
+    write_value(writer, &output.value)?;
+
+    if let Some(datum_hash) = &output.datum_hash {
+        writer.uint(2);
+        writer.array(2);
+        writer.uint(0);
+        writer.bytes(datum_hash);
+    } else if let Some(datum) = &output.datum {
+        writer.uint(2);
+        writer.array(2);
+        writer.uint(1);
+        writer.tag(ENCODED_CBOR);
+        writer.bytes(datum);
+    }
+
+    if let Some(script_ref) = &output.script_ref {
+        writer.uint(3);
+        writer.tag(ENCODED_CBOR);
+        writer.bytes(script_ref);
+    }
+
+    Some(())
+}
+
+/// Reads a field of the output in hex, `None` if it is missing.
+fn get_hex(output: &JsOutput, key: &str) -> Result<Option<Vec<u8>>, ErrorCode> {
+    let value = get_field(output, key)?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+
+    value
+        .as_string()
+        .and_then(|hex| from_hex(&hex))
+        .map(Some)
+        .ok_or(ErrorCode::InvalidType)
+}
+
+/**
+Encode an output in the CBOR of a Babbage `TransactionOutput`
+
+The address is the one given, or the `address` of the output, in bech32, base58 or hex.
+The `datumHash`, inline `datum` and `scriptRef` of the output are kept, all in hex of their CBOR.
+The output is encoded as the legacy array unless it has an inline datum or a script reference,
+as serialization libraries do.
+
+Raises errors when the types used are wrong, the address is invalid or missing,
+the output has both a datum hash and an inline datum, or any field is not hex.
+*/
+#[wasm_bindgen(js_name = outputToCbor)]
+pub fn output_to_cbor(output: &JsOutput, address: Option<String>) -> Result<String, JsError> {
+    let address = address
+        .or_else(|| output.address())
+        .ok_or_else(|| raise(ErrorKind::MissingAddress, "Address is missing"))?;
+    let address =
+        address::parse(&address).map_err(|message| raise(ErrorKind::BadAddress, message))?;
+    let datum_hash = get_hex(output, "datumHash")?;
+    let datum = get_hex(output, "datum")?;
+    if datum_hash.is_some() && datum.is_some() {
+        return Err(ErrorCode::InvalidType.into());
+    }
+    let script_ref = get_hex(output, "scriptRef")?;
+    let value: Output = output.try_into()?;
+    let output = TxOutput {
+        address: address.bytes,
+        value: ExtOutput {
+            value: value.value,
+            assets: value.assets,
+            data: None,
+        },
+        datum_hash,
+        datum,
+        script_ref,
+    };
+    let mut writer = Writer::default();
+
+    write_output(&mut writer, &output).ok_or(ErrorCode::InvalidType)?;
+
+    Ok(to_hex(&writer.bytes))
+}
+
+/**
+Decode an output from the CBOR hex of a `TransactionOutput`
+
+Reads both the legacy array and the map of Babbage. The `address` is in hex,
+and any `datumHash`, inline `datum` and `scriptRef` are in hex of their CBOR.
+
+Raises errors when the hex or the CBOR is invalid, caused by `{ message, offset }`.
+*/
+#[wasm_bindgen(js_name = outputFromCbor)]
+pub fn output_from_cbor(hex: &str) -> Result<JsOutput, JsError> {
+    let bytes = from_hex(hex).ok_or_else(|| raise(ErrorKind::BadHex, "Invalid hex"))?;
+    let mut reader = Reader::new(&bytes);
+    let decoded = output(&mut reader)
+        .and_then(|output| {
+            reader
+                .is_end()
+                .then_some(output)
+                .ok_or(Error::Invalid("Trailing bytes after TransactionOutput"))
+        })
+        .map_err(|error| {
+            to_error(
+                "Invalid CBOR of TransactionOutput",
+                error,
+                reader.position as u64,
+            )
+        })?;
+
+    let output: Output = ExtOutput {
+        value: decoded.value.value,
+        assets: decoded.value.assets,
+        data: None,
+    };
+    let output: JsOutput = output.into();
+    output.set_address(&to_hex(&decoded.address));
+
+    for (key, field) in [
+        ("datumHash", &decoded.datum_hash),
+        ("datum", &decoded.datum),
+        ("scriptRef", &decoded.script_ref),
+    ] {
+        if let Some(bytes) = field {
+            Reflect::set(&output, &key.into(), &to_hex(bytes).into()).expect("Unreachable");
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cbor::{
+        output::{output, write_output, TxOutput},
+        to_hex, Reader, Writer,
+    };
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_output_round_trip() {
+        let mut legacy = TxOutput {
+            address: vec![0x61; 29],
+            value: ExtOutput {
+                value: 2_000_000,
+                assets: BTreeMap::new(),
+                data: None,
+            },
+            datum_hash: None,
+            datum: None,
+            script_ref: None,
+        };
+        let round_trip = |tx_output: &TxOutput| {
+            let mut writer = Writer::default();
+            write_output(&mut writer, tx_output).unwrap();
+            let mut reader = Reader::new(&writer.bytes);
+            let decoded = output(&mut reader).unwrap();
+            assert!(reader.is_end());
+            (to_hex(&writer.bytes), decoded)
+        };
+
+        let (hex, decoded) = round_trip(&legacy);
+        assert!(hex.starts_with("82581d61"));
+        assert!(hex.ends_with("1a001e8480"));
+        assert_eq!(decoded, legacy);
+
+        legacy.datum_hash = Some(vec![0xdd; 32]);
+        let (hex, decoded) = round_trip(&legacy);
+        assert!(hex.starts_with("83"));
+        assert_eq!(decoded, legacy);
+
+        let mut babbage = legacy.clone();
+        babbage.datum_hash = None;
+        babbage.datum = Some(vec![0xd8, 0x79, 0x80]);
+        babbage.script_ref = Some(vec![0x82, 0x02, 0x41, 0x00]);
+        babbage
+            .value
+            .insert_asset(("ab".repeat(28), "cafe".into()), 10);
+        let (hex, decoded) = round_trip(&babbage);
+        assert!(hex.starts_with("a4"));
+        assert!(hex.ends_with("03d8184482024100"));
+        assert_eq!(decoded, babbage);
+    }
+}
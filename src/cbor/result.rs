@@ -0,0 +1,363 @@
+/*!
+CBOR of selection results
+
+A result is encoded as the self-described CBOR tag wrapping a map of integer keys:
+
+- `0`: the version of the encoding, `1`.
+- `1`, `2`: the selected and unselected UTxOs.
+- `3`: the value of the excess.
+- `4`: the lovelace donated to the fee, if any.
+
+Each UTxO is a map of its value at `0`, and optionally its `OutPoint` as `[hash, index]`
+at `1` and its address as text at `2`. Other fields of the UTxOs are not kept.
+*/
+use crate::{
+    cbor::{
+        from_hex, to_error, to_hex,
+        value::{value, write_value},
+        Error, Reader, Writer,
+    },
+    collect_js_outputs, get_amount, get_field,
+    options::DustTokens,
+    out_point::OutPoint,
+    to_outputs, to_select_result, ErrorCode, JsOutput, Output, SelectResult,
+};
+use js_sys::Reflect;
+use utxo::ExtOutput;
+use wasm_bindgen::{prelude::*, JsCast};
+
+/// The tag of self-described CBOR.
+const SELF_DESCRIBED: u64 = 55799;
+
+const VERSION: u64 = 1;
+
+type Value = ExtOutput<(), (String, String)>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Utxo {
+    pub(crate) value: Value,
+    pub(crate) out_point: Option<OutPoint>,
+    pub(crate) address: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Encoded {
+    pub(crate) selected: Vec<Utxo>,
+    pub(crate) unselected: Vec<Utxo>,
+    pub(crate) excess: Value,
+    pub(crate) donated: Option<u64>,
+}
+
+/// Writes the result, `None` if any policy ID or asset name is not hex.
+pub(crate) fn write_result(writer: &mut Writer, result: &Encoded) -> Option<()> {
+    writer.tag(SELF_DESCRIBED);
+    writer.map(4 + usize::from(result.donated.is_some()));
+    writer.uint(0);
+    writer.uint(VERSION);
+
+    for (key, utxos) in [(1, &result.selected), (2, &result.unselected)] {
+        writer.uint(key);
+        writer.array(utxos.len());
+
+        for utxo in utxos {
+            let fields =
+                1 + usize::from(utxo.out_point.is_some()) + usize::from(utxo.address.is_some());
+            writer.map(fields);
+            writer.uint(0);
+            write_value(writer, &utxo.value)?;
+
+            if let Some(out_point) = &utxo.out_point {
+                writer.uint(1);
+                writer.array(2);
+                writer.bytes(&from_hex(&out_point.hash)?);
+                writer.uint(out_point.index.into());
+            }
+
+            if let Some(address) = &utxo.address {
+                writer.uint(2);
+                writer.text(address);
+            }
+        }
+    }
+
+    writer.uint(3);
+    write_value(writer, &result.excess)?;
+
+    if let Some(donated) = result.donated {
+        writer.uint(4);
+        writer.uint(donated);
+    }
+
+    Some(())
+}
+
+fn utxo(reader: &mut Reader) -> Result<Utxo, Error> {
+    let invalid = Error::Invalid("Invalid UTxO of selection");
+    let mut result = Utxo {
+        value: Value {
+            value: 0,
+            assets: Default::default(),
+            data: None,
+        },
+        out_point: None,
+        address: None,
+    };
+    let mut has_value = false;
+    let fields = reader.map()?;
+    let mut i = 0;
+
+    while reader.next(fields, i)? {
+        match reader.uint()? {
+            0 => {
+                result.value = value(reader)?;
+                has_value = true;
+            }
+            1 => {
+                let length = reader.array()?;
+                if length != Some(2) {
+                    return Err(invalid);
+                }
+                let hash = to_hex(&reader.bytes()?);
+                let index = u32::try_from(reader.uint()?).map_err(|_| invalid)?;
+                result.out_point = Some(OutPoint::new(&hash, index).ok_or(invalid)?);
+            }
+            2 => result.address = Some(reader.text()?),
+            _ => reader.skip()?,
+        }
+
+        i += 1;
+    }
+
+    has_value.then_some(result).ok_or(invalid)
+}
+
+pub(crate) fn read_result(reader: &mut Reader) -> Result<Encoded, Error> {
+    let invalid = Error::Invalid("Invalid selection result");
+
+    if reader.tag()? != SELF_DESCRIBED {
+        return Err(invalid);
+    }
+
+    let mut version = None;
+    let mut selected = None;
+    let mut unselected = None;
+    let mut excess = None;
+    let mut donated = None;
+    let fields = reader.map()?;
+    let mut i = 0;
+
+    while reader.next(fields, i)? {
+        match reader.uint()? {
+            0 => version = Some(reader.uint()?),
+            key @ (1 | 2) => {
+                let length = reader.array()?;
+                let mut utxos = Vec::new();
+                let mut j = 0;
+
+                while reader.next(length, j)? {
+                    utxos.push(utxo(reader)?);
+                    j += 1;
+                }
+
+                if key == 1 {
+                    selected = Some(utxos);
+                } else {
+                    unselected = Some(utxos);
+                }
+            }
+            3 => excess = Some(value(reader)?),
+            4 => donated = Some(reader.uint()?),
+            _ => reader.skip()?,
+        }
+
+        i += 1;
+    }
+
+    if version != Some(VERSION) {
+        return Err(Error::Invalid("Unsupported version of selection result"));
+    }
+
+    Ok(Encoded {
+        selected: selected.ok_or(invalid)?,
+        unselected: unselected.ok_or(invalid)?,
+        excess: excess.ok_or(invalid)?,
+        donated,
+    })
+}
+
+fn to_utxos(utxos: &JsValue) -> Result<Vec<Utxo>, ErrorCode> {
+    let js_utxos = collect_js_outputs(utxos.unchecked_ref())?;
+    let outputs = to_outputs(&js_utxos)?;
+
+    js_utxos
+        .iter()
+        .zip(outputs)
+        .map(|(js_utxo, output)| {
+            let address = get_field(js_utxo, "address")?;
+
+            Ok(Utxo {
+                value: Value {
+                    value: output.value,
+                    assets: output.assets,
+                    data: None,
+                },
+                out_point: OutPoint::of(js_utxo),
+                address: address.as_string(),
+            })
+        })
+        .collect()
+}
+
+impl From<Utxo> for JsOutput {
+    fn from(utxo: Utxo) -> Self {
+        let output: Output = ExtOutput {
+            value: utxo.value.value,
+            assets: utxo.value.assets,
+            data: None,
+        };
+        let output: JsOutput = output.into();
+
+        if let Some(address) = &utxo.address {
+            output.set_address(address);
+        }
+
+        if let Some(out_point) = &utxo.out_point {
+            output.set_data(&out_point.to_js());
+        }
+
+        output
+    }
+}
+
+/**
+Encode a result of selection in CBOR
+
+The UTxOs keep their values, the `OutPoint`s of their `data` and their addresses,
+so pending selections can be persisted or sent to other services compactly.
+The encoding is self-described and versioned, see `selectResultFromCbor`.
+
+Raises errors when the types used are wrong, or any policy ID or asset name is not hex.
+*/
+#[wasm_bindgen(js_name = selectResultToCbor)]
+pub fn select_result_to_cbor(result: &SelectResult) -> Result<Vec<u8>, JsError> {
+    let excess = get_field(result, "excess")?;
+    let excess: Output = excess.unchecked_ref::<JsOutput>().try_into()?;
+    let encoded = Encoded {
+        selected: to_utxos(&get_field(result, "selected")?)?,
+        unselected: to_utxos(&get_field(result, "unselected")?)?,
+        excess: Value {
+            value: excess.value,
+            assets: excess.assets,
+            data: None,
+        },
+        donated: get_amount(result, "donated")?,
+    };
+    let mut writer = Writer::default();
+
+    write_result(&mut writer, &encoded).ok_or(ErrorCode::InvalidType)?;
+
+    Ok(writer.bytes)
+}
+
+/**
+Decode a result of selection from the CBOR of `selectResultToCbor`
+
+The `data` of each UTxO is its `OutPoint` if it had one,
+and `dustTokens` is found again with the default thresholds.
+
+Raises errors when the CBOR is invalid, caused by `{ message, offset }`.
+*/
+#[wasm_bindgen(js_name = selectResultFromCbor)]
+pub fn select_result_from_cbor(bytes: &[u8]) -> Result<SelectResult, JsError> {
+    let mut reader = Reader::new(bytes);
+    let encoded = read_result(&mut reader)
+        .and_then(|encoded| {
+            reader
+                .is_end()
+                .then_some(encoded)
+                .ok_or(Error::Invalid("Trailing bytes after selection result"))
+        })
+        .map_err(|error| {
+            to_error(
+                "Invalid CBOR of selection result",
+                error,
+                reader.position as u64,
+            )
+        })?;
+
+    let js_selected: Vec<JsOutput> = encoded.selected.into_iter().map(JsOutput::from).collect();
+    let js_unselected: Vec<JsOutput> = encoded.unselected.into_iter().map(JsOutput::from).collect();
+    let excess: Output = ExtOutput {
+        value: encoded.excess.value,
+        assets: encoded.excess.assets,
+        data: None,
+    };
+    let result = to_select_result(
+        &to_outputs(&js_selected)?,
+        &to_outputs(&js_unselected)?,
+        excess,
+        &DustTokens::default(),
+    );
+
+    if let Some(donated) = encoded.donated {
+        Reflect::set(&result, &"donated".into(), &donated.into()).expect("Unreachable");
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cbor::{
+            result::{read_result, write_result, Encoded, Utxo},
+            Error, Reader, Writer,
+        },
+        out_point::OutPoint,
+    };
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_result_round_trip() {
+        let value = |value| ExtOutput {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        let mut token = value(2_000_000);
+        token.insert_asset(("ab".repeat(28), "cafe".into()), 10);
+
+        let result = Encoded {
+            selected: vec![Utxo {
+                value: token.clone(),
+                out_point: OutPoint::new(&"0f".repeat(32), 1),
+                address: Some("addr_test1".into()),
+            }],
+            unselected: vec![Utxo {
+                value: value(1_000_000),
+                out_point: None,
+                address: None,
+            }],
+            excess: token,
+            donated: Some(120),
+        };
+
+        let mut writer = Writer::default();
+        write_result(&mut writer, &result).unwrap();
+        assert_eq!(&writer.bytes[..3], [0xd9, 0xd9, 0xf7]);
+
+        let mut reader = Reader::new(&writer.bytes);
+        assert_eq!(read_result(&mut reader), Ok(result));
+        assert!(reader.is_end());
+
+        // The version is 2.
+        let mut bytes = writer.bytes.clone();
+        bytes[5] = 0x02;
+        assert_eq!(
+            read_result(&mut Reader::new(&bytes)),
+            Err(Error::Invalid("Unsupported version of selection result"))
+        );
+    }
+}
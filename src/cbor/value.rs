@@ -0,0 +1,184 @@
+use crate::{
+    cbor::{from_hex, to_error, to_hex, Error, Reader, Writer, MAJOR_UINT},
+    raise, ErrorCode, ErrorKind, JsOutput, Output,
+};
+use std::collections::BTreeMap;
+use utxo::ExtOutput;
+use wasm_bindgen::prelude::*;
+
+/// Reads a `Value`, i.e. `coin / [coin, multiasset<uint>]`.
+pub(crate) fn value(reader: &mut Reader) -> Result<ExtOutput<(), (String, String)>, Error> {
+    let mut value = ExtOutput {
+        value: 0,
+        assets: BTreeMap::new(),
+        data: None,
+    };
+
+    if reader.peek()? == MAJOR_UINT {
+        value.value = reader.uint()?;
+        return Ok(value);
+    }
+
+    let length = reader.array()?;
+    reader
+        .next(length, 0)?
+        .then_some(())
+        .ok_or(Error::Invalid("Invalid value"))?;
+    value.value = reader.uint()?;
+    reader
+        .next(length, 1)?
+        .then_some(())
+        .ok_or(Error::Invalid("Invalid value"))?;
+
+    let policies = reader.map()?;
+    let mut i = 0;
+    while reader.next(policies, i)? {
+        let policy_id = to_hex(&reader.bytes()?);
+        let assets = reader.map()?;
+        let mut j = 0;
+
+        while reader.next(assets, j)? {
+            let asset_name = to_hex(&reader.bytes()?);
+            value.insert_asset((policy_id.clone(), asset_name), reader.uint()?);
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    if reader.next(length, 2)? {
+        return Err(Error::Invalid("Invalid value"));
+    }
+
+    Ok(value)
+}
+
+/**
+Writes a `Value` in the order of the canonical CBOR as cardano-serialization-lib does:
+policy IDs by their bytes, and asset names by their lengths then bytes.
+
+Returns `None` if any policy ID or asset name is not hex.
+*/
+pub(crate) fn write_value<D>(
+    writer: &mut Writer,
+    value: &ExtOutput<D, (String, String)>,
+) -> Option<()> {
+    if value.assets.is_empty() {
+        writer.uint(value.value);
+        return Some(());
+    }
+
+    let mut policies: BTreeMap<Vec<u8>, Vec<(Vec<u8>, u64)>> = BTreeMap::new();
+
+    for ((policy_id, asset_name), quantity) in value.assets.iter() {
+        policies
+            .entry(from_hex(policy_id)?)
+            .or_default()
+            .push((from_hex(asset_name)?, *quantity));
+    }
+
+    writer.array(2);
+    writer.uint(value.value);
+    writer.map(policies.len());
+
+    for (policy_id, mut assets) in policies {
+        assets.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+
+        writer.bytes(&policy_id);
+        writer.map(assets.len());
+
+        for (asset_name, quantity) in assets {
+            writer.bytes(&asset_name);
+            writer.uint(quantity);
+        }
+    }
+
+    Some(())
+}
+
+/**
+Convert the value of an output to the CBOR hex of a CSL `Value`
+
+Raises errors when the types used are wrong, or any policy ID or asset name is not hex.
+*/
+#[wasm_bindgen(js_name = toCslValueHex)]
+pub fn to_csl_value_hex(output: &JsOutput) -> Result<String, JsError> {
+    let output: Output = output.try_into()?;
+    let mut writer = Writer::default();
+
+    write_value(&mut writer, &output).ok_or(ErrorCode::InvalidType)?;
+
+    Ok(to_hex(&writer.bytes))
+}
+
+/**
+Convert the CBOR hex of a CSL `Value` to an output
+
+The policy IDs and asset names are in lower case hex.
+
+Raises errors when the hex or the CBOR is invalid, caused by `{ message, offset }`.
+*/
+#[wasm_bindgen(js_name = fromCslValueHex)]
+pub fn from_csl_value_hex(hex: &str) -> Result<JsOutput, JsError> {
+    let bytes = from_hex(hex).ok_or_else(|| raise(ErrorKind::BadHex, "Invalid hex"))?;
+    let mut reader = Reader::new(&bytes);
+    let value = value(&mut reader)
+        .and_then(|value| {
+            reader
+                .is_end()
+                .then_some(value)
+                .ok_or(Error::Invalid("Trailing bytes after value"))
+        })
+        .map_err(|error| to_error("Invalid CBOR of Value", error, reader.position as u64))?;
+
+    let output: Output = ExtOutput {
+        value: value.value,
+        assets: value.assets,
+        data: None,
+    };
+
+    Ok(output.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cbor::{
+        from_hex, to_hex,
+        value::{value, write_value},
+        Reader, Writer,
+    };
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_value_round_trip() {
+        let mut output: ExtOutput<(), (String, String)> = ExtOutput {
+            value: 2_000_000,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+
+        let mut writer = Writer::default();
+        write_value(&mut writer, &output).unwrap();
+        assert_eq!(to_hex(&writer.bytes), "1a001e8480");
+
+        let policy_id = "ab".repeat(28);
+        output.insert_asset((policy_id.clone(), "ffff".into()), 1);
+        output.insert_asset((policy_id.clone(), "00ff00".into()), 2);
+        output.insert_asset((policy_id.clone(), "ee".into()), 3);
+
+        let mut writer = Writer::default();
+        write_value(&mut writer, &output).unwrap();
+        let hex = to_hex(&writer.bytes);
+
+        // The shorter asset names come first.
+        assert!(hex.ends_with("a341ee0342ffff014300ff0002"));
+
+        let bytes = from_hex(&hex).unwrap();
+        assert_eq!(value(&mut Reader::new(&bytes)).unwrap(), output);
+
+        output.insert_asset(("not hex".into(), "".into()), 1);
+        assert!(write_value(&mut Writer::default(), &output).is_none());
+    }
+}
@@ -0,0 +1,311 @@
+/*!
+Change to several destinations, or none
+
+The change of a transaction may go to more than one address, e.g. most to a hot wallet
+and the rest to a cold one. Destinations of fixed lovelace are paid first, and what remains
+is shared by the ratios of the others. The assets of the change go to the first destination
+of a ratio, or the first of all if there is none.
+
+The change may also be merged into an output paying the wallet itself,
+so the transaction creates one UTxO fewer.
+*/
+use crate::{
+    copy_extra_fields_of_outputs, get_amount, get_field, iterate, ErrorCode, JsOutput,
+    JsOutputArray, Output,
+};
+use js_sys::Array;
+use std::collections::BTreeMap;
+use utxo::{ExtOutput, Select};
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type ChangeDestination = {
+  address: string
+  minLovelace?: bigint
+} & ({ ratio: number } | { lovelace: bigint })
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Array<ChangeDestination>")]
+    pub type JsChangeDestinationArray;
+}
+
+/// The parts a ratio is counted in.
+const RATIO_PARTS: f64 = 1e9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Share {
+    /// Of what remains after the fixed shares, in parts of `RATIO_PARTS`.
+    Ratio(u64),
+    Lovelace(u64),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Destination {
+    pub(crate) address: String,
+    pub(crate) share: Share,
+    /// The lovelace of its change output at least.
+    pub(crate) min_lovelace: u64,
+}
+
+impl Destination {
+    fn from_js(value: &JsValue) -> Result<Self, ErrorCode> {
+        let address = get_field(value, "address")?
+            .as_string()
+            .ok_or(ErrorCode::InvalidType)?;
+        let ratio = get_field(value, "ratio")?;
+        let share = match (ratio.is_undefined(), get_amount(value, "lovelace")?) {
+            (true, Some(lovelace)) => Share::Lovelace(lovelace),
+            (false, None) => Share::Ratio(
+                ratio
+                    .as_f64()
+                    .filter(|r| r.is_finite() && *r >= 0.0)
+                    .map(|r| (r * RATIO_PARTS).round() as u64)
+                    .ok_or(ErrorCode::InvalidType)?,
+            ),
+            _ => return Err(ErrorCode::InvalidType),
+        };
+
+        Ok(Self {
+            address,
+            share,
+            min_lovelace: get_amount(value, "minLovelace")?.unwrap_or_default(),
+        })
+    }
+}
+
+/**
+Splits the change to the destinations, an output for each in their order.
+
+The lovelace a ratio leaves by rounding down goes to the first destination of a ratio.
+Returns `None` if there are no destinations, the fixed shares are more than the change,
+a destination gets less than its minimum, or the change is left to no destination.
+*/
+pub(crate) fn split_change<D>(
+    change: &ExtOutput<D, (String, String)>,
+    destinations: &[Destination],
+) -> Option<Vec<ExtOutput<D, (String, String)>>> {
+    if destinations.is_empty() {
+        return None;
+    }
+
+    let fixed =
+        destinations
+            .iter()
+            .try_fold(0u64, |total, destination| match destination.share {
+                Share::Lovelace(lovelace) => total.checked_add(lovelace),
+                Share::Ratio(_) => Some(total),
+            })?;
+    let remaining = change.value.checked_sub(fixed)?;
+    let parts: u128 = destinations
+        .iter()
+        .filter_map(|destination| match destination.share {
+            Share::Ratio(parts) => Some(u128::from(parts)),
+            Share::Lovelace(_) => None,
+        })
+        .sum();
+
+    let mut values: Vec<u64> = destinations
+        .iter()
+        .map(|destination| match destination.share {
+            Share::Lovelace(lovelace) => lovelace,
+            Share::Ratio(_) if parts == 0 => 0,
+            Share::Ratio(part) => (u128::from(remaining) * u128::from(part) / parts) as u64,
+        })
+        .collect();
+
+    let first_ratio = destinations
+        .iter()
+        .position(|destination| matches!(destination.share, Share::Ratio(_)));
+    let holder = match first_ratio {
+        Some(index) if parts > 0 => index,
+        _ if remaining > 0 => return None,
+        _ => 0,
+    };
+    values[holder] += change.value - values.iter().sum::<u64>();
+
+    let outputs: Vec<ExtOutput<D, (String, String)>> = values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| ExtOutput {
+            value,
+            assets: if index == holder {
+                change.assets.clone()
+            } else {
+                BTreeMap::new()
+            },
+            data: None,
+        })
+        .collect();
+
+    let enough = outputs
+        .iter()
+        .zip(destinations)
+        .all(|(output, destination)| output.value >= destination.min_lovelace);
+
+    enough.then_some(outputs)
+}
+
+type Bundle<D> = ExtOutput<D, (String, String)>;
+
+/**
+Merges the change, what the excess holds beyond the threshold, into the output.
+
+Returns the output with the change and the excess with the threshold only,
+`None` if the output overflowed.
+*/
+pub(crate) fn merge_change<D: Clone>(
+    output: &ExtOutput<D, (String, String)>,
+    excess: &ExtOutput<D, (String, String)>,
+    threshold: &ExtOutput<D, (String, String)>,
+) -> Option<(Bundle<D>, Bundle<D>)> {
+    let change = excess.saturating_sub(threshold);
+
+    Some((output.checked_add(&change)?, excess.saturating_sub(&change)))
+}
+
+/**
+The outputs with the change merged into the one at the index, and the excess left.
+
+The merged output keeps the address and extra fields of the output.
+*/
+pub(crate) fn merge_change_into<'o>(
+    outputs: &[Output<'o>],
+    index: usize,
+    excess: &Output<'o>,
+    threshold: &Output<'o>,
+) -> Result<(JsOutputArray, Output<'o>), ErrorCode> {
+    let (merged, excess) =
+        merge_change(&outputs[index], excess, threshold).ok_or(ErrorCode::OutputsOverflowed)?;
+    let merged: JsOutput = merged.into();
+    copy_extra_fields_of_outputs(&merged, &outputs[index..=index]);
+
+    let result: Array = outputs
+        .iter()
+        .map(|output| output.data.expect("Unreachable"))
+        .collect();
+    result.set(index as u32, merged.into());
+
+    Ok((result.unchecked_into(), excess))
+}
+
+/**
+Split change to several destinations
+
+Returns a change output for each destination in their order, with its `address`.
+Destinations of fixed `lovelace` are paid first, and what remains is shared by
+the `ratio`s of the others, e.g. `0.9` and `0.1`. The assets of the change go to
+the first destination of a ratio, or of all if there is none.
+Every output must have at least the `minLovelace` of its destination.
+
+Returns nothing if the change is not enough for the fixed amounts and the minimums,
+or some is left to no destination of a ratio.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = splitChange)]
+pub fn split_change_to(
+    change: &JsOutput,
+    destinations: &JsChangeDestinationArray,
+) -> Result<Option<JsOutputArray>, JsError> {
+    let change: Output = change.try_into()?;
+    let destinations = iterate(destinations)?
+        .iter()
+        .map(Destination::from_js)
+        .collect::<Result<Vec<Destination>, ErrorCode>>()?;
+
+    let Some(outputs) = split_change(&change, &destinations) else {
+        return Ok(None);
+    };
+
+    let result = Array::new();
+    for (output, destination) in outputs.into_iter().zip(destinations.iter()) {
+        let output: JsOutput = output.into();
+        output.set_address(&destination.address);
+        result.push(&output);
+    }
+
+    Ok(Some(result.unchecked_into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::change::{merge_change, split_change, Destination, Share};
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    fn destination(share: Share, min_lovelace: u64) -> Destination {
+        Destination {
+            address: "addr_test1".into(),
+            share,
+            min_lovelace,
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_split_change() {
+        let mut change: ExtOutput<(), (String, String)> = ExtOutput {
+            value: 10_000_001,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        change.insert_asset(("policy1".into(), "aname1".into()), 5);
+        let values = |outputs: Vec<ExtOutput<(), (String, String)>>| {
+            outputs.iter().map(|o| o.value).collect::<Vec<u64>>()
+        };
+
+        let hot_cold = [
+            destination(Share::Lovelace(1_000_000), 0),
+            destination(Share::Ratio(900_000_000), 0),
+            destination(Share::Ratio(100_000_000), 0),
+        ];
+        let outputs = split_change(&change, &hot_cold).unwrap();
+        assert_eq!(outputs[1].assets, change.assets);
+        assert!(outputs[2].assets.is_empty());
+        assert_eq!(values(outputs), vec![1_000_000, 8_100_001, 900_000]);
+
+        let fixed = [
+            destination(Share::Lovelace(4_000_000), 0),
+            destination(Share::Lovelace(6_000_001), 0),
+        ];
+        let outputs = split_change(&change, &fixed).unwrap();
+        assert_eq!(outputs[0].assets, change.assets);
+        assert_eq!(values(outputs), vec![4_000_000, 6_000_001]);
+
+        // Left to no destination, or below a minimum.
+        assert!(split_change(&change, &fixed[..1]).is_none());
+        let minimums = [
+            destination(Share::Ratio(1), 0),
+            destination(Share::Ratio(1), 6_000_000),
+        ];
+        assert!(split_change(&change, &minimums).is_none());
+        assert!(split_change(&change, &[]).is_none());
+        change.value = 0;
+        assert!(split_change(&change, &[]).is_none());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_merge_change() {
+        let output = |value, quantity| {
+            let mut output: ExtOutput<(), (String, String)> = ExtOutput {
+                value,
+                assets: BTreeMap::new(),
+                data: None,
+            };
+            output.insert_asset(("policy1".into(), "aname1".into()), quantity);
+            output
+        };
+
+        let (merged, excess) = merge_change(
+            &output(2_000_000, 1),
+            &output(3_500_000, 4),
+            &output(200_000, 0),
+        )
+        .unwrap();
+        assert_eq!(merged, output(5_300_000, 5));
+        assert_eq!(excess, output(200_000, 0));
+    }
+}
@@ -0,0 +1,219 @@
+/*!
+Selection yielding to the event loop
+
+Reading tens of thousands of UTxOs from JS takes long enough to freeze a page.
+`selectAsync` reads the inputs in chunks, resolving a timeout between them so the browser
+renders and handles events, then selects as `select` does. The inputs may be an async iterable,
+e.g. of UTxOs read from an IndexedDB cursor, awaited one after another.
+*/
+use crate::{
+    clock, get_count, iterate, options::JsSelectOptions, parse_output, profile::Profiler,
+    select_parsed, spendable_inputs, ErrorCode, Failure, JsOutput, JsOutputArray, SelectOptions,
+    SelectResult,
+};
+use js_sys::{AsyncIterator, Function, IteratorNext, Promise, Reflect, Symbol};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(handler: &Function, timeout: i32);
+}
+
+/// The inputs read between the yields by default.
+const CHUNK_SIZE: usize = 1000;
+
+/// Resolves in a task of its own, after the event loop has run the others.
+async fn yield_now() {
+    let promise = Promise::new(&mut |resolve, _| set_timeout(&resolve, 0));
+    let _ = JsFuture::from(promise).await;
+}
+
+/// The async iterator of the value, `None` unless it has `Symbol.asyncIterator`.
+fn async_iterator(value: &JsValue) -> Option<AsyncIterator> {
+    if !value.is_object() {
+        return None;
+    }
+
+    let method = Reflect::get(value, &Symbol::async_iterator()).ok()?;
+    let method: Function = method.dyn_into().ok()?;
+    let iterator = method.call0(value).ok()?;
+
+    iterator.is_object().then(|| iterator.unchecked_into())
+}
+
+/**
+Reads the inputs, of an iterable or an async iterable, as `JsOutput`s.
+
+Yields to the event loop after every chunk of them, even when an async iterable resolves
+its items at once, in microtasks not letting the browser render between them.
+*/
+async fn read_inputs(inputs: &JsValue, chunk_size: usize) -> Result<Vec<JsOutput>, ErrorCode> {
+    let Some(iterator) = async_iterator(inputs) else {
+        return Ok(iterate(inputs)?
+            .into_iter()
+            .map(JsCast::unchecked_into)
+            .collect());
+    };
+    let mut js_inputs = Vec::new();
+
+    loop {
+        let next = iterator.next().map_err(|_| ErrorCode::InvalidType)?;
+        let next: IteratorNext = JsFuture::from(next)
+            .await
+            .map_err(|_| ErrorCode::InvalidType)?
+            .dyn_into()
+            .map_err(|_| ErrorCode::InvalidType)?;
+
+        if next.done() {
+            return Ok(js_inputs);
+        }
+
+        js_inputs.push(next.value().unchecked_into());
+
+        if js_inputs.len().is_multiple_of(chunk_size) {
+            yield_now().await;
+        }
+    }
+}
+
+/**
+Select UTxOs without blocking the event loop
+
+Works as `select` with the same options, resolving the result instead of returning it,
+and reads the inputs in chunks of `chunkSize` (default `1000`), yielding to the event loop
+between them, so a browser UI keeps responding while a large set of UTxOs is converted.
+The inputs may be an iterable, as for `select`, or an async iterable, e.g. an async generator
+reading an IndexedDB cursor, whose items are awaited in turn.
+The selection itself runs once all the inputs are read.
+
+Rejects with the errors `select` raises.
+*/
+#[wasm_bindgen(js_name = selectAsync)]
+pub async fn select_async(
+    #[wasm_bindgen(unchecked_param_type = "Iterable<Output> | AsyncIterable<Output>")]
+    inputs: JsOutputArray,
+    #[wasm_bindgen(unchecked_param_type = "Iterable<Output>")] outputs: JsOutputArray,
+    #[wasm_bindgen(unchecked_param_type = "Threshold")] threshold: JsOutput,
+    options: Option<JsSelectOptions>,
+) -> Result<Option<SelectResult>, JsError> {
+    let start = clock::now();
+    let chunk_size = match &options {
+        Some(options) => get_count(options, "chunkSize")?.map(|size| size as usize),
+        None => None,
+    }
+    .filter(|size| *size > 0)
+    .unwrap_or(CHUNK_SIZE);
+    let options = SelectOptions::try_from(options.as_ref())?;
+    let profiler = Profiler::new(options.profile, start);
+    let js_inputs = read_inputs(&inputs, chunk_size)
+        .await
+        .map_err(|code| Failure::from(code).within("inputs"))?;
+    let (js_inputs, reference_inputs) = spendable_inputs(js_inputs, &options)?;
+    let mut parsed = Vec::with_capacity(js_inputs.len());
+
+    for (index, chunk) in js_inputs.chunks(chunk_size).enumerate() {
+        if index > 0 {
+            yield_now().await;
+        }
+
+        for (offset, js_input) in chunk.iter().enumerate() {
+            let input = parse_output(js_input).map_err(|failure| {
+                failure
+                    .within(&format!("[{}]", index * chunk_size + offset))
+                    .within("inputs")
+            })?;
+            parsed.push(input);
+        }
+    }
+
+    Ok(select_parsed(
+        parsed,
+        &reference_inputs,
+        &outputs,
+        &threshold,
+        options,
+        profiler,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chunked::select_async, JsOutput, JsOutputArray, Output};
+    use js_sys::{Array, Function, Object, Reflect, Set};
+    use utxo::Select;
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    async fn test_select_async() {
+        let output = |value: u64| {
+            let output: JsOutput = Object::new().unchecked_into();
+            output.set_lovelace(value);
+            output
+        };
+        let inputs: JsOutputArray = (1..=5)
+            .map(|value| output(value * 1000))
+            .collect::<Array>()
+            .unchecked_into();
+        let outputs: JsOutputArray = Array::of1(&output(9000)).unchecked_into();
+        let threshold: JsOutput = Output::zero().into();
+        let options = Object::new();
+        Reflect::set(&options, &"chunkSize".into(), &2.into()).unwrap();
+
+        let result = select_async(
+            inputs.clone().unchecked_into(),
+            outputs,
+            threshold.clone(),
+            Some(options.clone().unchecked_into()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(result.excess().lovelace() < 5000);
+
+        inputs.unchecked_ref::<Array>().push(&1.into());
+        let error = select_async(
+            inputs,
+            Array::new().unchecked_into(),
+            threshold,
+            Some(options.unchecked_into()),
+        )
+        .await
+        .err();
+        assert!(error.is_some());
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_select_async_iterables() {
+        let inputs: Array = (1..=5)
+            .map(|value| {
+                let output: JsOutput = Output::zero().into();
+                output.set_lovelace(value * 1000);
+                output
+            })
+            .collect();
+        let generate =
+            Function::new_with_args("items", "return (async function* () { yield* items })()");
+        let outputs: JsOutput = Output::zero().into();
+        outputs.set_lovelace(9000);
+        let outputs = Set::new(&Array::of1(&outputs));
+
+        let result = select_async(
+            generate
+                .call1(&JsValue::NULL, &inputs)
+                .unwrap()
+                .unchecked_into(),
+            outputs.unchecked_into(),
+            Output::zero().into(),
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(result.excess().lovelace() < 5000);
+    }
+}
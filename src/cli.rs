@@ -0,0 +1,105 @@
+/*!
+Arguments of cardano-cli
+
+Selections can be tried against `cardano-cli transaction build-raw`
+with the arguments of their inputs and outputs.
+*/
+use crate::{
+    collect_js_outputs, get_field, out_point::OutPoint, raise, to_outputs, ErrorCode, ErrorKind,
+    JsOutputArray, SelectResult,
+};
+use js_sys::Array;
+use utxo::ExtOutput;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Array<string>")]
+    pub type StringArray;
+}
+
+/// The value of `--tx-out`, i.e. `address+lovelace+quantity policy.name+...`.
+pub(crate) fn tx_out<D>(address: &str, output: &ExtOutput<D, (String, String)>) -> String {
+    let mut arg = format!("{}+{}", address, output.value);
+
+    for ((policy_id, asset_name), quantity) in output.assets.iter() {
+        arg.push_str(&format!("+{} {}", quantity, policy_id));
+
+        if !asset_name.is_empty() {
+            arg.push_str(&format!(".{}", asset_name));
+        }
+    }
+
+    arg
+}
+
+/**
+Make the arguments of cardano-cli for a selection
+
+Returns `--tx-in hash#index` for each selected UTxO and `--tx-out` for each change output,
+as separate items to pass to a process without a shell:
+
+```text
+--tx-in 09d3...add8#1 --tx-out addr1...+1000000+2 b0d0...38a7.6d794e4654
+```
+
+The selected UTxOs should have their `OutPoint` as `data`,
+and the change outputs should have their `address`.
+
+Raises errors when the types used are wrong, or any `OutPoint` or address is missing.
+*/
+#[wasm_bindgen(js_name = toCliArgs)]
+pub fn to_cli_args(
+    result: &SelectResult,
+    change_outputs: &JsOutputArray,
+) -> Result<StringArray, JsError> {
+    let selected = collect_js_outputs(get_field(result, "selected")?.unchecked_ref())?;
+    let js_change = collect_js_outputs(change_outputs)?;
+    let args = Array::new();
+
+    for utxo in selected.iter() {
+        let out_point = OutPoint::of(utxo).ok_or_else(|| {
+            raise(
+                ErrorKind::MissingOutPoint,
+                "Selected UTxOs should have their OutPoint as data",
+            )
+        })?;
+        args.push(&"--tx-in".into());
+        args.push(&out_point.key().into());
+    }
+
+    for (js_output, output) in js_change.iter().zip(to_outputs(&js_change)?) {
+        let address = get_field(js_output, "address")?
+            .as_string()
+            .ok_or(ErrorCode::InvalidType)?;
+        args.push(&"--tx-out".into());
+        args.push(&tx_out(&address, &output).into());
+    }
+
+    Ok(args.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::tx_out;
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_tx_out() {
+        let mut output: ExtOutput<(), (String, String)> = ExtOutput {
+            value: 1_000_000,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        assert_eq!(tx_out("addr1", &output), "addr1+1000000");
+
+        output.insert_asset(("policy1".into(), "6d79".into()), 2);
+        output.insert_asset(("policy2".into(), "".into()), 3);
+        assert_eq!(
+            tx_out("addr1", &output),
+            "addr1+1000000+2 policy1.6d79+3 policy2"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+/*!
+Monotonic enough time for budgets and benchmarks
+*/
+
+/// Milliseconds from `performance.now()`, or `Date.now()` where it is not available.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now() -> f64 {
+    use js_sys::{Date, Reflect};
+    use wasm_bindgen::JsCast;
+
+    let performance = Reflect::get(&js_sys::global(), &"performance".into())
+        .ok()
+        .filter(|p| p.is_object());
+    let now = performance
+        .as_ref()
+        .and_then(|p| Reflect::get(p, &"now".into()).ok())
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+
+    match (performance, now) {
+        (Some(performance), Some(now)) => now
+            .call0(&performance)
+            .ok()
+            .and_then(|t| t.as_f64())
+            .unwrap_or_else(Date::now),
+        _ => Date::now(),
+    }
+}
+
+/// Milliseconds from the Unix epoch, for native tests.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64() * 1000.0)
+}
+
+/// Adds a measure of `performance` from the start to the end, if the API is available.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn measure(name: &str, start: f64, end: f64) {
+    use js_sys::{Object, Reflect};
+    use wasm_bindgen::JsCast;
+
+    let Some(performance) = Reflect::get(&js_sys::global(), &"performance".into())
+        .ok()
+        .filter(|p| p.is_object())
+    else {
+        return;
+    };
+    let Some(measure) = Reflect::get(&performance, &"measure".into())
+        .ok()
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+    else {
+        return;
+    };
+    let range = Object::new();
+    Reflect::set(&range, &"start".into(), &start.into()).expect("Unreachable");
+    Reflect::set(&range, &"end".into(), &end.into()).expect("Unreachable");
+
+    // Older implementations reject the range, the timings are returned anyway.
+    let _ = measure.call2(&performance, &name.into(), &range);
+}
+
+/// Nothing to measure with natively.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn measure(_name: &str, _start: f64, _end: f64) {}
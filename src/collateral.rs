@@ -0,0 +1,354 @@
+/*!
+Collateral of Plutus transactions
+
+Collateral is forfeited when a script fails, so it is best taken from
+a dedicated pure ADA UTxO that is big enough but not too big.
+The collateral required is the fee times the collateral percentage of the protocol,
+from at most the max collateral inputs. Since Babbage, what the inputs hold above it
+may come back in a collateral return output, and only the total collateral is forfeited.
+*/
+use crate::{
+    collect_js_outputs, get_amount, get_count, to_outputs, ErrorCode, JsOutput, JsOutputArray,
+    Output,
+};
+use js_sys::{Array, Object, Reflect};
+use std::cmp::Reverse;
+use utxo::{ExtOutput, Select};
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type CollateralOptions = {
+  minLovelace?: bigint
+  maxLovelace?: bigint
+}
+
+export type CollateralIssue = "HAS_ASSETS" | "BELOW_MIN" | "ABOVE_MAX"
+
+export type CollateralClass = {
+  output: Output
+  suitable: boolean
+  issue?: CollateralIssue
+}
+
+export type CollateralParams = {
+  fee: bigint
+  collateralPercentage?: number
+  maxCollateralInputs?: number
+  minReturnLovelace?: bigint
+}
+
+export type CollateralSelection = {
+  collateral: Array<Output>
+  required: bigint
+  total: bigint
+  totalCollateral: bigint
+  collateralReturn?: Output
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CollateralOptions")]
+    pub type JsCollateralOptions;
+
+    #[wasm_bindgen(typescript_type = "Array<CollateralClass>")]
+    pub type CollateralClassArray;
+
+    #[wasm_bindgen(typescript_type = "CollateralParams")]
+    pub type JsCollateralParams;
+
+    #[wasm_bindgen(typescript_type = "CollateralSelection")]
+    pub type CollateralSelection;
+}
+
+/// 5 ADA, enough for the collateral of most transactions.
+pub(crate) const MIN_LOVELACE: u64 = 5_000_000;
+
+/// 50 ADA, above which too much would be locked as collateral.
+pub(crate) const MAX_LOVELACE: u64 = 50_000_000;
+
+/// The `collateralPercentage` of the protocol, of the fee.
+pub(crate) const COLLATERAL_PERCENTAGE: u64 = 150;
+
+/// The `maxCollateralInputs` of the protocol.
+pub(crate) const MAX_COLLATERAL_INPUTS: usize = 3;
+
+/// 1 ADA, above the minimum of a pure ADA output to return collateral to.
+pub(crate) const MIN_RETURN_LOVELACE: u64 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CollateralIssue {
+    HasAssets,
+    BelowMin,
+    AboveMax,
+}
+
+impl CollateralIssue {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::HasAssets => "HAS_ASSETS",
+            Self::BelowMin => "BELOW_MIN",
+            Self::AboveMax => "ABOVE_MAX",
+        }
+    }
+}
+
+/// Checks if an output is suitable as collateral, returning its issue if not.
+pub(crate) fn check<D, K>(
+    output: &ExtOutput<D, K>,
+    min: u64,
+    max: u64,
+) -> Result<(), CollateralIssue> {
+    if !output.assets.is_empty() {
+        Err(CollateralIssue::HasAssets)
+    } else if output.value < min {
+        Err(CollateralIssue::BelowMin)
+    } else if output.value > max {
+        Err(CollateralIssue::AboveMax)
+    } else {
+        Ok(())
+    }
+}
+
+/**
+Classify UTxOs by their suitability as collateral
+
+A UTxO is suitable when it holds no assets and its lovelace is between
+`minLovelace` (default 5 ADA) and `maxLovelace` (default 50 ADA).
+
+Returns an array with one entry per UTxO, in the same order,
+with the `issue` of the UTxOs not suitable.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = classifyCollateral)]
+pub fn classify_collateral(
+    utxos: &JsOutputArray,
+    options: Option<JsCollateralOptions>,
+) -> Result<CollateralClassArray, JsError> {
+    let option = |key, default| match options.as_ref() {
+        Some(options) => get_amount(options, key).map(|value| value.unwrap_or(default)),
+        None => Ok(default),
+    };
+    let min = option("minLovelace", MIN_LOVELACE)?;
+    let max = option("maxLovelace", MAX_LOVELACE)?;
+    let js_utxos = collect_js_outputs(utxos)?;
+    let result = Array::new();
+
+    for utxo in to_outputs(&js_utxos)? {
+        let class = Object::new();
+        let check = check(&utxo, min, max);
+        let data = utxo.data.expect("Unreachable");
+
+        Reflect::set(&class, &"output".into(), data).expect("Unreachable");
+        Reflect::set(&class, &"suitable".into(), &check.is_ok().into()).expect("Unreachable");
+
+        if let Err(issue) = check {
+            Reflect::set(&class, &"issue".into(), &issue.code().into()).expect("Unreachable");
+        }
+
+        result.push(&class);
+    }
+
+    Ok(result.unchecked_into())
+}
+
+/// The collateral required for the fee, rounded up, `None` if overflowed.
+pub(crate) fn required(fee: u64, percentage: u64) -> Option<u64> {
+    let required = u128::from(fee) * u128::from(percentage);
+
+    u64::try_from(required.div_ceil(100)).ok()
+}
+
+/**
+Finds the indices of the pure ADA outputs to collateralize the required lovelace.
+
+The smallest output covering it alone is taken, otherwise the largest ones,
+so the fewest are used and the least is locked.
+Returns `None` if more than `max_inputs` would be needed.
+*/
+pub(crate) fn find<D, K>(
+    outputs: &[ExtOutput<D, K>],
+    required: u64,
+    max_inputs: usize,
+) -> Option<Vec<usize>> {
+    let mut order: Vec<usize> = (0..outputs.len())
+        .filter(|index| outputs[*index].assets.is_empty())
+        .collect();
+    order.sort_by_key(|index| outputs[*index].value);
+
+    if let Some(index) = order
+        .iter()
+        .find(|index| outputs[**index].value >= required)
+    {
+        return Some(vec![*index]);
+    }
+
+    order.sort_by_key(|index| Reverse(outputs[*index].value));
+    let mut total: u64 = 0;
+    let selected: Vec<usize> = order
+        .into_iter()
+        .take(max_inputs)
+        .take_while(|index| {
+            let more = total < required;
+            total = total.saturating_add(outputs[*index].value);
+            more
+        })
+        .collect();
+
+    (total >= required && !selected.is_empty()).then_some(selected)
+}
+
+/**
+Splits the total of the collateral inputs to the total collateral and the collateral return.
+
+Returns the total collateral, which is all of the inputs when what they hold above the required
+is less than `min_return`, otherwise the required and the lovelace returned.
+*/
+pub(crate) fn collateral_return(total: u64, required: u64, min_return: u64) -> (u64, Option<u64>) {
+    match total.checked_sub(required) {
+        Some(excess) if excess > 0 && excess >= min_return => (required, Some(excess)),
+        _ => (total, None),
+    }
+}
+
+/**
+Select pure ADA UTxOs as collateral of a Plutus transaction
+
+The collateral `required` is the `fee` times `collateralPercentage` (default 150) percent.
+The protocol parameters may be passed with the fee, e.g. `{ ...mainnetParams(), fee }`.
+Only UTxOs holding no assets are considered: the smallest one covering the requirement,
+otherwise the largest ones up to `maxCollateralInputs` (default 3).
+
+Returns the `collateral` UTxOs with their `total` lovelace and the `required`.
+When the total is above the required by at least `minReturnLovelace` (default 1 ADA),
+the rest is in the `collateralReturn`, to the address of the first collateral UTxO,
+and the `totalCollateral` forfeited is the required, as of Babbage. Otherwise it is the total.
+Returns nothing if the UTxOs are not enough within `maxCollateralInputs`.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = selectCollateral)]
+pub fn select_collateral(
+    utxos: &JsOutputArray,
+    params: &JsCollateralParams,
+) -> Result<Option<CollateralSelection>, JsError> {
+    let fee = get_amount(params, "fee")?.ok_or(ErrorCode::InvalidType)?;
+    let percentage =
+        get_count(params, "collateralPercentage")?.map_or(COLLATERAL_PERCENTAGE, u64::from);
+    let max_inputs = get_count(params, "maxCollateralInputs")?
+        .map_or(MAX_COLLATERAL_INPUTS, |count| count as usize);
+    let min_return = get_amount(params, "minReturnLovelace")?.unwrap_or(MIN_RETURN_LOVELACE);
+    let required = required(fee, percentage).ok_or(ErrorCode::OutputsOverflowed)?;
+    let js_utxos = collect_js_outputs(utxos)?;
+    let outputs = to_outputs(&js_utxos)?;
+
+    let Some(selected) = find(&outputs, required, max_inputs) else {
+        return Ok(None);
+    };
+
+    let collateral = Array::new();
+    let mut total: u64 = 0;
+
+    for index in selected.iter() {
+        collateral.push(&js_utxos[*index]);
+        total = total
+            .checked_add(outputs[*index].value)
+            .ok_or(ErrorCode::OutputsOverflowed)?;
+    }
+
+    let (total_collateral, returned) = collateral_return(total, required, min_return);
+    let result = Object::new();
+    let set = |key: &str, value: &JsValue| {
+        Reflect::set(&result, &key.into(), value).expect("Unreachable");
+    };
+    set("collateral", &collateral);
+    set("required", &required.into());
+    set("total", &total.into());
+    set("totalCollateral", &total_collateral.into());
+
+    if let Some(value) = returned {
+        let output: JsOutput = Output {
+            value,
+            ..Output::zero()
+        }
+        .into();
+        if let Some(address) = js_utxos[selected[0]].address() {
+            output.set_address(&address);
+        }
+        set("collateralReturn", &output);
+    }
+
+    Ok(Some(result.unchecked_into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collateral::{check, collateral_return, find, required, CollateralIssue};
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_check_collateral() {
+        let mut output: ExtOutput<(), (String, String)> = ExtOutput {
+            value: 5_000_000,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+
+        assert_eq!(check(&output, 5_000_000, 10_000_000), Ok(()));
+        assert_eq!(
+            check(&output, 6_000_000, 10_000_000),
+            Err(CollateralIssue::BelowMin)
+        );
+        assert_eq!(
+            check(&output, 1_000_000, 2_000_000),
+            Err(CollateralIssue::AboveMax)
+        );
+
+        output.insert_asset(("policy1".into(), "aname1".into()), 1);
+        assert_eq!(
+            check(&output, 5_000_000, 10_000_000),
+            Err(CollateralIssue::HasAssets)
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_find_collateral() {
+        let output = |value, quantity| {
+            let mut output: ExtOutput<(), (String, String)> = ExtOutput {
+                value,
+                assets: BTreeMap::new(),
+                data: None,
+            };
+            output.insert_asset(("policy1".into(), "aname1".into()), quantity);
+            output
+        };
+        let outputs = vec![
+            output(2_000_000, 0),
+            output(9_000_000, 1),
+            output(6_000_000, 0),
+            output(1_000_000, 0),
+            output(8_000_000, 0),
+        ];
+
+        assert_eq!(required(200_001, 150), Some(300_002));
+        assert_eq!(find(&outputs, 5_000_000, 3), Some(vec![2]));
+        assert_eq!(find(&outputs, 10_000_000, 3), Some(vec![4, 2]));
+        assert_eq!(find(&outputs, 15_500_000, 3), Some(vec![4, 2, 0]));
+        assert_eq!(find(&outputs, 15_500_000, 2), None);
+        assert_eq!(find(&outputs, 18_000_000, 5), None);
+
+        assert_eq!(
+            collateral_return(6_000_000, 750_000, 1_000_000),
+            (750_000, Some(5_250_000))
+        );
+        assert_eq!(
+            collateral_return(1_500_000, 750_000, 1_000_000),
+            (1_500_000, None)
+        );
+        assert_eq!(collateral_return(750_000, 750_000, 0), (750_000, None));
+    }
+}
@@ -0,0 +1,249 @@
+/*!
+Signed values
+
+A [`Delta`] is the difference of values, e.g. what the inputs and mint of a transaction
+bring minus what its outputs and fee take. Its lovelace and assets may be negative,
+so balancing is computed directly instead of as a surplus and a deficit.
+*/
+use crate::{
+    scenario::{AssetValue, Value},
+    ErrorCode,
+};
+use js_sys::{Array, Object, Reflect};
+use std::collections::BTreeMap;
+use utxo::ExtOutput;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type SignedAsset = {
+  policyId: string
+  assetName: string
+  quantity: bigint
+}
+
+export type Delta = {
+  lovelace: bigint
+  assets: Array<SignedAsset>
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Delta")]
+    pub type JsDelta;
+}
+
+/// Reads a signed amount, of a bigint or a safe integer.
+pub(crate) fn to_signed_amount(value: &JsValue) -> Result<i128, ErrorCode> {
+    const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+    if value.is_bigint() {
+        return i128::try_from(value.clone()).map_err(|_| ErrorCode::InvalidAmount);
+    }
+
+    match value.as_f64() {
+        Some(n) if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER => Ok(n as i128),
+        _ => Err(ErrorCode::InvalidAmount),
+    }
+}
+
+/// A value of signed quantities, without the assets of quantity zero.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Delta {
+    pub lovelace: i128,
+    pub assets: BTreeMap<(String, String), i128>,
+}
+
+impl Delta {
+    /// Whether it is balanced, i.e. nothing is left or missing.
+    pub fn is_zero(&self) -> bool {
+        self.lovelace == 0 && self.assets.is_empty()
+    }
+
+    /// Adds the quantity of the asset, removing it if the sum is zero.
+    pub fn add_asset(&mut self, key: (String, String), quantity: i128) -> Option<()> {
+        let sum = self
+            .assets
+            .get(&key)
+            .copied()
+            .unwrap_or_default()
+            .checked_add(quantity)?;
+
+        if sum == 0 {
+            self.assets.remove(&key);
+        } else {
+            self.assets.insert(key, sum);
+        }
+
+        Some(())
+    }
+
+    /// Computes `self + rhs`, `None` if overflowed.
+    pub fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        let mut sum = self.clone();
+        sum.lovelace = sum.lovelace.checked_add(rhs.lovelace)?;
+
+        for (key, quantity) in rhs.assets.iter() {
+            sum.add_asset(key.clone(), *quantity)?;
+        }
+
+        Some(sum)
+    }
+
+    /// Computes `-self`, `None` if overflowed.
+    pub fn checked_neg(&self) -> Option<Self> {
+        Some(Self {
+            lovelace: self.lovelace.checked_neg()?,
+            assets: self
+                .assets
+                .iter()
+                .map(|(key, quantity)| Some((key.clone(), quantity.checked_neg()?)))
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    /// Computes `self - rhs`, `None` if overflowed.
+    pub fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        self.checked_add(&rhs.checked_neg()?)
+    }
+
+    /// The positive part, i.e. what is left over.
+    pub fn surplus(&self) -> Value {
+        self.part(1)
+    }
+
+    /// The negative part as positive quantities, i.e. what is missing.
+    pub fn deficit(&self) -> Value {
+        self.part(-1)
+    }
+
+    fn part(&self, sign: i128) -> Value {
+        let quantity = |q: i128| u64::try_from(q.saturating_mul(sign).max(0)).unwrap_or(u64::MAX);
+
+        Value {
+            lovelace: quantity(self.lovelace),
+            assets: self
+                .assets
+                .iter()
+                .filter(|(_, q)| q.signum() == sign)
+                .map(|((policy_id, asset_name), q)| AssetValue {
+                    policy_id: policy_id.clone(),
+                    asset_name: asset_name.clone(),
+                    quantity: quantity(*q),
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn to_js(&self) -> JsDelta {
+        let result = Object::new();
+        let assets: Array = self
+            .assets
+            .iter()
+            .map(|((policy_id, asset_name), quantity)| {
+                let asset = Object::new();
+                let set = |key: &str, value: &JsValue| {
+                    Reflect::set(&asset, &key.into(), value).expect("Unreachable");
+                };
+                set("policyId", &policy_id.into());
+                set("assetName", &asset_name.into());
+                set("quantity", &(*quantity).into());
+                JsValue::from(asset)
+            })
+            .collect();
+
+        Reflect::set(&result, &"lovelace".into(), &self.lovelace.into()).expect("Unreachable");
+        Reflect::set(&result, &"assets".into(), &assets).expect("Unreachable");
+        result.unchecked_into()
+    }
+
+    /// The output of the delta, `None` if anything is negative or too large.
+    pub fn to_output<D>(&self) -> Option<ExtOutput<D, (String, String)>> {
+        let mut output = ExtOutput {
+            value: u64::try_from(self.lovelace).ok()?,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+
+        for (key, quantity) in self.assets.iter() {
+            output.insert_asset(key.clone(), u64::try_from(*quantity).ok()?);
+        }
+
+        Some(output)
+    }
+}
+
+impl<D> From<&ExtOutput<D, (String, String)>> for Delta {
+    fn from(output: &ExtOutput<D, (String, String)>) -> Self {
+        Self {
+            lovelace: output.value.into(),
+            assets: output
+                .assets
+                .iter()
+                .filter(|(_, quantity)| **quantity > 0)
+                .map(|(key, quantity)| (key.clone(), (*quantity).into()))
+                .collect(),
+        }
+    }
+}
+
+impl From<&Value> for Delta {
+    fn from(value: &Value) -> Self {
+        let mut delta = Self {
+            lovelace: value.lovelace.into(),
+            assets: BTreeMap::new(),
+        };
+
+        for asset in value.assets.iter() {
+            let key = (asset.policy_id.clone(), asset.asset_name.clone());
+            delta
+                .add_asset(key, asset.quantity.into())
+                .expect("Unreachable");
+        }
+
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{delta::Delta, scenario::Value};
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    fn output(value: u64, quantity: u64) -> ExtOutput<(), (String, String)> {
+        let mut output = ExtOutput {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        output.insert_asset(("policy1".into(), "aname1".into()), quantity);
+        output
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_delta() {
+        let inputs = Delta::from(&output(5000, 10));
+        let outputs = Delta::from(&output(7000, 10));
+        let delta = inputs.checked_sub(&outputs).unwrap();
+
+        assert_eq!(delta.lovelace, -2000);
+        assert!(delta.assets.is_empty());
+        assert_eq!(delta.surplus(), Value::from(0));
+        assert_eq!(delta.deficit(), Value::from(2000));
+        assert!(delta.to_output::<()>().is_none());
+
+        let delta = delta.checked_add(&Delta::from(&output(2000, 3))).unwrap();
+        assert!(!delta.is_zero());
+        assert_eq!(delta.to_output(), Some(output(0, 3)));
+        assert!(delta.checked_sub(&delta).unwrap().is_zero());
+
+        let max = Delta {
+            lovelace: i128::MAX,
+            assets: BTreeMap::new(),
+        };
+        assert!(max.checked_add(&max).is_none());
+    }
+}
@@ -0,0 +1,167 @@
+/*!
+Draft transactions
+
+A draft describes what a transaction spends and produces before it is built.
+It is balanced when what it brings equals what it takes:
+inputs, mint and withdrawals are brought; outputs, the fee and deposits are taken.
+*/
+use crate::{
+    collect_js_outputs,
+    delta::{to_signed_amount, Delta, JsDelta},
+    get_amount, get_field, iterate, to_outputs, ErrorCode, JsOutput,
+};
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type Draft = {
+  inputs: Array<Output>
+  outputs: Array<Output>
+  mint?: Array<SignedAsset>
+  fee?: bigint
+  withdrawals?: bigint
+  deposits?: bigint
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Draft")]
+    pub type JsDraft;
+}
+
+/// What a draft consists of, in lovelace and signed assets.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Draft {
+    pub(crate) inputs: Delta,
+    pub(crate) outputs: Delta,
+    pub(crate) mint: Delta,
+    pub(crate) fee: u64,
+    pub(crate) withdrawals: u64,
+    pub(crate) deposits: u64,
+}
+
+impl Draft {
+    /// What it brings minus what it takes, `None` if overflowed.
+    pub(crate) fn imbalance(&self) -> Option<Delta> {
+        let lovelace = |amount: u64| Delta {
+            lovelace: amount.into(),
+            ..Delta::default()
+        };
+
+        self.inputs
+            .checked_add(&self.mint)?
+            .checked_add(&lovelace(self.withdrawals))?
+            .checked_sub(&self.outputs)?
+            .checked_sub(&lovelace(self.fee))?
+            .checked_sub(&lovelace(self.deposits))
+    }
+}
+
+/// The sum of the outputs as a delta.
+fn sum_outputs(js_outputs: &[JsOutput]) -> Result<Delta, ErrorCode> {
+    to_outputs(js_outputs)?
+        .iter()
+        .try_fold(Delta::default(), |sum, output| {
+            sum.checked_add(&output.into())
+                .ok_or(ErrorCode::OutputsOverflowed)
+        })
+}
+
+impl Draft {
+    /// Reads the draft, with its inputs and outputs.
+    pub(crate) fn from_js(
+        draft: &JsDraft,
+    ) -> Result<(Self, Vec<JsOutput>, Vec<JsOutput>), ErrorCode> {
+        let js_inputs = collect_js_outputs(get_field(draft, "inputs")?.unchecked_ref())?;
+        let js_outputs = collect_js_outputs(get_field(draft, "outputs")?.unchecked_ref())?;
+        let mut mint = Delta::default();
+        let js_mint = get_field(draft, "mint")?;
+
+        if !js_mint.is_undefined() {
+            for asset in iterate(&js_mint)? {
+                let get_string = |key| {
+                    get_field(&asset, key)?
+                        .as_string()
+                        .ok_or(ErrorCode::InvalidType)
+                };
+                let key = (get_string("policyId")?, get_string("assetName")?);
+                let quantity = to_signed_amount(&get_field(&asset, "quantity")?)?;
+                mint.add_asset(key, quantity)
+                    .ok_or(ErrorCode::OutputsOverflowed)?;
+            }
+        }
+
+        let draft = Self {
+            inputs: sum_outputs(&js_inputs)?,
+            outputs: sum_outputs(&js_outputs)?,
+            mint,
+            fee: get_amount(draft, "fee")?.unwrap_or_default(),
+            withdrawals: get_amount(draft, "withdrawals")?.unwrap_or_default(),
+            deposits: get_amount(draft, "deposits")?.unwrap_or_default(),
+        };
+
+        Ok((draft, js_inputs, js_outputs))
+    }
+}
+
+impl TryFrom<&JsDraft> for Draft {
+    type Error = ErrorCode;
+
+    fn try_from(draft: &JsDraft) -> Result<Self, Self::Error> {
+        Ok(Self::from_js(draft)?.0)
+    }
+}
+
+/**
+Compute by how much a draft transaction is unbalanced
+
+Returns what the inputs, mint and withdrawals bring minus what the outputs, fee and deposits take.
+Positive quantities are left over for change, and negative ones are missing from the inputs.
+Mint quantities are negative to burn. The draft is balanced when everything is zero.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = computeImbalance)]
+pub fn compute_imbalance(draft: &JsDraft) -> Result<JsDelta, JsError> {
+    let draft = Draft::try_from(draft)?;
+
+    Ok(draft
+        .imbalance()
+        .ok_or(ErrorCode::OutputsOverflowed)?
+        .to_js())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{delta::Delta, draft::Draft};
+    use std::collections::BTreeMap;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_imbalance() {
+        let token = ("policy1".to_string(), "aname1".to_string());
+        let delta = |lovelace, quantity| Delta {
+            lovelace,
+            assets: BTreeMap::from([(token.clone(), quantity)]),
+        };
+        let draft = Draft {
+            inputs: delta(10_000_000, 5),
+            outputs: delta(7_000_000, 8),
+            mint: delta(0, 2),
+            fee: 200_000,
+            withdrawals: 1_000_000,
+            deposits: 2_000_000,
+        };
+
+        assert_eq!(draft.imbalance(), Some(delta(1_800_000, -1)));
+
+        let balanced = Draft {
+            mint: delta(0, 3),
+            withdrawals: 0,
+            deposits: 2_800_000,
+            ..draft
+        };
+        assert!(balanced.imbalance().unwrap().is_zero());
+    }
+}
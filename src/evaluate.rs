@@ -0,0 +1,167 @@
+/*!
+What-if evaluation of selection
+
+Reactive UIs preview a selection every time the user changes the amount,
+and only need a few numbers of it, not the selected outputs.
+*/
+use crate::{
+    collect_js_outputs,
+    fee::{JsFeeParams, LinearFee, TxShape},
+    to_outputs, ErrorCode, JsOutput, JsOutputArray, Output,
+};
+use js_sys::{Object, Reflect};
+use utxo::try_sum;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type Evaluation = {
+  feasible: boolean
+  inputCount: number
+  excessLovelace: bigint
+  excessAssetCount: number
+  estimatedFee?: bigint
+  changeLovelace: bigint
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Evaluation")]
+    pub type Evaluation;
+}
+
+/**
+Evaluate the selection of UTxOs for the outputs
+
+Runs the same selection as `select`, but returns only the numbers of its result:
+whether it is `feasible`, the `inputCount`, the lovelace and the number of assets of the excess.
+When the fee parameters are given, the fee of the transaction with a change output
+is estimated from its size, and `changeLovelace` is the excess lovelace minus that fee,
+otherwise it is the excess lovelace.
+All the numbers are zero if the selection is not feasible.
+
+Raises errors when the types used are wrong or there is any value overflowed.
+*/
+#[wasm_bindgen]
+pub fn evaluate(
+    inputs: &JsOutputArray,
+    outputs: &JsOutputArray,
+    threshold: &JsOutput,
+    fee_params: Option<JsFeeParams>,
+) -> Result<Evaluation, JsError> {
+    let fee = fee_params.as_ref().map(LinearFee::try_from).transpose()?;
+    let js_inputs = collect_js_outputs(inputs)?;
+    let mut inputs = to_outputs(&js_inputs)?;
+    let js_outputs = collect_js_outputs(outputs)?;
+    let outputs = to_outputs(&js_outputs)?;
+    let threshold: Output = threshold.try_into()?;
+    let total_output = try_sum(&outputs).ok_or(ErrorCode::OutputsOverflowed)?;
+    let selection = utxo::select(&mut inputs[..], &total_output, &threshold)
+        .map(|(selected, _, excess)| (selected.len(), excess));
+
+    let result = Object::new();
+    let set = |key: &str, value: JsValue| {
+        Reflect::set(&result, &key.into(), &value).expect("Unreachable");
+    };
+
+    let (input_count, excess) = match selection {
+        Some((input_count, excess)) => (input_count, Some(excess)),
+        None => (0, None),
+    };
+    let excess_lovelace = excess.as_ref().map_or(0, |e| e.value);
+    let excess_asset_count = excess.as_ref().map_or(0, |e| e.assets.len());
+    let mut change_lovelace = excess_lovelace;
+
+    if let (Some(fee), Some(excess)) = (fee, excess.as_ref()) {
+        let assets = outputs.iter().map(|o| o.assets.len()).sum::<usize>()
+            + excess.assets.values().filter(|q| **q > 0).count();
+        let shape = TxShape {
+            inputs: input_count as u64,
+            outputs: outputs.len() as u64 + 1,
+            assets: assets as u64,
+            witnesses: input_count as u64,
+        };
+        let fee = fee.fee(shape.size()).ok_or(ErrorCode::OutputsOverflowed)?;
+
+        change_lovelace = excess_lovelace.saturating_sub(fee);
+        set("estimatedFee", fee.into());
+    }
+
+    set("feasible", excess.is_some().into());
+    set("inputCount", (input_count as u32).into());
+    set("excessLovelace", excess_lovelace.into());
+    set("excessAssetCount", (excess_asset_count as u32).into());
+    set("changeLovelace", change_lovelace.into());
+
+    Ok(result.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{evaluate::evaluate, JsOutput, JsOutputArray, Output};
+    use js_sys::{Array, Object, Reflect};
+    use std::collections::BTreeMap;
+    use utxo::Select;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn outputs(values: &[u64]) -> JsOutputArray {
+        let result = Array::new();
+
+        for value in values {
+            let output: JsOutput = Output {
+                value: *value,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into();
+            result.push(&output);
+        }
+
+        result.unchecked_into()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_evaluate() {
+        let inputs = outputs(&[3_000_000, 2_000_000, 1_000_000]);
+        let threshold: JsOutput = Output::zero().into();
+        let fee_params = Object::new();
+        Reflect::set(&fee_params, &"minFeeA".into(), &44u64.into()).unwrap();
+        Reflect::set(&fee_params, &"minFeeB".into(), &155381u64.into()).unwrap();
+
+        let evaluation = evaluate(
+            &inputs,
+            &outputs(&[4_000_000]),
+            &threshold,
+            Some(fee_params.unchecked_into()),
+        )
+        .unwrap();
+        let get = |key: &str| Reflect::get(&evaluation, &key.into()).unwrap();
+
+        assert_eq!(get("feasible"), true);
+        assert_eq!(get("inputCount"), 2);
+        assert_eq!(get("excessLovelace"), 1_000_000u64);
+        assert!(get("estimatedFee").is_bigint());
+
+        let evaluation = evaluate(&inputs, &outputs(&[7_000_000]), &threshold, None).unwrap();
+        let get = |key: &str| Reflect::get(&evaluation, &key.into()).unwrap();
+
+        assert_eq!(get("feasible"), false);
+        assert_eq!(get("inputCount"), 0);
+        assert!(get("estimatedFee").is_undefined());
+
+        let bad_params = Object::new();
+        Reflect::set(&bad_params, &"minFeeA".into(), &44u64.into()).unwrap();
+
+        assert!(evaluate(
+            &inputs,
+            &outputs(&[7_000_000]),
+            &threshold,
+            Some(bad_params.unchecked_into()),
+        )
+        .is_err());
+    }
+}
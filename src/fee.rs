@@ -0,0 +1,187 @@
+/*!
+Linear fee of transactions
+
+The minimum fee of a transaction is `minFeeA * size + minFeeB`,
+where the size is of the serialized transaction in bytes.
+Before a transaction is built its size can only be estimated from what it contains.
+*/
+use crate::{get_amount, get_count, ErrorCode};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type FeeParams = {
+  minFeeA: bigint
+  minFeeB: bigint
+}
+
+export type TxShape = {
+  inputs: number
+  outputs: number
+  assets?: number
+  witnesses?: number
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "FeeParams")]
+    pub type JsFeeParams;
+}
+
+/// The bytes of a transaction other than its inputs, outputs and witnesses.
+const TX_OVERHEAD_SIZE: u64 = 30;
+
+/// The bytes of an input: a transaction hash and an index.
+const INPUT_SIZE: u64 = 40;
+
+/// The bytes of an output of a base address and lovelace only.
+const OUTPUT_SIZE: u64 = 70;
+
+/// The bytes an asset adds to an output, assuming it has its own policy.
+const ASSET_SIZE: u64 = 45;
+
+/// The bytes of a verification key witness: a key and a signature.
+const WITNESS_SIZE: u64 = 101;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LinearFee {
+    pub(crate) coefficient: u64,
+    pub(crate) constant: u64,
+}
+
+impl LinearFee {
+    /// The fee of a transaction of the size, `None` if overflowed.
+    pub(crate) fn fee(&self, size: u64) -> Option<u64> {
+        self.coefficient
+            .checked_mul(size)?
+            .checked_add(self.constant)
+    }
+}
+
+impl LinearFee {
+    /// Reads `minFeeA` and `minFeeB` of the object.
+    pub(crate) fn from_js(value: &JsValue) -> Result<Self, ErrorCode> {
+        let get = |key| get_amount(value, key)?.ok_or(ErrorCode::InvalidType);
+
+        Ok(Self {
+            coefficient: get("minFeeA")?,
+            constant: get("minFeeB")?,
+        })
+    }
+}
+
+impl TryFrom<&JsFeeParams> for LinearFee {
+    type Error = ErrorCode;
+
+    fn try_from(value: &JsFeeParams) -> Result<Self, Self::Error> {
+        Self::from_js(value)
+    }
+}
+
+/// The bytes an input adds to a transaction, with its assets moved to the change.
+pub(crate) fn input_size(assets: u64) -> u64 {
+    INPUT_SIZE + assets * ASSET_SIZE
+}
+
+/// What a transaction contains, to estimate its size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct TxShape {
+    pub(crate) inputs: u64,
+    pub(crate) outputs: u64,
+    pub(crate) assets: u64,
+    pub(crate) witnesses: u64,
+}
+
+impl TxShape {
+    /// Reads the counts of the object, the witnesses one per input by default.
+    pub(crate) fn from_js(value: &JsValue) -> Result<Self, ErrorCode> {
+        let get = |key| get_count(value, key).map(|count| count.map(u64::from));
+        let inputs = get("inputs")?.ok_or(ErrorCode::InvalidType)?;
+
+        Ok(Self {
+            inputs,
+            outputs: get("outputs")?.ok_or(ErrorCode::InvalidType)?,
+            assets: get("assets")?.unwrap_or(0),
+            witnesses: get("witnesses")?.unwrap_or(inputs),
+        })
+    }
+
+    /// An upper estimate of the transaction size in bytes.
+    pub(crate) fn size(&self) -> u64 {
+        TX_OVERHEAD_SIZE
+            + self.inputs * INPUT_SIZE
+            + self.outputs * OUTPUT_SIZE
+            + self.assets * ASSET_SIZE
+            + self.witnesses * WITNESS_SIZE
+    }
+}
+
+/**
+Estimate the linear fee of a transaction
+
+The transaction is either its size in bytes, or a `TxShape` of the counts of its inputs,
+outputs, distinct assets and witnesses (default one per input) to estimate its size from.
+The fee is `minFeeA * size + minFeeB`, e.g. for the threshold passed to `select`.
+
+Raises errors when the types used are wrong, or the fee overflowed.
+*/
+#[wasm_bindgen(js_name = estimateFee)]
+pub fn estimate_fee(
+    #[wasm_bindgen(unchecked_param_type = "number | TxShape")] tx: &JsValue,
+    params: &JsFeeParams,
+) -> Result<u64, JsError> {
+    let size = match tx.as_f64() {
+        Some(size) if size >= 0.0 && size.fract() == 0.0 && size <= u32::MAX as f64 => size as u64,
+        Some(_) => return Err(ErrorCode::InvalidType.into()),
+        None => TxShape::from_js(tx)?.size(),
+    };
+
+    Ok(LinearFee::try_from(params)?
+        .fee(size)
+        .ok_or(ErrorCode::OutputsOverflowed)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fee::{estimate_fee, LinearFee, TxShape};
+    use js_sys::{Object, Reflect};
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_linear_fee() {
+        let fee = LinearFee {
+            coefficient: 44,
+            constant: 155381,
+        };
+        let shape = TxShape {
+            inputs: 1,
+            outputs: 2,
+            assets: 0,
+            witnesses: 1,
+        };
+
+        assert_eq!(shape.size(), 311);
+        assert_eq!(fee.fee(shape.size()), Some(44 * 311 + 155381));
+        assert_eq!(fee.fee(u64::MAX), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_estimate_fee() {
+        let params = Object::new();
+        Reflect::set(&params, &"minFeeA".into(), &44u64.into()).unwrap();
+        Reflect::set(&params, &"minFeeB".into(), &155381u64.into()).unwrap();
+        let params = params.unchecked_into();
+        let shape = Object::new();
+        Reflect::set(&shape, &"inputs".into(), &1.into()).unwrap();
+        Reflect::set(&shape, &"outputs".into(), &2.into()).unwrap();
+
+        assert_eq!(
+            estimate_fee(&JsValue::from(311), &params).unwrap(),
+            44 * 311 + 155381
+        );
+        assert_eq!(estimate_fee(&shape, &params).unwrap(), 44 * 311 + 155381);
+        assert!(estimate_fee(&JsValue::from(-1), &params).is_err());
+    }
+}
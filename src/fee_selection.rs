@@ -0,0 +1,274 @@
+/*!
+Selection paying its own fee
+
+The fee of a transaction depends on its inputs and change output, which depend on the fee.
+Selection is made for the outputs plus an estimated fee, the fee of the transaction
+of the selected inputs and change is estimated, and selection is made again for that
+until the fee selected for covers the estimate.
+*/
+use crate::{
+    build_result, collect_js_outputs,
+    fee::TxShape,
+    options::{JsSelectOptions, MinChange, SelectOptions},
+    protocol::{JsProtocolParameters, ProtocolParameters},
+    raise,
+    selection::{select_with_threshold, Threshold, Thresholded},
+    sort_inputs, spendable_inputs, to_outputs, view, ErrorCode, ErrorKind, Failure, JsOutputArray,
+    Output,
+};
+use js_sys::Reflect;
+use utxo::{try_sum, ExtOutput, Select};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type FeeSelectResult = SelectResult & {
+  fee: bigint
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(extends = crate::SelectResult, typescript_type = "FeeSelectResult")]
+    pub type FeeSelectResult;
+}
+
+/// The times the fee is estimated again before giving up.
+const FEE_ATTEMPTS: usize = 8;
+
+/// The inputs the threshold of protocol parameters is estimated for, unless `maxInputs` is less.
+const AUTO_THRESHOLD_INPUTS: u64 = 8;
+
+fn lovelace<'o>(value: u64) -> Output<'o> {
+    ExtOutput {
+        value,
+        assets: Default::default(),
+        data: None,
+    }
+}
+
+/**
+Selects for the output, the sum of the number of `outputs`, plus the fee of the transaction.
+
+Returns the selection, whose excess covers the fee, and the fee.
+Returns `None` if the inputs are not enough, or the fee never settles.
+*/
+pub(crate) fn select_paying_fee<'o>(
+    inputs: Vec<Output<'o>>,
+    output: &Output<'o>,
+    outputs: u64,
+    params: &ProtocolParameters,
+    options: &SelectOptions,
+) -> Option<(Thresholded<'o>, u64)> {
+    let assets = output.assets.len() as u64;
+    let shape = |inputs: u64, change_assets: Option<u64>| TxShape {
+        inputs,
+        outputs: outputs + change_assets.map_or(0, |_| 1),
+        assets: assets + change_assets.unwrap_or(0),
+        witnesses: inputs,
+    };
+    let mut fee = params.fee.fee(shape(1, None).size())?;
+
+    for _ in 0..FEE_ATTEMPTS {
+        let threshold = Threshold::Output(lovelace(fee));
+        let thresholded = select_with_threshold(inputs.clone(), output, &threshold, options)?;
+        let change = thresholded.selection.excess.saturating_sub(&lovelace(fee));
+        let change_assets = change.assets.values().filter(|q| **q > 0).count() as u64;
+        let has_change = change.value > thresholded.donated || change_assets > 0;
+        let selected = thresholded.selection.selected.len() as u64;
+        let estimated = params
+            .fee
+            .fee(shape(selected, has_change.then_some(change_assets)).size())?;
+
+        if estimated <= fee {
+            return Some((thresholded, fee));
+        }
+
+        fee = estimated;
+    }
+
+    None
+}
+
+/**
+The threshold of the fee estimated for the outputs and a change output, passed protocol
+parameters instead of a threshold.
+
+The fee is of a transaction spending `AUTO_THRESHOLD_INPUTS`, or `max_inputs` if fewer,
+so it covers the fee of most selections, and the change has the minimum lovelace
+of its assets as `minChange`, unless set.
+Returns `None` if the fee overflowed.
+*/
+pub(crate) fn auto_threshold<'o>(
+    outputs: &[Output<'o>],
+    params: &ProtocolParameters,
+    options: &mut SelectOptions,
+) -> Option<Threshold<'o>> {
+    options.min_change.get_or_insert(MinChange::MinAda {
+        coins_per_utxo_byte: params.coins_per_utxo_byte,
+    });
+    let inputs = options.max_inputs.map_or(AUTO_THRESHOLD_INPUTS, |max| {
+        AUTO_THRESHOLD_INPUTS.min(max as u64)
+    });
+    let shape = TxShape {
+        inputs,
+        outputs: outputs.len() as u64 + 1,
+        assets: outputs
+            .iter()
+            .map(|output| output.assets.len() as u64)
+            .sum(),
+        witnesses: inputs,
+    };
+
+    Some(Threshold::Output(lovelace(params.fee.fee(shape.size())?)))
+}
+
+/**
+Select UTxOs for the outputs and the fee of the transaction
+
+Works as `select` with the same options, where the threshold is the fee estimated
+from the protocol parameters for the transaction of the outputs, the selected UTxOs
+and a change output if there is one. Selection is made again while the fee rises with
+the UTxOs and change selected, instead of repeating `select` in JS for each estimate.
+Non-zero change must have the minimum lovelace of its assets unless `minChange` is set.
+The change merged by `mergeChangeInto` is estimated as a change output.
+
+Returns the result of `select` with the `fee` its excess covers, the rest being the change.
+Returns nothing if the inputs are not enough, or the fee does not settle in a few attempts.
+
+Raises the errors `select` raises, and `BAD_OPTIONS` for `alternatives` and
+`splitOversizedChange`, whose transactions the fee is not estimated for.
+*/
+#[wasm_bindgen(js_name = selectWithFee)]
+pub fn select_with_fee(
+    inputs: &JsOutputArray,
+    outputs: &JsOutputArray,
+    params: &JsProtocolParameters,
+    options: Option<JsSelectOptions>,
+) -> Result<Option<FeeSelectResult>, JsError> {
+    let params = ProtocolParameters::try_from(params)?;
+    let mut options = SelectOptions::try_from(options.as_ref())?;
+    if options.alternatives > 0 || options.split_oversized_change.is_some() {
+        return Err(raise(
+            ErrorKind::BadOptions,
+            "The fee is not estimated for alternatives or splitOversizedChange",
+        ));
+    }
+    options.min_change = options.min_change.or(Some(MinChange::MinAda {
+        coins_per_utxo_byte: params.coins_per_utxo_byte,
+    }));
+    let js_inputs =
+        collect_js_outputs(inputs).map_err(|code| Failure::from(code).within("inputs"))?;
+    let (js_inputs, reference_inputs) = spendable_inputs(js_inputs, &options)?;
+    let mut inputs = to_outputs(&js_inputs).map_err(|failure| failure.within("inputs"))?;
+    sort_inputs(&mut inputs, &options)?;
+    let in_outputs = |failure: Failure| failure.within("outputs");
+    let js_outputs = collect_js_outputs(outputs).map_err(|code| in_outputs(code.into()))?;
+    let outputs = to_outputs(&js_outputs).map_err(in_outputs)?;
+    let total_output = try_sum(&outputs).ok_or(ErrorCode::OutputsOverflowed)?;
+
+    let Some((thresholded, fee)) = select_paying_fee(
+        inputs.clone(),
+        &total_output,
+        outputs.len() as u64,
+        &params,
+        &options,
+    ) else {
+        return Ok(None);
+    };
+
+    let result = build_result(
+        thresholded,
+        &inputs,
+        &outputs,
+        &total_output,
+        &reference_inputs,
+        &options,
+    )?;
+    Reflect::set(&result, &"fee".into(), &fee.into()).expect("Unreachable");
+
+    if options.transferable {
+        return Ok(Some(view::transferable(&result)?.unchecked_into()));
+    }
+
+    Ok(Some(result.unchecked_into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fee::TxShape,
+        fee_selection::{auto_threshold, select_paying_fee},
+        min_ada::min_ada,
+        options::{MinChange, SelectOptions},
+        protocol::tests::mainnet,
+        selection::Threshold,
+        Output,
+    };
+    use std::collections::BTreeMap;
+    use wasm_bindgen_test::*;
+
+    fn output(value: u64) -> Output<'static> {
+        Output {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_select_paying_fee() {
+        let params = mainnet();
+        let inputs: Vec<Output> = (1..=10).map(|i| output(i * 400_000)).collect();
+        let options = SelectOptions {
+            min_change: Some(MinChange::MinAda {
+                coins_per_utxo_byte: params.coins_per_utxo_byte,
+            }),
+            ..SelectOptions::default()
+        };
+
+        let (thresholded, fee) =
+            select_paying_fee(inputs.clone(), &output(9_000_000), 1, &params, &options).unwrap();
+        let selection = thresholded.selection;
+        let shape = TxShape {
+            inputs: selection.selected.len() as u64,
+            outputs: 2,
+            assets: 0,
+            witnesses: selection.selected.len() as u64,
+        };
+        assert!(fee >= params.fee.fee(shape.size()).unwrap());
+        assert!(selection.excess.value >= fee);
+        let change = selection.excess.value - fee;
+        assert!(change == 0 || change >= min_ada(&output(change), params.coins_per_utxo_byte));
+
+        assert!(select_paying_fee(inputs, &output(22_000_000), 1, &params, &options).is_none());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_auto_threshold() {
+        let params = mainnet();
+        let mut options = SelectOptions {
+            max_inputs: Some(2),
+            ..SelectOptions::default()
+        };
+        let shape = TxShape {
+            inputs: 2,
+            outputs: 2,
+            assets: 0,
+            witnesses: 2,
+        };
+
+        let Some(Threshold::Output(threshold)) =
+            auto_threshold(&[output(9_000_000)], &params, &mut options)
+        else {
+            panic!("The threshold should be an output");
+        };
+        assert_eq!(threshold.value, params.fee.fee(shape.size()).unwrap());
+        assert_eq!(
+            options.min_change,
+            Some(MinChange::MinAda {
+                coins_per_utxo_byte: params.coins_per_utxo_byte
+            })
+        );
+    }
+}
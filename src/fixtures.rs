@@ -0,0 +1,263 @@
+/*!
+Deterministic UTxO fixtures
+
+Synthetic UTxO sets for tests and benchmarks, reproducible from a seed.
+*/
+use crate::{
+    get_amount, get_count, get_field, iterate, out_point::OutPoint, raise, rng::Rng, ErrorCode,
+    ErrorKind, JsOutput, JsOutputArray,
+};
+use js_sys::Array;
+use std::collections::BTreeMap;
+use utxo::ExtOutput;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type AssetProfile = {
+  policyId: string
+  assetName: string
+  probability: number
+  minQuantity: bigint
+  maxQuantity: bigint
+}
+
+export type FixtureOptions = {
+  count: number
+  seed: bigint
+  assetProfiles?: Array<AssetProfile>
+  minLovelace?: bigint
+  maxLovelace?: bigint
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "FixtureOptions")]
+    pub type JsFixtureOptions;
+}
+
+/// The error of too many UTxOs to generate.
+pub(crate) fn out_of_memory() -> JsError {
+    raise(ErrorKind::OutOfMemory, "Cannot allocate the UTxOs")
+}
+
+/// A generated UTxO, its data is the transaction hash and output index.
+pub(crate) type Fixture = ExtOutput<(String, u32), (String, String)>;
+
+/// The chance of a UTxO to hold an asset, with the range of its quantity.
+pub(crate) struct AssetProfile {
+    pub(crate) policy_id: String,
+    pub(crate) asset_name: String,
+    pub(crate) probability: f64,
+    pub(crate) min_quantity: u64,
+    pub(crate) max_quantity: u64,
+}
+
+pub(crate) struct FixtureOptions {
+    pub(crate) count: usize,
+    pub(crate) seed: u64,
+    pub(crate) asset_profiles: Vec<AssetProfile>,
+    pub(crate) min_lovelace: u64,
+    pub(crate) max_lovelace: u64,
+}
+
+impl FixtureOptions {
+    /// 1 ADA, a little less than the minimum of a pure ADA output.
+    pub(crate) const MIN_LOVELACE: u64 = 1_000_000;
+
+    /// 100,000 ADA.
+    pub(crate) const MAX_LOVELACE: u64 = 100_000_000_000;
+}
+
+impl TryFrom<&JsFixtureOptions> for FixtureOptions {
+    type Error = JsError;
+
+    fn try_from(value: &JsFixtureOptions) -> Result<Self, Self::Error> {
+        let string = |profile: &JsValue, key: &str| {
+            get_field(profile, key)?
+                .as_string()
+                .ok_or(ErrorCode::InvalidType)
+        };
+        let required =
+            |target: &JsValue, key: &str| get_amount(target, key)?.ok_or(ErrorCode::InvalidType);
+        let profiles = get_field(value, "assetProfiles")?;
+        let profiles = match profiles.is_undefined() {
+            true => Vec::new(),
+            false => iterate(&profiles)?,
+        };
+        let mut asset_profiles = Vec::new();
+
+        for profile in profiles.iter() {
+            let profile = AssetProfile {
+                policy_id: string(profile, "policyId")?,
+                asset_name: string(profile, "assetName")?,
+                probability: get_field(profile, "probability")?
+                    .as_f64()
+                    .ok_or(ErrorCode::InvalidType)?,
+                min_quantity: required(profile, "minQuantity")?,
+                max_quantity: required(profile, "maxQuantity")?,
+            };
+
+            if !(0.0..=1.0).contains(&profile.probability) {
+                return Err(raise(
+                    ErrorKind::BadOptions,
+                    "Asset probability should be between 0 and 1",
+                ));
+            }
+            if profile.min_quantity == 0 || profile.min_quantity > profile.max_quantity {
+                return Err(raise(ErrorKind::BadOptions, "Invalid asset quantity range"));
+            }
+
+            asset_profiles.push(profile);
+        }
+
+        let options = Self {
+            count: get_count(value, "count")?.ok_or(ErrorCode::InvalidType)? as usize,
+            seed: required(value, "seed")?,
+            asset_profiles,
+            min_lovelace: get_amount(value, "minLovelace")?.unwrap_or(Self::MIN_LOVELACE),
+            max_lovelace: get_amount(value, "maxLovelace")?.unwrap_or(Self::MAX_LOVELACE),
+        };
+
+        if options.min_lovelace == 0 || options.min_lovelace > options.max_lovelace {
+            return Err(raise(ErrorKind::BadOptions, "Invalid lovelace range"));
+        }
+
+        Ok(options)
+    }
+}
+
+/**
+Generates UTxOs from the options.
+
+The lovelace is log-uniformly distributed in the range, so there are many
+small UTxOs and few large ones like in a real wallet.
+Returns `None` if the memory for `count` UTxOs cannot be allocated.
+*/
+pub(crate) fn generate(options: &FixtureOptions) -> Option<Vec<Fixture>> {
+    let mut fixtures = Vec::new();
+    fixtures.try_reserve_exact(options.count).ok()?;
+    let mut rng = Rng::new(options.seed);
+    let (min, max) = (
+        (options.min_lovelace as f64).ln(),
+        (options.max_lovelace as f64).ln(),
+    );
+
+    fixtures.extend((0..options.count).map(|_| {
+        let hash: String = (0..4).map(|_| format!("{:016x}", rng.next_u64())).collect();
+        let index = rng.below(4) as u32;
+        let lovelace = (min + (max - min) * rng.next_f64()).exp() as u64;
+        let mut fixture = Fixture {
+            value: lovelace.clamp(options.min_lovelace, options.max_lovelace),
+            assets: BTreeMap::new(),
+            data: Some((hash, index)),
+        };
+
+        for profile in options.asset_profiles.iter() {
+            if rng.next_f64() < profile.probability {
+                let quantity = rng.between(profile.min_quantity, profile.max_quantity);
+                fixture.insert_asset(
+                    (profile.policy_id.clone(), profile.asset_name.clone()),
+                    quantity,
+                );
+            }
+        }
+
+        fixture
+    }));
+
+    Some(fixtures)
+}
+
+/**
+Generate synthetic UTxOs deterministically
+
+Returns `count` outputs, the same for the same options.
+The `data` of each output is the `OutPoint` of a random transaction hash.
+Each asset profile is held by a UTxO with its `probability`,
+in a quantity between `minQuantity` and `maxQuantity`.
+The lovelace is between `minLovelace` (default 1 ADA) and `maxLovelace` (default 100,000 ADA),
+with small UTxOs more common than large ones.
+
+Raises errors when the options are invalid, or the memory for `count` UTxOs cannot be allocated.
+*/
+#[wasm_bindgen(js_name = generateUtxos)]
+pub fn generate_utxos(options: &JsFixtureOptions) -> Result<JsOutputArray, JsError> {
+    let options: FixtureOptions = options.try_into()?;
+    let result = Array::new();
+
+    for mut fixture in generate(&options).ok_or_else(out_of_memory)? {
+        let (hash, index) = fixture.data.take().expect("Unreachable");
+        let output = ExtOutput {
+            value: fixture.value,
+            assets: fixture.assets,
+            data: None,
+        };
+        let output: JsOutput = output.into();
+        let out_point = OutPoint { hash, index };
+        output.set_data(&out_point.to_js());
+        result.push(&output);
+    }
+
+    Ok(result.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{generate, generate_utxos, AssetProfile, FixtureOptions};
+    use js_sys::{Array, Object, Reflect};
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_test::*;
+
+    fn options(seed: u64) -> FixtureOptions {
+        FixtureOptions {
+            count: 100,
+            seed,
+            asset_profiles: vec![AssetProfile {
+                policy_id: "policy1".into(),
+                asset_name: "aname1".into(),
+                probability: 0.5,
+                min_quantity: 1,
+                max_quantity: 1000,
+            }],
+            min_lovelace: FixtureOptions::MIN_LOVELACE,
+            max_lovelace: FixtureOptions::MAX_LOVELACE,
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_generate_fixtures() {
+        let fixtures = generate(&options(7)).unwrap();
+
+        assert_eq!(fixtures.len(), 100);
+        assert_eq!(Some(&fixtures), generate(&options(7)).as_ref());
+        assert_ne!(Some(&fixtures), generate(&options(8)).as_ref());
+
+        let with_asset = fixtures.iter().filter(|f| !f.assets.is_empty()).count();
+        assert!(with_asset > 20 && with_asset < 80);
+
+        for fixture in fixtures.iter() {
+            assert!(fixture.value >= FixtureOptions::MIN_LOVELACE);
+            assert!(fixture.value <= FixtureOptions::MAX_LOVELACE);
+            assert_eq!(fixture.data.as_ref().unwrap().0.len(), 64);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_generate_utxos_options() {
+        let options = Object::new();
+        Reflect::set(&options, &"count".into(), &3.into()).unwrap();
+        Reflect::set(&options, &"seed".into(), &7.into()).unwrap();
+        let utxos: Array = generate_utxos(options.unchecked_ref())
+            .unwrap()
+            .unchecked_into();
+        assert_eq!(utxos.length(), 3);
+
+        Reflect::set(&options, &"seed".into(), &JsValue::from(-1)).unwrap();
+        assert!(generate_utxos(options.unchecked_ref()).is_err());
+        Reflect::set(&options, &"seed".into(), &JsValue::from(7u64)).unwrap();
+        Reflect::set(&options, &"count".into(), &"3".into()).unwrap();
+        assert!(generate_utxos(options.unchecked_ref()).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+/*!
+Health check of wallets
+
+A wallet accumulates UTxOs that are costly or impossible to spend over time.
+The check finds them, so wallets can suggest what to consolidate or split.
+*/
+use crate::{
+    collateral, collect_js_outputs,
+    fee::input_size,
+    min_ada::{min_ada, value_size},
+    protocol::{JsProtocolParameters, ProtocolParameters},
+    to_outputs, JsOutputArray,
+};
+use js_sys::{Array, Object, Reflect};
+use utxo::ExtOutput;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type HealthReport = {
+  utxoCount: number
+  unspendable: Array<Output>
+  collateral: Array<Output>
+  dust: Array<Output>
+  dustRatio: number
+  oversized: Array<Output>
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "HealthReport")]
+    pub type HealthReport;
+}
+
+/// The UTxO is dust if its lovelace is less than this times its minimum lovelace.
+const DUST_FACTOR: u64 = 2;
+
+/// The UTxO is oversized if its value is larger than this percent of the maximum value size.
+const OVERSIZED_PERCENT: u64 = 80;
+
+/// The indices of the UTxOs found by the check.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Health {
+    pub(crate) unspendable: Vec<usize>,
+    pub(crate) collateral: Vec<usize>,
+    pub(crate) dust: Vec<usize>,
+    pub(crate) oversized: Vec<usize>,
+}
+
+pub(crate) fn check<D>(
+    utxos: &[ExtOutput<D, (String, String)>],
+    params: &ProtocolParameters,
+) -> Health {
+    let mut health = Health::default();
+
+    for (index, utxo) in utxos.iter().enumerate() {
+        let min = min_ada(utxo, params.coins_per_utxo_byte);
+
+        if is_unspendable(utxo, params) {
+            health.unspendable.push(index);
+        } else if utxo.value < min.saturating_mul(DUST_FACTOR) {
+            health.dust.push(index);
+        }
+
+        if collateral::check(utxo, collateral::MIN_LOVELACE, collateral::MAX_LOVELACE).is_ok() {
+            health.collateral.push(index);
+        }
+
+        if value_size(utxo).saturating_mul(100)
+            > params.max_value_size.saturating_mul(OVERSIZED_PERCENT)
+        {
+            health.oversized.push(index);
+        }
+    }
+
+    health
+}
+
+/**
+Whether spending the UTxO costs more than it brings.
+
+Its lovelace does not pay the fee of including it, or it holds assets
+but not the minimum lovelace of an output of its own.
+*/
+pub(crate) fn is_unspendable<D>(
+    utxo: &ExtOutput<D, (String, String)>,
+    params: &ProtocolParameters,
+) -> bool {
+    let marginal_fee = params
+        .fee
+        .coefficient
+        .saturating_mul(input_size(utxo.assets.len() as u64));
+
+    utxo.value <= marginal_fee
+        || (!utxo.assets.is_empty() && utxo.value < min_ada(utxo, params.coins_per_utxo_byte))
+}
+
+/**
+Find the UTxOs that cannot be spent economically
+
+A UTxO is unspendable if its lovelace does not pay the fee of including it in a transaction,
+or it holds assets but less than the minimum lovelace of an output of its own.
+Pass the parameters as `excludeUnspendable` to `select` to leave them out of selection.
+
+Returns the same objects of the UTxOs.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = findUnspendable)]
+pub fn find_unspendable(
+    utxos: &JsOutputArray,
+    params: &JsProtocolParameters,
+) -> Result<JsOutputArray, JsError> {
+    let js_utxos = collect_js_outputs(utxos)?;
+    let outputs = to_outputs(&js_utxos)?;
+    let params = params.try_into()?;
+
+    Ok(js_utxos
+        .iter()
+        .zip(outputs.iter())
+        .filter(|(_, output)| is_unspendable(output, &params))
+        .map(|(utxo, _)| utxo)
+        .collect::<Array>()
+        .unchecked_into())
+}
+
+/**
+Check the health of the UTxOs of a wallet
+
+Reports the UTxOs of the wallet that:
+
+- `unspendable`: cost more to spend than they bring, the same as `findUnspendable` finds.
+- `collateral`: are suitable as collateral, see `classifyCollateral`.
+- `dust`: hold less than twice their minimum lovelace, so most of their lovelace is locked.
+- `oversized`: have values close to the maximum value size, so they can hardly take more assets.
+
+`dustRatio` is the number of the dust UTxOs over `utxoCount`, zero for no UTxOs.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = healthCheck)]
+pub fn health_check(
+    utxos: &JsOutputArray,
+    params: &JsProtocolParameters,
+) -> Result<HealthReport, JsError> {
+    let js_utxos = collect_js_outputs(utxos)?;
+    let outputs = to_outputs(&js_utxos)?;
+    let health = check(&outputs, &params.try_into()?);
+
+    let result = Object::new();
+    let set = |key: &str, value: &JsValue| {
+        Reflect::set(&result, &key.into(), value).expect("Unreachable");
+    };
+    let pick = |indices: &[usize]| -> Array { indices.iter().map(|i| &js_utxos[*i]).collect() };
+    let dust_ratio = if js_utxos.is_empty() {
+        0.0
+    } else {
+        health.dust.len() as f64 / js_utxos.len() as f64
+    };
+
+    set("utxoCount", &js_utxos.len().into());
+    set("unspendable", &pick(&health.unspendable));
+    set("collateral", &pick(&health.collateral));
+    set("dust", &pick(&health.dust));
+    set("dustRatio", &dust_ratio.into());
+    set("oversized", &pick(&health.oversized));
+
+    Ok(result.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        health::{check, is_unspendable, Health},
+        protocol::tests::mainnet,
+    };
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    fn utxo(value: u64) -> ExtOutput<(), (String, String)> {
+        ExtOutput {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_is_unspendable() {
+        let params = mainnet();
+        assert!(is_unspendable(&utxo(44 * 40), &params));
+        assert!(!is_unspendable(&utxo(44 * 40 + 1), &params));
+
+        let mut token = utxo(2_000_000);
+        token.insert_asset(("ab".repeat(28), "cd".repeat(4)), 1);
+        assert!(!is_unspendable(&token, &params));
+        token.value = 500_000;
+        assert!(is_unspendable(&token, &params));
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_health_check() {
+        let mut bundle = utxo(100_000_000);
+        for i in 0..100 {
+            bundle.insert_asset((format!("{:056x}", i), "cd".repeat(32)), 1);
+        }
+
+        let utxos = vec![
+            utxo(1000),
+            utxo(1_500_000),
+            utxo(10_000_000),
+            bundle,
+            utxo(500_000),
+        ];
+
+        // Below its minimum, pure ADA still pays the fee of spending it.
+        assert_eq!(
+            check(&utxos, &mainnet()),
+            Health {
+                unspendable: vec![0],
+                collateral: vec![2],
+                dust: vec![1, 4],
+                oversized: vec![3],
+            }
+        );
+    }
+}
@@ -3,14 +3,86 @@ UTxO helpers for Cardano in WASM
 
 This package wraps UTxO helpers written in Rust into WASM
 so that they can be used by Nodejs and the browsers.
+
+The owned types in [`scenario`] are for selecting from Rust, e.g. with
+[`scenario::select_indices`]. With the `serde` feature, they can be serialized
+to snapshot and replay selections.
+
+The other features are enabled by default, and can be disabled for a smaller WASM:
+
+- `adapters`: conversion from the UTxOs of other tools, see [`adapters`].
+- `cbor`: decoding of the UTxOs from CIP-30 wallets and CBOR of values, see [`cbor`].
+- `fee`: fee and minimum lovelace math, and the planners built on them.
+- `fixtures`: generation of synthetic UTxOs, and benchmarks on them.
 */
-use js_sys::{try_iter, Array, Object};
-use std::collections::BTreeMap;
+#[cfg(feature = "adapters")]
+pub mod adapters;
+#[cfg(feature = "cbor")]
+pub mod address;
+#[cfg(feature = "fee")]
+pub mod balance;
+#[cfg(feature = "fixtures")]
+pub mod bench;
+#[cfg(feature = "fee")]
+pub mod bundle;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod change;
+pub mod chunked;
+pub mod cli;
+mod clock;
+pub mod collateral;
+pub mod delta;
+pub mod draft;
+#[cfg(feature = "fee")]
+pub mod evaluate;
+#[cfg(feature = "fee")]
+pub mod fee;
+#[cfg(feature = "fee")]
+pub mod fee_selection;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(feature = "fee")]
+pub mod health;
+#[cfg(feature = "fee")]
+pub mod min_ada;
+pub mod options;
+pub mod out_point;
+mod profile;
+#[cfg(feature = "fee")]
+pub mod protocol;
+#[cfg(feature = "fee")]
+pub mod reshape;
+mod rng;
+pub mod scenario;
+mod selection;
+pub mod skeleton;
+#[cfg(feature = "fee")]
+pub mod split;
+pub mod strategy;
+#[cfg(feature = "fee")]
+pub mod sweep;
+mod total;
+pub mod validate;
+pub mod value;
+pub mod view;
+
+use js_sys::{try_iter, Array, Object, Reflect};
+use options::{DustTokens, JsSelectOptions, SelectOptions, Strategy};
+use profile::Profiler;
+use rng::Rng;
+use selection::{Selection, Threshold};
+use std::{
+    alloc::Layout,
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+};
+use total::Total;
 use utxo::{try_sum, ExtOutput};
 use wasm_bindgen::{prelude::*, JsCast};
 
 #[wasm_bindgen(typescript_custom_section)]
-const TS_APPEND_CONTENT: &'static str = r#"
+const TS_APPEND_CONTENT: &str = r#"
 export type Asset = {
   policyId: string
   assetName: string
@@ -20,13 +92,96 @@ export type Asset = {
 export type Output = {
   lovelace: bigint
   assets: Array<Asset>
+  address?: string
+  slot?: bigint
+  datumHash?: string
+  datum?: string
+  scriptRef?: string
   data?: any
 }
 
+export type OutPoint = {
+  hash: string
+  index: number
+}
+
+export type ErrorKind =
+  | "OUTPUT_OVERFLOW"
+  | "INSUFFICIENT_INPUT"
+  | "BAD_AMOUNT"
+  | "UNSAFE_NUMBER"
+  | "BAD_TYPE"
+  | "TOO_MANY_INPUTS"
+  | "DUPLICATE_INPUT"
+  | "BAD_ASSET"
+  | "BAD_HEX"
+  | "BAD_CBOR"
+  | "BAD_JSON"
+  | "BAD_RESPONSE"
+  | "BAD_ADDRESS"
+  | "BAD_OUTPOINT"
+  | "MISSING_OUTPOINT"
+  | "MISSING_ADDRESS"
+  | "BAD_OPTIONS"
+  | "UNSPLITTABLE"
+  | "OUT_OF_MEMORY"
+
+export type UtxoError = Error & {
+  code: ErrorKind
+  detail?:
+    | { field: string }
+    | { message: string, offset: number }
+    | { lovelace: bigint, assets: Record<string, bigint> }
+}
+
 export type SelectResult = {
   selected: Array<Output>
   unselected: Array<Output>
   excess: Output
+  dustTokens: Array<Asset>
+  alternatives?: Array<SelectResult>
+  donated?: bigint
+  seed?: bigint
+  timings?: Timings
+  referenceInputs?: Array<Output>
+  changeOutputs?: Array<Output>
+  outputs?: Array<Output>
+}
+
+export type SelectRequest = SelectOptions & {
+  inputs: Iterable<Output>
+  outputs: Iterable<Output>
+  threshold: Threshold
+}
+
+export type SelectOutcome =
+  | ({ ok: true } & SelectResult)
+  | { ok: false, code: ErrorKind, details: string, field?: string, shortfall?: Output }
+
+export type Threshold =
+  | Output
+  | { feeReserve: bigint, minChange: bigint }
+  | AutoThreshold
+
+export type Target = {
+  outputs: Array<Output>
+  threshold: Output
+}
+
+export type Pool = {
+  tag: string
+  utxos: Array<Output>
+  maxLovelace?: bigint
+}
+
+export type PoolSelection = {
+  tag: string
+  selected: Array<Output>
+  total: Output
+}
+
+export type PoolsResult = SelectResult & {
+  pools: Array<PoolSelection>
 }
 "#;
 
@@ -47,9 +202,6 @@ extern "C" {
     #[wasm_bindgen(method, setter = assetName)]
     fn set_asset_name(this: &JsAsset, asset_name: &str);
 
-    #[wasm_bindgen(method, getter)]
-    fn quantity(this: &JsAsset) -> u64;
-
     #[wasm_bindgen(method, setter)]
     fn set_quantity(this: &JsAsset, quantity: u64);
 
@@ -57,8 +209,11 @@ extern "C" {
     type JsAssetArray;
 
     #[wasm_bindgen(typescript_type = "Output")]
+    #[derive(Clone)]
     pub type JsOutput;
 
+    // Wraps amounts beyond 64 bits, so amounts are read by `to_amount` instead.
+    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
     fn lovelace(this: &JsOutput) -> u64;
 
@@ -71,6 +226,12 @@ extern "C" {
     #[wasm_bindgen(method, setter)]
     fn set_assets(this: &JsOutput, assets: &JsAssetArray);
 
+    #[wasm_bindgen(method, setter)]
+    fn set_address(this: &JsOutput, address: &str);
+
+    #[wasm_bindgen(method, setter)]
+    fn set_data(this: &JsOutput, data: &JsValue);
+
     #[wasm_bindgen(typescript_type = "Array<Output>")]
     pub type JsOutputArray;
 
@@ -94,10 +255,280 @@ extern "C" {
 
     #[wasm_bindgen(method, setter)]
     fn set_excess(this: &SelectResult, excess: &JsOutput);
+
+    #[wasm_bindgen(typescript_type = "SelectOutcome")]
+    pub type SelectOutcome;
+
+    #[wasm_bindgen(typescript_type = "SelectRequest")]
+    pub type SelectRequest;
+
+    #[wasm_bindgen(typescript_type = "Array<SelectResult | undefined>")]
+    pub type SelectResultArray;
+
+    #[wasm_bindgen(typescript_type = "Target")]
+    pub type JsTarget;
+
+    #[wasm_bindgen(typescript_type = "Array<Target>")]
+    pub type JsTargetArray;
+
+    #[wasm_bindgen(typescript_type = "Array<Pool>")]
+    pub type JsPoolArray;
+
+    #[wasm_bindgen(extends = SelectResult, typescript_type = "PoolsResult")]
+    pub type PoolsResult;
+
+    #[wasm_bindgen(js_namespace = console)]
+    fn warn(message: &str);
 }
 
 pub type Output<'o> = ExtOutput<&'o JsOutput, (String, String)>;
 
+impl JsOutput {
+    /// The `address` of the output, `None` if it is not a string.
+    fn address(&self) -> Option<String> {
+        get_field(self, "address").ok()?.as_string()
+    }
+}
+
+/// Codes of the failures of a selection, raised as the `code` of their `ErrorKind`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// A sum of the lovelace or a quantity overflowed 64 bits.
+    OutputsOverflowed = 0,
+    /// The inputs are not enough for the outputs plus threshold.
+    InsufficientInputs = 1,
+    /// A lovelace or quantity is not a non-negative integer within 64 bits.
+    InvalidAmount = 2,
+    /// A lovelace or quantity is a number above `Number.MAX_SAFE_INTEGER` in the strict BigInt mode.
+    UnsafeNumber = 3,
+    /// A value is not of the type expected, e.g. an output that is not an object.
+    InvalidType = 4,
+    /// The inputs are enough for the outputs plus threshold only beyond `maxInputs`.
+    TooManyInputs = 5,
+    /// An input is of the same `OutPoint` as another.
+    DuplicateInputs = 6,
+}
+
+impl ErrorCode {
+    fn message(&self) -> &'static str {
+        match self {
+            Self::OutputsOverflowed => "Value overflow: a sum is beyond 64 bits",
+            Self::InsufficientInputs => "Inputs are not enough for the outputs plus threshold",
+            Self::InvalidAmount => "Amounts should be non-negative integers within 64 bits",
+            Self::UnsafeNumber => "Amounts above Number.MAX_SAFE_INTEGER should be bigint",
+            Self::InvalidType => "Values should be of the types expected",
+            Self::TooManyInputs => "More inputs are needed than maxInputs",
+            Self::DuplicateInputs => "Inputs should not repeat a UTxO",
+        }
+    }
+}
+
+impl From<ErrorCode> for JsError {
+    fn from(value: ErrorCode) -> Self {
+        raise(value.into(), value.message())
+    }
+}
+
+/// A failure of the code at a field of the arguments, e.g. `inputs[3].assets[0].quantity`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Failure {
+    pub(crate) code: ErrorCode,
+    /// The path to the field, empty if it is not known.
+    pub(crate) field: String,
+    /// What the inputs lack for the outputs plus threshold when they are not enough.
+    pub(crate) missing: Option<ExtOutput<(), (String, String)>>,
+}
+
+impl Failure {
+    /// The failure at the field within the parent, e.g. an index `[3]` or the name of an argument.
+    fn within(mut self, parent: &str) -> Self {
+        if !self.field.is_empty() && !self.field.starts_with('[') {
+            self.field.insert(0, '.');
+        }
+        self.field.insert_str(0, parent);
+        self
+    }
+
+    /// The kind of the failure raised, `BAD_ASSET` for a field of the assets.
+    fn kind(&self) -> ErrorKind {
+        if self.field.contains("assets[") {
+            ErrorKind::BadAsset
+        } else {
+            self.code.into()
+        }
+    }
+
+    /// Sets the `field` of the object if it is known.
+    fn set_field(&self, target: &Object) {
+        if !self.field.is_empty() {
+            Reflect::set(target, &"field".into(), &self.field.as_str().into())
+                .expect("Unreachable");
+        }
+    }
+}
+
+impl From<ErrorCode> for Failure {
+    fn from(code: ErrorCode) -> Self {
+        Self {
+            code,
+            field: String::new(),
+            missing: None,
+        }
+    }
+}
+
+impl From<Failure> for ErrorCode {
+    fn from(value: Failure) -> Self {
+        value.code
+    }
+}
+
+/// The lovelace missing and the quantities of the assets by their units, the policy ID then the name.
+fn deficit(missing: &ExtOutput<(), (String, String)>) -> Object {
+    let assets = Object::new();
+    for ((policy_id, asset_name), quantity) in missing.assets.iter() {
+        let unit = format!("{policy_id}{asset_name}");
+        Reflect::set(&assets, &unit.into(), &(*quantity).into()).expect("Unreachable");
+    }
+
+    let result = Object::new();
+    Reflect::set(&result, &"lovelace".into(), &missing.value.into()).expect("Unreachable");
+    Reflect::set(&result, &"assets".into(), &assets).expect("Unreachable");
+    result
+}
+
+impl From<Failure> for JsError {
+    fn from(value: Failure) -> Self {
+        if let Some(missing) = &value.missing {
+            return with_detail(value.code.into(), &deficit(missing));
+        }
+
+        if value.field.is_empty() {
+            return value.code.into();
+        }
+
+        let kind = value.kind();
+        let cause = Object::new();
+        Reflect::set(
+            &cause,
+            &"code".into(),
+            &ErrorKind::from(value.code).code().into(),
+        )
+        .expect("Unreachable");
+        Reflect::set(&cause, &"details".into(), &value.code.message().into()).expect("Unreachable");
+        value.set_field(&cause);
+        let detail = Object::new();
+        value.set_field(&detail);
+
+        with_detail(
+            error_with_cause(
+                kind,
+                &format!("{} at {}", value.code.message(), value.field),
+                &cause,
+            ),
+            &detail,
+        )
+    }
+}
+
+/// The kinds of errors raised, each of a machine-readable `code` for callers to branch on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ErrorKind {
+    OutputOverflow,
+    InsufficientInput,
+    BadAmount,
+    UnsafeNumber,
+    BadType,
+    TooManyInputs,
+    DuplicateInput,
+    /// An asset of an output is invalid, e.g. its quantity.
+    BadAsset,
+    #[cfg_attr(not(feature = "cbor"), allow(dead_code))]
+    BadHex,
+    #[cfg_attr(not(feature = "cbor"), allow(dead_code))]
+    BadCbor,
+    #[cfg_attr(not(feature = "adapters"), allow(dead_code))]
+    BadJson,
+    /// The response of a provider is not of the shape expected.
+    #[cfg_attr(not(feature = "adapters"), allow(dead_code))]
+    BadResponse,
+    #[cfg_attr(not(feature = "cbor"), allow(dead_code))]
+    BadAddress,
+    BadOutPoint,
+    /// A UTxO has no `OutPoint` where one is needed, e.g. to build a transaction.
+    MissingOutPoint,
+    MissingAddress,
+    #[cfg_attr(not(any(feature = "fee", feature = "fixtures")), allow(dead_code))]
+    BadOptions,
+    /// A UTxO or an output cannot be split as asked.
+    #[cfg_attr(not(feature = "fee"), allow(dead_code))]
+    Unsplittable,
+    OutOfMemory,
+}
+
+impl ErrorKind {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::OutputOverflow => "OUTPUT_OVERFLOW",
+            Self::InsufficientInput => "INSUFFICIENT_INPUT",
+            Self::BadAmount => "BAD_AMOUNT",
+            Self::UnsafeNumber => "UNSAFE_NUMBER",
+            Self::BadType => "BAD_TYPE",
+            Self::TooManyInputs => "TOO_MANY_INPUTS",
+            Self::DuplicateInput => "DUPLICATE_INPUT",
+            Self::BadAsset => "BAD_ASSET",
+            Self::BadHex => "BAD_HEX",
+            Self::BadCbor => "BAD_CBOR",
+            Self::BadJson => "BAD_JSON",
+            Self::BadResponse => "BAD_RESPONSE",
+            Self::BadAddress => "BAD_ADDRESS",
+            Self::BadOutPoint => "BAD_OUTPOINT",
+            Self::MissingOutPoint => "MISSING_OUTPOINT",
+            Self::MissingAddress => "MISSING_ADDRESS",
+            Self::BadOptions => "BAD_OPTIONS",
+            Self::Unsplittable => "UNSPLITTABLE",
+            Self::OutOfMemory => "OUT_OF_MEMORY",
+        }
+    }
+}
+
+impl From<ErrorCode> for ErrorKind {
+    fn from(value: ErrorCode) -> Self {
+        match value {
+            ErrorCode::OutputsOverflowed => Self::OutputOverflow,
+            ErrorCode::InsufficientInputs => Self::InsufficientInput,
+            ErrorCode::InvalidAmount => Self::BadAmount,
+            ErrorCode::UnsafeNumber => Self::UnsafeNumber,
+            ErrorCode::InvalidType => Self::BadType,
+            ErrorCode::TooManyInputs => Self::TooManyInputs,
+            ErrorCode::DuplicateInputs => Self::DuplicateInput,
+        }
+    }
+}
+
+/// A JS error of the message, with the `code` of its kind.
+fn raise(kind: ErrorKind, message: &str) -> JsError {
+    let error = JsError::new(message);
+    let value: JsValue = error.clone().into();
+    Reflect::set(&value, &"code".into(), &kind.code().into()).expect("Unreachable");
+    error
+}
+
+/// The error with the `detail` of what failed, e.g. the field or the offset.
+fn with_detail(error: JsError, detail: &JsValue) -> JsError {
+    let value: JsValue = error.clone().into();
+    Reflect::set(&value, &"detail".into(), detail).expect("Unreachable");
+    error
+}
+
+/// A JS error of the message, with the inner error wrapped as its `cause`.
+fn error_with_cause(kind: ErrorKind, message: &str, cause: &JsValue) -> JsError {
+    let error = raise(kind, message);
+    let value: JsValue = error.clone().into();
+    Reflect::set(&value, &"cause".into(), cause).expect("Unreachable");
+    error
+}
+
 struct Asset<'a> {
     policy_id: &'a str,
     asset_name: &'a str,
@@ -124,23 +555,218 @@ impl From<Asset<'_>> for JsAsset {
     }
 }
 
-impl<'o> From<&'o JsOutput> for Output<'o> {
-    fn from(value: &'o JsOutput) -> Self {
-        let mut output = Self {
-            value: value.lovelace(),
-            assets: BTreeMap::new(),
-            data: Some(value),
-        };
+thread_local! {
+    static STRICT_BIGINT: Cell<bool> = const { Cell::new(false) };
+    static NUMBER_WARNED: Cell<bool> = const { Cell::new(false) };
+    static INPUTS: RefCell<Vec<JsValue>> = const { RefCell::new(Vec::new()) };
+}
 
-        if let Some(assets) = try_iter(&value.assets()).unwrap() {
-            for result in assets {
-                let asset: JsAsset = result.unwrap().unchecked_into();
-                output.insert_asset((asset.policy_id(), asset.asset_name()), asset.quantity());
-            }
+/**
+Enable or disable the strict BigInt mode
+
+Lovelace and quantities should be `bigint`, but `number` is accepted as well.
+A `number` above `Number.MAX_SAFE_INTEGER` may have lost its precision before it is passed in,
+so in the strict mode it is rejected, and a warning is logged the first time
+any `number` is used for an amount.
+
+The mode is disabled by default.
+*/
+#[wasm_bindgen(js_name = setStrictBigInt)]
+pub fn set_strict_bigint(enabled: bool) {
+    STRICT_BIGINT.with(|strict| strict.set(enabled));
+}
+
+/**
+Reserve capacity for UTxOs to be converted
+
+Grows the list the inputs of a selection are collected into for the number of UTxOs expected,
+once, and keeps it for the selections after, so collecting a large set of inputs
+does not grow it and the WASM memory again and again. More inputs still grow it as needed.
+The assets of each UTxO are held in ordered maps, which cannot be reserved,
+so `expectedAssetsPerUtxo` is only checked to fit in memory.
+
+Raises errors when the memory cannot be reserved.
+*/
+#[wasm_bindgen(js_name = reserveCapacity)]
+pub fn reserve_capacity(expected_utxos: u32, expected_assets_per_utxo: u32) -> Result<(), JsError> {
+    let out_of_memory = || raise(ErrorKind::OutOfMemory, "Cannot reserve the memory");
+    let utxos = expected_utxos as usize;
+    Layout::array::<JsValue>(utxos)
+        .and(Layout::array::<JsValue>(expected_assets_per_utxo as usize))
+        .map_err(|_| out_of_memory())?;
+
+    INPUTS.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.clear();
+        buffer.try_reserve_exact(utxos).map_err(|_| out_of_memory())
+    })
+}
+
+/// The inputs of a selection, collected into the list kept by `reserveCapacity`.
+fn collect_inputs(inputs: &JsValue) -> Result<Vec<JsOutput>, ErrorCode> {
+    let mut result = INPUTS.with(|buffer| std::mem::take(&mut *buffer.borrow_mut()));
+
+    for item in try_iter(inputs)
+        .ok()
+        .flatten()
+        .ok_or(ErrorCode::InvalidType)?
+    {
+        result.push(item.map_err(|_| ErrorCode::InvalidType)?);
+    }
+
+    Ok(result.into_iter().map(JsCast::unchecked_into).collect())
+}
+
+/// Keeps the list of the inputs collected for the next selection, if it is the larger.
+fn release_inputs(inputs: Vec<JsOutput>) {
+    let mut inputs: Vec<JsValue> = inputs.into_iter().map(JsValue::from).collect();
+    inputs.clear();
+
+    INPUTS.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        if inputs.capacity() > buffer.capacity() {
+            *buffer = inputs;
+        }
+    });
+}
+
+/// Converts a lovelace or quantity from JS, obeying the strict BigInt mode.
+fn to_amount(value: &JsValue) -> Result<u64, ErrorCode> {
+    const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+    if value.is_bigint() {
+        return u64::try_from(value.clone()).map_err(|_| ErrorCode::InvalidAmount);
+    }
+
+    let number = value.as_f64().ok_or(ErrorCode::InvalidAmount)?;
+
+    if number < 0.0 || number.fract() != 0.0 || number >= u64::MAX as f64 {
+        return Err(ErrorCode::InvalidAmount);
+    }
+
+    if STRICT_BIGINT.with(|strict| strict.get()) {
+        if number > MAX_SAFE_INTEGER {
+            return Err(ErrorCode::UnsafeNumber);
+        }
+
+        if !NUMBER_WARNED.with(|warned| warned.replace(true)) {
+            warn(
+                "cardano-utxo-wasm: amounts given as number may lose precision, use bigint instead",
+            );
         }
+    }
+
+    Ok(number as u64)
+}
+
+/// Gets the field of an object from JS, failing rather than throwing on anything else.
+fn get_field(target: &JsValue, key: &str) -> Result<JsValue, ErrorCode> {
+    if !target.is_object() {
+        return Err(ErrorCode::InvalidType);
+    }
+
+    Reflect::get(target, &key.into()).map_err(|_| ErrorCode::InvalidType)
+}
 
-        output
+/// Gets a lovelace or quantity field, `None` if it is undefined.
+fn get_amount(target: &JsValue, key: &str) -> Result<Option<u64>, ErrorCode> {
+    let value = get_field(target, key)?;
+
+    if value.is_undefined() {
+        return Ok(None);
+    }
+
+    to_amount(&value).map(Some)
+}
+
+/// Gets a count field, `None` if it is undefined.
+fn get_count(target: &JsValue, key: &str) -> Result<Option<u32>, ErrorCode> {
+    let value = get_field(target, key)?;
+
+    if value.is_undefined() {
+        return Ok(None);
+    }
+
+    match value.as_f64() {
+        Some(n) if n >= 0.0 && n.fract() == 0.0 && n <= u32::MAX as f64 => Ok(Some(n as u32)),
+        _ => Err(ErrorCode::InvalidType),
+    }
+}
+
+/// Collects the items of an iterable from JS, failing rather than throwing on anything else.
+fn iterate(iterable: &JsValue) -> Result<Vec<JsValue>, ErrorCode> {
+    try_iter(iterable)
+        .ok()
+        .flatten()
+        .ok_or(ErrorCode::InvalidType)?
+        .map(|item| item.map_err(|_| ErrorCode::InvalidType))
+        .collect()
+}
+
+impl<'o> TryFrom<&'o JsOutput> for Output<'o> {
+    type Error = ErrorCode;
+
+    fn try_from(value: &'o JsOutput) -> Result<Self, Self::Error> {
+        Ok(parse_output(value)?)
+    }
+}
+
+/// Reads an output, failing at the field of it, e.g. `assets[0].quantity`.
+fn parse_output(value: &JsOutput) -> Result<Output<'_>, Failure> {
+    fn at(field: &str) -> impl Fn(ErrorCode) -> Failure + '_ {
+        move |code| Failure::from(code).within(field)
+    }
+    if !value.is_object() {
+        return Err(ErrorCode::InvalidType.into());
+    }
+
+    let mut output = Output {
+        value: get_field(value, "lovelace")
+            .and_then(|lovelace| to_amount(&lovelace))
+            .map_err(at("lovelace"))?,
+        assets: BTreeMap::new(),
+        data: Some(value),
+    };
+    let assets = get_field(value, "assets")?;
+
+    if assets.is_undefined() || assets.is_null() {
+        return Ok(output);
     }
+
+    for (index, asset) in iterate(&assets).map_err(at("assets"))?.iter().enumerate() {
+        let field = |key: &str| {
+            get_field(asset, key)?
+                .as_string()
+                .ok_or(ErrorCode::InvalidType)
+        };
+        let policy_id = field("policyId").map_err(at("policyId"));
+        let asset_name = field("assetName").map_err(at("assetName"));
+        let quantity = get_field(asset, "quantity")
+            .and_then(|quantity| to_amount(&quantity))
+            .map_err(at("quantity"));
+        let in_assets = |failure: Failure| failure.within(&format!("[{index}]")).within("assets");
+
+        output.insert_asset(
+            (
+                policy_id.map_err(in_assets)?,
+                asset_name.map_err(in_assets)?,
+            ),
+            quantity.map_err(in_assets)?,
+        );
+    }
+
+    Ok(output)
+}
+
+/// The outputs, failing at the index and field of the first invalid, e.g. `[3].lovelace`.
+fn to_outputs(js_outputs: &[JsOutput]) -> Result<Vec<Output<'_>>, Failure> {
+    js_outputs
+        .iter()
+        .enumerate()
+        .map(|(index, js_output)| {
+            parse_output(js_output).map_err(|failure| failure.within(&format!("[{index}]")))
+        })
+        .collect()
 }
 
 impl From<Output<'_>> for JsOutput {
@@ -161,76 +787,724 @@ impl From<Output<'_>> for JsOutput {
     }
 }
 
+/// The outputs of an Array, or of any iterable such as a `Set` or a generator.
+fn collect_js_outputs(outputs: &JsValue) -> Result<Vec<JsOutput>, ErrorCode> {
+    Ok(iterate(outputs)?
+        .into_iter()
+        .map(JsCast::unchecked_into)
+        .collect())
+}
+
+fn to_js_output_array(outputs: &[Output]) -> JsOutputArray {
+    let result = Array::new();
+
+    for output in outputs {
+        result.push(output.data.expect("Unreachable"));
+    }
+
+    result.unchecked_into()
+}
+
+/**
+Copies the fields of the sources other than the known ones to the target.
+
+A field is copied only when it is unambiguous, i.e. all the sources having it agree on its value.
+*/
+fn copy_extra_fields(target: &JsValue, sources: &[&JsValue], known: &[&str]) {
+    let mut fields: BTreeMap<String, Option<JsValue>> = BTreeMap::new();
+
+    for source in sources {
+        let source: &Object = source.unchecked_ref();
+
+        for key in Object::keys(source)
+            .iter()
+            .filter_map(|key| key.as_string())
+        {
+            if known.contains(&key.as_str()) {
+                continue;
+            }
+
+            let value = Reflect::get(source, &key.as_str().into()).unwrap_or(JsValue::UNDEFINED);
+
+            fields
+                .entry(key)
+                .and_modify(|field| {
+                    if field.as_ref().is_some_and(|v| !Object::is(v, &value)) {
+                        *field = None;
+                    }
+                })
+                .or_insert(Some(value));
+        }
+    }
+
+    for (key, value) in fields {
+        if let Some(value) = value {
+            Reflect::set(target, &key.into(), &value).expect("Unreachable");
+        }
+    }
+}
+
+/// The fields of a UTxO itself, never copied from the inputs to the excess.
+const UTXO_FIELDS: [&str; 5] = ["address", "slot", "datumHash", "datum", "scriptRef"];
+
+/// Copies the extra fields of the outputs and their assets to the target made from them.
+fn copy_extra_fields_of_outputs(target: &JsOutput, outputs: &[Output]) {
+    copy_extra_fields_except(target, outputs, &[]);
+}
+
+/// Copies the extra fields of the inputs and their assets to the excess, but `UTXO_FIELDS`.
+fn copy_extra_fields_of_inputs(excess: &JsOutput, inputs: &[Output]) {
+    copy_extra_fields_except(excess, inputs, &UTXO_FIELDS);
+}
+
+/// The assets of the output and their units, none if they are missing or not iterable.
+fn assets_by_unit(output: &JsValue) -> Vec<((String, String), JsValue)> {
+    let Ok(assets) = get_field(output, "assets") else {
+        return Vec::new();
+    };
+    if assets.is_undefined() || assets.is_null() {
+        return Vec::new();
+    }
+
+    iterate(&assets)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|asset| {
+            let field = |key: &str| get_field(&asset, key).ok()?.as_string();
+            let key = (field("policyId")?, field("assetName")?);
+            Some((key, asset))
+        })
+        .collect()
+}
+
+fn copy_extra_fields_except(target: &JsOutput, outputs: &[Output], except: &[&str]) {
+    let mut assets: BTreeMap<(String, String), Vec<JsValue>> = BTreeMap::new();
+    let outputs: Vec<&JsValue> = outputs
+        .iter()
+        .filter_map(|output| output.data.map(|data| data.as_ref()))
+        .collect();
+
+    for output in outputs.iter() {
+        for (key, asset) in assets_by_unit(output) {
+            assets.entry(key).or_default().push(asset);
+        }
+    }
+
+    let known: Vec<&str> = ["lovelace", "assets", "data"]
+        .into_iter()
+        .chain(except.iter().copied())
+        .collect();
+    copy_extra_fields(target, &outputs, &known);
+
+    for (key, asset) in assets_by_unit(target) {
+        if let Some(sources) = assets.get(&key) {
+            let sources: Vec<&JsValue> = sources.iter().collect();
+            copy_extra_fields(&asset, &sources, &["policyId", "assetName", "quantity"]);
+        }
+    }
+}
+
+fn to_select_result(
+    selected: &[Output],
+    unselected: &[Output],
+    excess: Output,
+    dust: &DustTokens,
+) -> SelectResult {
+    let result: SelectResult = Object::new().unchecked_into();
+    let dust_tokens: Array = selection::dust_tokens(&excess, &[selected, unselected], dust)
+        .iter()
+        .map(|((policy_id, asset_name), quantity)| {
+            JsValue::from(JsAsset::from(Asset::new(policy_id, asset_name, *quantity)))
+        })
+        .collect();
+    let excess: JsOutput = excess.into();
+    copy_extra_fields_of_inputs(&excess, selected);
+
+    result.set_selected(&to_js_output_array(selected));
+    result.set_unselected(&to_js_output_array(unselected));
+    result.set_excess(&excess);
+    Reflect::set(&result, &"dustTokens".into(), &dust_tokens).expect("Unreachable");
+
+    result
+}
+
 /**
 Select UTxOs for the outputs
 
 Returns an object contains selected UTxOs, unselected UTxOs and the excess output
 to pay the fee and return the change.
 The excess output will be larger than or equal to the threshold argument.
+Extra fields of the selected UTxOs and their assets (e.g. `ticker` of an asset)
+are copied to the excess when all the UTxOs having them agree on their values,
+except the `address`, `slot` and datums and script of a UTxO itself.
+The inputs and outputs may be Arrays or any other iterables, e.g. a `Set` or a generator
+of the UTxOs of a database cursor, each iterated once.
+
+The threshold may instead be `{ feeReserve, minChange }`, for the excess to cover
+`feeReserve` plus either no change or change of at least `minChange` lovelace,
+so the change never falls below its minimum lovelace.
+Or it may be `ProtocolParameters`, for the threshold to be computed: the fee estimated
+for the outputs and a change output spending a few inputs, up to `maxInputs`, with the
+change of its minimum lovelace as `minChange` unless set. This needs the `fee` feature.
 
 Returns nothing if the inputs are not enough for the outputs plus threshold.
 
-Raises errors when the types used are wrong.
+The options are:
+
+* `previous` - A previous result of selection, whose selected UTxOs are kept
+  if they are still in the inputs, and only as many other UTxOs as needed are added.
+  This minimizes the changes in the selection when the outputs change slightly.
+* `excludeUnspendable` - Protocol parameters to leave the UTxOs found by `findUnspendable`
+  out of selection, listed as unselected. Needs the `fee` feature.
+* `splitOversizedChange` - Protocol parameters to split the change, the excess beyond
+  the threshold, into `changeOutputs` of the result whose values are at most `maxValueSize`
+  bytes, each with its minimum lovelace, which `minChange` becomes unless it is lovelace.
+  Needs the `fee` feature.
+* `alternatives` - The number of distinct selections to list in `alternatives` of the result,
+  ranked by `objective`, so users can choose between them.
+* `objective` - How alternatives and the `auto` strategy rank selections:
+  `"fewestInputs"` (default), `"smallestChange"`, or `"fewestChangeAssets"` for the excess
+  of the fewest distinct assets, so the change has a smaller bundle and minimum lovelace.
+* `strategy` - `"default"`, or `"auto"` to try other selections in turn for `timeBudgetMs`
+  (default `50`) and return the best by `objective`, or `"randomImprove"` for Random-Improve
+  of CIP-2 to spread the UTxOs of wallets over time, random by `seed` (default from the clock),
+  `"largestFirst"` for Largest-First of CIP-2 to spend the fewest inputs, `"branchAndBound"`
+  to search for inputs matching exactly so no change is needed, up to `matchTolerance` lovelace
+  over (default `0n`) and falling back to the default when there is none, `"knapsack"` to
+  try random subsets for the change of the fewest assets and least lovelace, random by `seed`,
+  `"fewestInputs"` to search for the fewest inputs, which Largest-First may miss, when the size
+  of the transaction binds, `"dust"` to spend the smallest UTxOs first and consolidate dust
+  while paying, unless more than `maxInputs` (default `20`) are needed, then as the default,
+  `"oldestFirst"` to spend the UTxOs created at the earliest `slot` first, those without last,
+  or the name of a strategy that Rust embedders registered, see [`strategy`].
+  The random strategies return their `seed` in the result, to log it and select the same again.
+* `assetWeights` - Weights of assets, for alternatives and the `auto` strategy to rank
+  selections spending inputs holding them first: better for positive weights, worse for negative.
+* `minChange` - The lovelace that change beyond the threshold must have at least unless it is none,
+  or `{ coinsPerUtxoByte }` for the minimum lovelace of the change with its assets,
+  which needs the `fee` feature. Selections without change or with enough are preferred.
+* `absorbChange` - The most lovelace of change below its minimum to leave to the fee,
+  reported as `donated` of the result, rather than selecting more for the minimum.
+* `consolidate` - `{ maxInputs, minLovelace?, maxLovelace? }` to also select up to `maxInputs`
+  UTxOs of lovelace in the range, the smallest first, merging them into the excess.
+  With no outputs, this consolidates a wallet with the same constraints as payments.
+* `requireAsset` - `{ policyId, assetName, fallback? }` to select only from the UTxOs holding the asset,
+  or if they are not enough and `fallback` is `true`, all of them and then from the others.
+* `score` - A callback `(output) => number` scoring each input once, for the inputs
+  to be selected from the highest score, those of equal scores in the order passed.
+  It replaces the `strategy`, which must be left out or `"default"` with it.
+  Raises an error if it throws or returns anything but a finite number.
+* `avoidMixingAddresses` - `true` to select from the UTxOs of one `address` if they are enough,
+  otherwise of as few addresses as possible, so fewer addresses are linked by a transaction.
+* `dedupeInputs` - `true` to keep only the first of the inputs of the same `OutPoint`,
+  rather than raising the error of `DUPLICATE_INPUT` at the input repeating one,
+  since a transaction spending a UTxO twice cannot be submitted.
+* `transferable` - `true` to return the result of `toTransferable`, its UTxOs copied to
+  plain objects, to be posted from a Worker.
+* `raiseInsufficient` - `true` to raise the error of `INSUFFICIENT_INPUT` when the inputs
+  are not enough, rather than returning nothing, with the `detail` of what they lack:
+  the `lovelace` and the quantities of `assets` by their units, the policy ID then the name.
+* `maxInputs` - The most UTxOs to select. A selection of more is made again for the fewest
+  inputs, and if those are still more, the error of `TOO_MANY_INPUTS` is raised.
+* `tokenDustFloor` - The quantity below which an asset left in the excess is token dust,
+  or the quantities of the assets listed with the rest never dust. All the other UTxOs holding
+  such an asset are selected as well, unless more than `maxInputs`, so the change holds
+  either none of the asset or all the UTxOs hold.
+* `mandatory` - UTxOs to select regardless of their value, e.g. a script UTxO being spent,
+  whether or not they are in the inputs. Their value counts toward the outputs plus threshold,
+  and only as many other UTxOs as needed are added.
+* `exclude` - The `OutPoint`s of UTxOs never to select, or their keys of `outPointKey`,
+  e.g. of UTxOs spent by pending transactions. They are listed as unselected.
+* `mergeChangeInto` - The index of an output, or its `address`, to merge the change into,
+  the excess beyond the threshold, rather than creating a change output, e.g. when paying
+  the wallet itself. The `outputs` of the result are the outputs with it merged, and
+  the excess is left with the threshold only, without `minChange` since no change output
+  is made. Raises an error if there is no such output. When change below its minimum
+  is donated to the fee by `absorbChange`, it is not merged and there are no `outputs`.
+* `referenceInputs` - The `OutPoint`s, or their keys, of UTxOs to be referenced and not spent,
+  e.g. those holding reference scripts. They are never selected, even if `mandatory`,
+  and are listed as `referenceInputs` of the result, not as unselected, for transaction
+  builders to attach them.
+* `profile` - `true` to return `timings` of the result, the milliseconds spent parsing,
+  selecting and building the result, also added as measures of `performance` if available.
+* `dustTokens` - When an asset of the excess is listed in `dustTokens` of the result:
+  its quantity is below `ratio` (default `0.01`) of what the inputs hold in total,
+  or at most `quantity` (default `0n`).
+
+Totals of the inputs are counted in 128 bits, so only what is left after the outputs
+must fit in 64. Raises errors when the types used are wrong, the outputs overflowed,
+or the inputs beyond the outputs are more than 64 bits of lovelace or a quantity
+and no selection is made, as `OUTPUT_OVERFLOW`.
 */
 #[wasm_bindgen]
 pub fn select(
-    inputs: &JsOutputArray,
-    outputs: &JsOutputArray,
-    threshold: &JsOutput,
+    #[wasm_bindgen(unchecked_param_type = "Iterable<Output>")] inputs: &JsOutputArray,
+    #[wasm_bindgen(unchecked_param_type = "Iterable<Output>")] outputs: &JsOutputArray,
+    #[wasm_bindgen(unchecked_param_type = "Threshold")] threshold: &JsOutput,
+    options: Option<JsSelectOptions>,
 ) -> Result<Option<SelectResult>, JsError> {
-    let js_inputs: Vec<JsOutput> = try_iter(inputs)
-        .unwrap()
-        .unwrap()
-        .into_iter()
-        .map(|i| i.unwrap().unchecked_into())
-        .collect();
-    let mut inputs: Vec<Output> = js_inputs.iter().map(|o| o.into()).collect();
-    let js_outputs: Vec<JsOutput> = try_iter(outputs)
-        .unwrap()
-        .unwrap()
-        .into_iter()
-        .map(|i| i.unwrap().unchecked_into())
-        .collect();
-    let outputs: Vec<Output> = js_outputs.iter().map(|o| o.into()).collect();
-    let threshold: Output = threshold.into();
-    let total_output: Output =
-        try_sum(&outputs).ok_or_else(|| JsError::new("Outputs overflowed"))?;
+    Ok(try_select(inputs, outputs, threshold, options.as_ref())?)
+}
 
-    Ok(
-        utxo::select(&mut inputs[..], &total_output, &threshold).and_then(
-            |(selected, unselected, excess)| {
-                let result: SelectResult = Object::new().unchecked_into();
+/**
+Select UTxOs for a request of the inputs, outputs and threshold with the options
 
-                let selected: JsOutputArray = {
-                    let result = Array::new();
+Works as `select`, with the positional arguments as the fields `inputs`, `outputs`
+and `threshold` of the same object as the options, so that a request reads as a whole
+and builds on other requests with the spread syntax.
+*/
+#[wasm_bindgen(js_name = selectWithOptions)]
+pub fn select_with_options(request: &SelectRequest) -> Result<Option<SelectResult>, JsError> {
+    let get = |key| get_field(request, key);
+    let inputs = get("inputs")?;
+    let outputs = get("outputs")?;
+    let threshold = get("threshold")?;
+
+    Ok(try_select(
+        &inputs,
+        &outputs,
+        threshold.unchecked_ref(),
+        Some(request.unchecked_ref()),
+    )?)
+}
 
-                    for output in selected {
-                        result.push(output.data.expect("Unreachable"));
-                    }
+fn try_select(
+    inputs: &JsValue,
+    outputs: &JsValue,
+    threshold: &JsOutput,
+    options: Option<&JsSelectOptions>,
+) -> Result<Option<SelectResult>, Failure> {
+    let start = clock::now();
+    let options = SelectOptions::try_from(options)?;
 
-                    result.unchecked_into()
-                };
+    select_with(inputs, outputs, threshold, options, start)
+}
+
+/// Selects as `select` with the options converted already, since the start given.
+fn select_with(
+    inputs: &JsValue,
+    outputs: &JsValue,
+    threshold: &JsOutput,
+    options: SelectOptions,
+    start: f64,
+) -> Result<Option<SelectResult>, Failure> {
+    let profiler = Profiler::new(options.profile, start);
+    let js_inputs = collect_inputs(inputs).map_err(|code| Failure::from(code).within("inputs"))?;
+    let (js_inputs, reference_inputs) = spendable_inputs(js_inputs, &options)?;
+    let result = to_outputs(&js_inputs)
+        .map_err(|failure| failure.within("inputs"))
+        .and_then(|inputs| {
+            select_parsed(
+                inputs,
+                &reference_inputs,
+                outputs,
+                threshold,
+                options,
+                profiler,
+            )
+        });
+
+    release_inputs(js_inputs);
+    result
+}
 
-                let unselected: JsOutputArray = {
-                    let result = Array::new();
+/**
+The inputs to spend, with the mandatory, and the reference inputs taken out of them.
 
-                    for output in unselected {
-                        result.push(output.data.expect("Unreachable"));
-                    }
+Raises the error of `DUPLICATE_INPUT` at an input repeating an `OutPoint`,
+or keeps the first of them with `dedupeInputs`.
+*/
+fn spendable_inputs(
+    mut js_inputs: Vec<JsOutput>,
+    options: &SelectOptions,
+) -> Result<(Vec<JsOutput>, Vec<JsOutput>), Failure> {
+    options.add_mandatory(&mut js_inputs);
+    let reference_inputs = options.take_reference_inputs(&mut js_inputs);
+    let repeated = out_point::repeated(&js_inputs);
+    match repeated.first() {
+        Some(_) if options.dedupe_inputs => {
+            let mut index = 0;
+            js_inputs.retain(|_| {
+                index += 1;
+                repeated.binary_search(&(index - 1)).is_err()
+            });
+        }
+        Some(index) => {
+            return Err(Failure::from(ErrorCode::DuplicateInputs)
+                .within(&format!("[{index}]"))
+                .within("inputs"))
+        }
+        None => {}
+    }
 
-                    result.unchecked_into()
-                };
+    Ok((js_inputs, reference_inputs))
+}
+
+/// Selects from the inputs parsed already, for the outputs and threshold yet to parse.
+fn select_parsed<'o>(
+    mut inputs: Vec<Output<'o>>,
+    reference_inputs: &[JsOutput],
+    outputs: &JsValue,
+    threshold: &'o JsOutput,
+    mut options: SelectOptions,
+    mut profiler: Profiler,
+) -> Result<Option<SelectResult>, Failure> {
+    let in_outputs = |failure: Failure| failure.within("outputs");
+    let js_outputs = collect_js_outputs(outputs).map_err(|code| in_outputs(code.into()))?;
+    let outputs = to_outputs(&js_outputs).map_err(in_outputs)?;
+    let threshold = to_threshold(threshold, &outputs, &mut options)?;
+    let total_output: Output = try_sum(&outputs).ok_or(ErrorCode::OutputsOverflowed)?;
+    sort_inputs(&mut inputs, &options)?;
+
+    profiler.end("parse");
+
+    let Some(thresholded) =
+        selection::select_with_threshold(inputs.clone(), &total_output, &threshold, &options)
+    else {
+        if let Err(ErrorCode::OutputsOverflowed) = Total::of(&inputs).excess(&total_output) {
+            return Err(ErrorCode::OutputsOverflowed.into());
+        }
+        let missing = match options.raise_insufficient {
+            true => selection::shortfall(&inputs, &total_output, &threshold, &options),
+            false => None,
+        };
+        if selection::exceeds_max_inputs(inputs, &total_output, &threshold, &options) {
+            return Err(ErrorCode::TooManyInputs.into());
+        }
+        return match missing {
+            Some(missing) => Err(Failure {
+                missing: Some(ExtOutput {
+                    value: missing.value,
+                    assets: missing.assets,
+                    data: None,
+                }),
+                ..ErrorCode::InsufficientInputs.into()
+            }),
+            None => Ok(None),
+        };
+    };
+    profiler.end("select");
+    let result = build_result(
+        thresholded,
+        &inputs,
+        &outputs,
+        &total_output,
+        reference_inputs,
+        &options,
+    )?;
+
+    profiler.end("build");
+    profiler.report(&result);
+
+    if options.transferable {
+        return Ok(Some(view::transferable(&result)?));
+    }
 
-                let excess: JsOutput = excess.into();
+    Ok(Some(result))
+}
 
-                result.set_selected(&selected);
-                result.set_unselected(&unselected);
-                result.set_excess(&excess);
+/// Sorts the inputs for the strategies selecting them in order, by `score` or `oldestFirst`.
+fn sort_inputs(inputs: &mut Vec<Output>, options: &SelectOptions) -> Result<(), ErrorCode> {
+    if let Some(score) = &options.score {
+        strategy::score::sort(inputs, score)?;
+    }
+    if options.strategy == Strategy::OldestFirst {
+        strategy::oldest_first::sort(inputs)?;
+    }
 
-                Some(result)
-            },
-        ),
-    )
+    Ok(())
+}
+
+/**
+The result of the selection, with what the options add to it: the `outputs` of merged change,
+the `changeOutputs`, and the `donated`, `seed`, `referenceInputs` and `alternatives`.
+*/
+fn build_result<'o>(
+    mut thresholded: selection::Thresholded<'o>,
+    inputs: &[Output<'o>],
+    outputs: &[Output<'o>],
+    total_output: &Output<'o>,
+    reference_inputs: &[JsOutput],
+    options: &SelectOptions,
+) -> Result<SelectResult, ErrorCode> {
+    let to_result = |selection: Selection| {
+        to_select_result(
+            &selection.selected,
+            &selection.unselected,
+            selection.excess,
+            &options.dust_tokens,
+        )
+    };
+    let merged = match &options.merge_change {
+        Some(target) if thresholded.donated == 0 => {
+            let index = target.index_of(outputs).ok_or(ErrorCode::InvalidType)?;
+            let (merged, excess) = change::merge_change_into(
+                outputs,
+                index,
+                &thresholded.selection.excess,
+                &thresholded.base,
+            )?;
+            thresholded.selection.excess = excess;
+            Some(merged)
+        }
+        _ => None,
+    };
+    #[cfg(feature = "fee")]
+    let change_outputs = options.split_oversized_change.map(|params| {
+        bundle::split_change(&thresholded, &params)
+            .into_iter()
+            .map(|output| JsValue::from(JsOutput::from(output)))
+            .collect::<Array>()
+    });
+    let result = to_result(thresholded.selection);
+
+    if let Some(merged) = merged {
+        Reflect::set(&result, &"outputs".into(), &merged).expect("Unreachable");
+    }
+
+    #[cfg(feature = "fee")]
+    if let Some(change_outputs) = change_outputs {
+        Reflect::set(&result, &"changeOutputs".into(), &change_outputs).expect("Unreachable");
+    }
+
+    if thresholded.donated > 0 {
+        let donated = JsValue::from(thresholded.donated);
+        Reflect::set(&result, &"donated".into(), &donated).expect("Unreachable");
+    }
+
+    if let Some(seed) = options.strategy.seed() {
+        Reflect::set(&result, &"seed".into(), &seed.into()).expect("Unreachable");
+    }
+
+    if !reference_inputs.is_empty() {
+        let reference_inputs: Array = reference_inputs.iter().collect();
+        Reflect::set(&result, &"referenceInputs".into(), &reference_inputs).expect("Unreachable");
+    }
+
+    if options.alternatives > 0 {
+        let alternatives: Array =
+            selection::alternatives(inputs, total_output, &thresholded.threshold, options)
+                .into_iter()
+                .map(to_result)
+                .collect();
+        Reflect::set(&result, &"alternatives".into(), &alternatives).expect("Unreachable");
+    }
+
+    Ok(result)
+}
+
+/// Reads a threshold of either form, or estimates it of the protocol parameters passed instead.
+fn to_threshold<'o>(
+    threshold: &'o JsOutput,
+    #[cfg_attr(not(feature = "fee"), allow(unused_variables))] outputs: &[Output<'o>],
+    #[cfg_attr(not(feature = "fee"), allow(unused_variables))] options: &mut SelectOptions,
+) -> Result<Threshold<'o>, ErrorCode> {
+    #[cfg(feature = "fee")]
+    if !get_field(threshold, "coinsPerUtxoByte")?.is_undefined() {
+        let params: protocol::ProtocolParameters = threshold
+            .unchecked_ref::<protocol::JsProtocolParameters>()
+            .try_into()?;
+
+        return fee_selection::auto_threshold(outputs, &params, options)
+            .ok_or(ErrorCode::OutputsOverflowed);
+    }
+
+    let fee_reserve = get_amount(threshold, "feeReserve")?;
+    let min_change = get_amount(threshold, "minChange")?;
+
+    match (fee_reserve, min_change) {
+        (None, None) => Ok(Threshold::Output(threshold.try_into()?)),
+        (Some(fee_reserve), Some(min_change)) => Ok(Threshold::Change {
+            fee_reserve,
+            min_change,
+        }),
+        _ => Err(ErrorCode::InvalidType),
+    }
+}
+
+/**
+Select UTxOs for the outputs, reporting the outcome as a discriminated union
+
+Works as `select` with the same options, but never returns nothing
+or raises errors for a failed selection.
+Returns the result of `select` with `ok` set to `true` on success,
+otherwise an object with `ok` set to `false`, the `code` of the failure and its `details`,
+the `code` of the `ErrorKind` of the error `select` raises, e.g. `"INSUFFICIENT_INPUT"`,
+and the `field` of the inputs or outputs that is invalid, e.g. `inputs[3].lovelace`.
+When the inputs are not enough, the failure has the `shortfall` of them,
+the lovelace and assets the inputs the options may select lack for the outputs plus threshold,
+so the user can be told exactly what is missing.
+*/
+#[wasm_bindgen(js_name = selectOutcome)]
+pub fn select_outcome(
+    #[wasm_bindgen(unchecked_param_type = "Iterable<Output>")] inputs: &JsOutputArray,
+    #[wasm_bindgen(unchecked_param_type = "Iterable<Output>")] outputs: &JsOutputArray,
+    #[wasm_bindgen(unchecked_param_type = "Threshold")] threshold: &JsOutput,
+    options: Option<JsSelectOptions>,
+) -> SelectOutcome {
+    let start = clock::now();
+    let selected = SelectOptions::try_from(options.as_ref())
+        .map_err(Failure::from)
+        .and_then(|options| {
+            let options = SelectOptions {
+                raise_insufficient: true,
+                ..options
+            };
+            select_with(inputs, outputs, threshold, options, start)
+        });
+    let outcome: Object = match selected {
+        Ok(Some(result)) => {
+            let result: Object = result.unchecked_into();
+            Reflect::set(&result, &"ok".into(), &true.into()).expect("Unreachable");
+            result
+        }
+        Ok(None) => failure(ErrorCode::InsufficientInputs.into()),
+        Err(code) => failure(code),
+    };
+
+    outcome.unchecked_into()
+}
+
+fn failure(failure: Failure) -> Object {
+    let result = Object::new();
+    Reflect::set(&result, &"ok".into(), &false.into()).expect("Unreachable");
+    Reflect::set(&result, &"code".into(), &failure.kind().code().into()).expect("Unreachable");
+    Reflect::set(&result, &"details".into(), &failure.code.message().into()).expect("Unreachable");
+    failure.set_field(&result);
+    if let Some(missing) = failure.missing {
+        let shortfall: JsOutput = Output {
+            value: missing.value,
+            assets: missing.assets,
+            data: None,
+        }
+        .into();
+        Reflect::set(&result, &"shortfall".into(), &shortfall).expect("Unreachable");
+    }
+    result
+}
+
+/**
+Select UTxOs for several targets from one shared pool of inputs
+
+Each target is selected in order from the inputs left by the previous targets,
+so no input is ever used by more than one result.
+The `unselected` of each result are the inputs still available after that target.
+
+Returns an array with one entry per target, which is nothing if the remaining
+inputs are not enough for the outputs plus threshold of that target.
+A target that cannot be met does not consume any input.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = selectMulti)]
+pub fn select_multi(
+    inputs: &JsOutputArray,
+    targets: &JsTargetArray,
+) -> Result<SelectResultArray, JsError> {
+    let js_targets: Vec<(Vec<JsOutput>, JsOutput)> = iterate(targets)?
+        .iter()
+        .map(|target| {
+            let outputs = collect_js_outputs(get_field(target, "outputs")?.unchecked_ref())?;
+            Ok((outputs, get_field(target, "threshold")?.unchecked_into()))
+        })
+        .collect::<Result<_, ErrorCode>>()?;
+    let js_inputs = collect_js_outputs(inputs)?;
+    let mut pool = to_outputs(&js_inputs)?;
+    let results = Array::new();
+
+    for (js_outputs, js_threshold) in js_targets.iter() {
+        let outputs = to_outputs(js_outputs)?;
+        let threshold: Output = js_threshold.try_into()?;
+        let total_output: Output = try_sum(&outputs).ok_or(ErrorCode::OutputsOverflowed)?;
+
+        let remaining = match selection::select_fast(&mut pool[..], &total_output, &threshold) {
+            Some((selected, unselected, excess)) => {
+                results.push(&to_select_result(
+                    selected,
+                    unselected,
+                    excess,
+                    &DustTokens::default(),
+                ));
+                Some(unselected.to_vec())
+            }
+            None => {
+                results.push(&JsValue::UNDEFINED);
+                None
+            }
+        };
+
+        if let Some(remaining) = remaining {
+            pool = remaining;
+        }
+    }
+
+    Ok(results.unchecked_into())
+}
+
+/**
+Select UTxOs for the outputs from several tagged pools
+
+The pools are selected from in order, so a later pool (e.g. a treasury) only funds
+what the earlier pools (e.g. a hot wallet) cannot. The lovelace taken from a pool
+never exceeds its `maxLovelace`.
+
+Returns the result as `select` for all the pools, and in `pools` the UTxOs selected
+from each pool with their total. Returns nothing if the pools within their caps
+are not enough for the outputs plus threshold.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = selectPools)]
+pub fn select_pools(
+    pools: &JsPoolArray,
+    outputs: &JsOutputArray,
+    threshold: &JsOutput,
+) -> Result<Option<PoolsResult>, JsError> {
+    let js_pools: Vec<(String, Vec<JsOutput>, Option<u64>)> = iterate(pools)?
+        .iter()
+        .map(|pool| {
+            let tag = get_field(pool, "tag")?
+                .as_string()
+                .ok_or(ErrorCode::InvalidType)?;
+            let utxos = collect_js_outputs(get_field(pool, "utxos")?.unchecked_ref())?;
+            Ok((tag, utxos, get_amount(pool, "maxLovelace")?))
+        })
+        .collect::<Result<_, ErrorCode>>()?;
+    let pool_inputs = js_pools
+        .iter()
+        .map(|(_, utxos, cap)| Ok((to_outputs(utxos)?, *cap)))
+        .collect::<Result<_, ErrorCode>>()?;
+    let js_outputs = collect_js_outputs(outputs)?;
+    let outputs = to_outputs(&js_outputs)?;
+    let threshold: Output = threshold.try_into()?;
+    let total_output = try_sum(&outputs).ok_or(ErrorCode::OutputsOverflowed)?;
+
+    let Some((selections, excess)) =
+        selection::select_pools(pool_inputs, &total_output, &threshold)
+    else {
+        return Ok(None);
+    };
+
+    let selected: Vec<Output> = selections.iter().flat_map(|(s, _)| s.clone()).collect();
+    let unselected: Vec<Output> = selections.iter().flat_map(|(_, u)| u.clone()).collect();
+    let result = to_select_result(&selected, &unselected, excess, &DustTokens::default());
+    let breakdown = Array::new();
+
+    for ((tag, _, _), (selected, _)) in js_pools.iter().zip(selections) {
+        let total: JsOutput = try_sum(&selected)
+            .ok_or(ErrorCode::OutputsOverflowed)?
+            .into();
+        let pool = Object::new();
+        Reflect::set(&pool, &"tag".into(), &tag.into()).expect("Unreachable");
+        Reflect::set(&pool, &"selected".into(), &to_js_output_array(&selected))
+            .expect("Unreachable");
+        Reflect::set(&pool, &"total".into(), &total).expect("Unreachable");
+        breakdown.push(&pool);
+    }
+
+    Reflect::set(&result, &"pools".into(), &breakdown).expect("Unreachable");
+
+    Ok(Some(result.unchecked_into()))
 }
 
 /**
@@ -242,29 +1516,108 @@ Raises errors when there is any value overflowed.
 */
 #[wasm_bindgen]
 pub fn sum(outputs: &JsOutputArray) -> Result<JsOutput, JsError> {
-    let js_outputs: Vec<JsOutput> = try_iter(outputs)
-        .unwrap()
-        .unwrap()
-        .into_iter()
-        .map(|i| i.unwrap().unchecked_into())
-        .collect();
-    let outputs: Vec<Output> = js_outputs.iter().map(|o| o.into()).collect();
+    let js_outputs = collect_js_outputs(outputs)?;
+    let outputs = to_outputs(&js_outputs)?;
+
+    let output: Output = Total::of(&outputs).to_output()?;
+
+    Ok(output.into())
+}
+
+/**
+Sum the values of outputs, e.g. the balance of a wallet
+
+Works as `sum`, with the same overflow checks the selection makes.
+*/
+#[wasm_bindgen(js_name = sumOutputs)]
+pub fn sum_outputs(outputs: &JsOutputArray) -> Result<JsOutput, JsError> {
+    sum(outputs)
+}
+
+/**
+Aggregate outputs paying to the same address
+
+Returns a new array in which the outputs with the same `address` are merged into one,
+at the position of the first of them, summing their lovelace and assets.
+A merged output has no `data`, and keeps the extra fields its outputs agree on.
+Outputs without an address and outputs whose address is unique are returned as they are.
+
+Raises errors when there is any value overflowed.
+*/
+#[wasm_bindgen(js_name = aggregateByAddress)]
+pub fn aggregate_by_address(outputs: &JsOutputArray) -> Result<JsOutputArray, JsError> {
+    let js_outputs = collect_js_outputs(outputs)?;
+    let outputs = to_outputs(&js_outputs)?;
+    let mut groups: Vec<Vec<Output>> = Vec::new();
+    let mut positions: BTreeMap<String, usize> = BTreeMap::new();
+
+    for output in outputs {
+        match output.data.and_then(|data| data.address()) {
+            Some(address) => match positions.get(&address) {
+                Some(&position) => groups[position].push(output),
+                None => {
+                    positions.insert(address, groups.len());
+                    groups.push(vec![output]);
+                }
+            },
+            None => groups.push(vec![output]),
+        }
+    }
+
+    let result = Array::new();
+
+    for group in groups {
+        if let [output] = &group[..] {
+            result.push(output.data.expect("Unreachable"));
+        } else {
+            let total = try_sum(&group).ok_or(ErrorCode::OutputsOverflowed)?;
+            let merged: JsOutput = total.into();
+            copy_extra_fields_of_outputs(&merged, &group);
+            result.push(&merged);
+        }
+    }
+
+    Ok(result.unchecked_into())
+}
+
+/**
+Shuffle outputs with a seed
+
+Returns a new array of the outputs in a random order, the same for the same seed.
+The seeded random number generator is the one the crate uses everywhere randomness is needed,
+so randomized orderings prepared by callers are reproducible alike.
+
+Raises errors when the types used are wrong, or the seed is not an integer within 64 bits.
+*/
+#[wasm_bindgen(js_name = shuffleOutputs)]
+pub fn shuffle_outputs(
+    outputs: &JsOutputArray,
+    #[wasm_bindgen(unchecked_param_type = "bigint")] seed: &JsValue,
+) -> Result<JsOutputArray, JsError> {
+    let mut js_outputs = collect_js_outputs(outputs)?;
+    Rng::new(to_amount(seed)?).shuffle(&mut js_outputs);
+
+    let result = Array::new();
+    for output in js_outputs.iter() {
+        result.push(output);
+    }
 
-    try_sum(&outputs)
-        .and_then(|output| Some(output.into()))
-        .ok_or_else(|| JsError::new("Outputs overflowed"))
+    Ok(result.unchecked_into())
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        select, sum, Asset, JsAsset, JsAssetArray, JsOutput, JsOutputArray,
-        Output, SelectResult,
+        aggregate_by_address, collect_inputs, collect_js_outputs, options::JsSelectOptions,
+        out_point::OutPoint, release_inputs, reserve_capacity, select, select_multi,
+        select_outcome, select_pools, select_with_options, set_strict_bigint, shuffle_outputs, sum,
+        to_outputs, try_select, Asset, ErrorCode, Failure, JsAsset, JsAssetArray, JsOutput,
+        JsOutputArray, Output, SelectRequest, SelectResult,
     };
-    use js_sys::{try_iter, Array, Object};
+    use js_sys::{try_iter, Array, Object, Reflect};
     use std::collections::BTreeMap;
     use utxo::Select;
-    use wasm_bindgen::JsCast;
+    use wasm_bindgen::{JsCast, JsError, JsValue};
     use wasm_bindgen_test::*;
 
     wasm_bindgen_test_configure!(run_in_browser);
@@ -289,7 +1642,7 @@ mod tests {
         assert_eq!(js_output.lovelace(), 1000);
         assert_eq!(js_output.assets().unchecked_into::<Array>().length(), 2);
 
-        let output: Output = (&js_output).into();
+        let output: Output = (&js_output).try_into().unwrap();
 
         assert_eq!(output.value, 1000);
         assert_eq!(output.assets.len(), 2);
@@ -393,7 +1746,7 @@ mod tests {
 
         let threshold: JsOutput = Output::zero().into();
 
-        let select_result = select(&inputs, &outputs, &threshold);
+        let select_result = select(&inputs, &outputs, &threshold, None);
         assert!(select_result.is_ok());
 
         if let Ok(select_result) = select_result {
@@ -403,7 +1756,6 @@ mod tests {
                 try_iter(&list)
                     .unwrap()
                     .unwrap()
-                    .into_iter()
                     .map(|o| o.unwrap().unchecked_into())
                     .collect()
             };
@@ -412,7 +1764,6 @@ mod tests {
                 try_iter(&list)
                     .unwrap()
                     .unwrap()
-                    .into_iter()
                     .map(|o| o.unwrap().unchecked_into())
                     .collect()
             };
@@ -460,4 +1811,802 @@ mod tests {
             assert_eq!(js_output.assets().unchecked_into::<Array>().length(), 2);
         };
     }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_pools() {
+        let lovelace_outputs = |values: &[u64]| -> Array {
+            values
+                .iter()
+                .map(|value| {
+                    JsOutput::from(Output {
+                        value: *value,
+                        assets: BTreeMap::new(),
+                        data: None,
+                    })
+                })
+                .collect()
+        };
+        let pool = |tag: &str, values: &[u64], cap: Option<u64>| {
+            let pool = Object::new();
+            Reflect::set(&pool, &"tag".into(), &tag.into()).unwrap();
+            Reflect::set(&pool, &"utxos".into(), &lovelace_outputs(values)).unwrap();
+            if let Some(cap) = cap {
+                Reflect::set(&pool, &"maxLovelace".into(), &cap.into()).unwrap();
+            }
+            pool
+        };
+        let pools: Array = [
+            pool("hot", &[1000], None),
+            pool("treasury", &[3000, 2000], Some(2500)),
+        ]
+        .iter()
+        .collect();
+        let outputs = lovelace_outputs(&[2500]).unchecked_into();
+
+        let result = select_pools(pools.unchecked_ref(), &outputs, &Output::zero().into())
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.selected().unchecked_into::<Array>().length(), 2);
+        assert_eq!(result.excess().lovelace(), 500);
+
+        let breakdown: Array = Reflect::get(&result, &"pools".into())
+            .unwrap()
+            .unchecked_into();
+        let treasury = breakdown.get(1);
+        assert_eq!(Reflect::get(&treasury, &"tag".into()).unwrap(), "treasury");
+        let total: JsOutput = Reflect::get(&treasury, &"total".into())
+            .unwrap()
+            .unchecked_into();
+        assert_eq!(total.lovelace(), 2000);
+
+        let outputs = lovelace_outputs(&[4000]).unchecked_into();
+        assert!(
+            select_pools(pools.unchecked_ref(), &outputs, &Output::zero().into())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_multi() {
+        let inputs: JsOutputArray = {
+            let result = Array::new();
+
+            for value in [5000, 3000, 1000] {
+                let output = Output {
+                    value,
+                    assets: BTreeMap::new(),
+                    data: None,
+                };
+
+                let output: JsOutput = output.into();
+                result.push(&output);
+            }
+
+            result.unchecked_into()
+        };
+
+        let targets = Array::new();
+        for value in [4000, 3500, 2000] {
+            let outputs = Array::new();
+            let output: JsOutput = Output {
+                value,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into();
+            outputs.push(&output);
+
+            let target = Object::new();
+            Reflect::set(&target, &"outputs".into(), &outputs).unwrap();
+            let threshold: JsOutput = Output::zero().into();
+            Reflect::set(&target, &"threshold".into(), &threshold).unwrap();
+            targets.push(&target);
+        }
+
+        let results = select_multi(&inputs, &targets.unchecked_into());
+        assert!(results.is_ok());
+
+        if let Ok(results) = results {
+            let results: Vec<Option<SelectResult>> = try_iter(&results)
+                .unwrap()
+                .unwrap()
+                .map(|r| {
+                    let r = r.unwrap();
+                    (!r.is_undefined()).then(|| r.unchecked_into())
+                })
+                .collect();
+
+            assert_eq!(results.len(), 3);
+
+            let first = results[0].as_ref().unwrap();
+            assert_eq!(first.selected().unchecked_into::<Array>().length(), 1);
+            assert_eq!(first.unselected().unchecked_into::<Array>().length(), 2);
+            assert_eq!(first.excess().lovelace(), 1000);
+
+            let second = results[1].as_ref().unwrap();
+            assert_eq!(second.selected().unchecked_into::<Array>().length(), 2);
+            assert_eq!(second.unselected().unchecked_into::<Array>().length(), 0);
+            assert_eq!(second.excess().lovelace(), 500);
+
+            assert!(results[2].is_none());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_outcome() {
+        let inputs: JsOutputArray = {
+            let result = Array::new();
+
+            let output: JsOutput = Output {
+                value: 3000,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into();
+            result.push(&output);
+
+            result.unchecked_into()
+        };
+        let outputs = |value| -> JsOutputArray {
+            let result = Array::new();
+
+            let output: JsOutput = Output {
+                value,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into();
+            result.push(&output);
+
+            result.unchecked_into()
+        };
+        let threshold: JsOutput = Output::zero().into();
+
+        let outcome = select_outcome(&inputs, &outputs(2000), &threshold, None);
+        assert_eq!(Reflect::get(&outcome, &"ok".into()).unwrap(), true);
+        assert_eq!(
+            outcome.unchecked_into::<SelectResult>().excess().lovelace(),
+            1000
+        );
+
+        let outcome = select_outcome(&inputs, &outputs(4000), &threshold, None);
+        assert_eq!(Reflect::get(&outcome, &"ok".into()).unwrap(), false);
+        assert_eq!(
+            Reflect::get(&outcome, &"code".into()).unwrap().as_string(),
+            Some("INSUFFICIENT_INPUT".into())
+        );
+        let shortfall: JsOutput = Reflect::get(&outcome, &"shortfall".into())
+            .unwrap()
+            .unchecked_into();
+        assert_eq!(shortfall.lovelace(), 1000);
+
+        // Of one pass over inputs iterable only once.
+        let once: JsOutputArray = inputs.unchecked_ref::<Array>().values().unchecked_into();
+        let outcome = select_outcome(&once, &outputs(4000), &threshold, None);
+        let shortfall: JsOutput = Reflect::get(&outcome, &"shortfall".into())
+            .unwrap()
+            .unchecked_into();
+        assert_eq!(shortfall.lovelace(), 1000);
+
+        let options = Object::new();
+        Reflect::set(&options, &"raiseInsufficient".into(), &true.into()).unwrap();
+        let failure = try_select(
+            &inputs,
+            &outputs(4000),
+            &threshold,
+            Some(options.unchecked_ref()),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(failure.code, ErrorCode::InsufficientInputs);
+        assert_eq!(failure.missing.map(|missing| missing.value), Some(1000));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_strict_bigint() {
+        let js_output: JsOutput = Object::new().unchecked_into();
+        js_output.set_assets(&Array::new().unchecked_into());
+
+        Reflect::set(&js_output, &"lovelace".into(), &1000.into()).unwrap();
+        let output: Output = (&js_output).try_into().unwrap();
+        assert_eq!(output.value, 1000);
+
+        let unsafe_number = JsValue::from(9007199254740993.0);
+        Reflect::set(&js_output, &"lovelace".into(), &unsafe_number).unwrap();
+        assert!(Output::try_from(&js_output).is_ok());
+
+        set_strict_bigint(true);
+        assert_eq!(
+            Output::try_from(&js_output).err().unwrap(),
+            ErrorCode::UnsafeNumber
+        );
+
+        Reflect::set(&js_output, &"lovelace".into(), &1000.into()).unwrap();
+        assert!(Output::try_from(&js_output).is_ok());
+
+        Reflect::set(&js_output, &"lovelace".into(), &(-1).into()).unwrap();
+        assert_eq!(
+            Output::try_from(&js_output).err().unwrap(),
+            ErrorCode::InvalidAmount
+        );
+        set_strict_bigint(false);
+
+        // A bigint beyond 64 bits, or negative, is rejected rather than wrapped.
+        for bigint in ["18446744073709551616", "-1"] {
+            let bigint = JsValue::bigint_from_str(bigint);
+            Reflect::set(&js_output, &"lovelace".into(), &bigint).unwrap();
+            assert_eq!(
+                Output::try_from(&js_output).err().unwrap(),
+                ErrorCode::InvalidAmount
+            );
+        }
+        let max = JsValue::bigint_from_str("18446744073709551615");
+        Reflect::set(&js_output, &"lovelace".into(), &max).unwrap();
+        assert_eq!(Output::try_from(&js_output).unwrap().value, u64::MAX);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_reserve_capacity() {
+        assert!(reserve_capacity(10_000, 4).is_ok());
+        assert!(collect_js_outputs(&Array::new()).unwrap().capacity() < 10_000);
+        let inputs = collect_inputs(&Array::new()).unwrap();
+        assert!(inputs.capacity() >= 10_000);
+        release_inputs(inputs);
+
+        // Kept across selections.
+        let threshold: JsOutput = Output::zero().into();
+        let none = Array::new();
+        assert!(select(none.unchecked_ref(), none.unchecked_ref(), &threshold, None).is_ok());
+        assert!(collect_inputs(&Array::new()).unwrap().capacity() >= 10_000);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_duplicate_inputs() {
+        let input = |value: u64, index: u32| {
+            let js_output: JsOutput = Object::new().unchecked_into();
+            js_output.set_lovelace(value);
+            let out_point = OutPoint::new(&"ab".repeat(32), index).unwrap().to_js();
+            Reflect::set(&js_output, &"data".into(), &out_point).unwrap();
+            js_output
+        };
+        let inputs: JsOutputArray =
+            Array::of3(&input(1000, 0), &input(2000, 1), &input(1000, 0)).unchecked_into();
+        let js_output: JsOutput = Object::new().unchecked_into();
+        js_output.set_lovelace(1500);
+        let outputs: JsOutputArray = Array::of1(&js_output).unchecked_into();
+        let threshold: JsOutput = Output::zero().into();
+
+        let failure = try_select(&inputs, &outputs, &threshold, None)
+            .err()
+            .unwrap();
+        assert_eq!(failure.code, ErrorCode::DuplicateInputs);
+        assert_eq!(failure.field, "inputs[2]");
+
+        let options = Object::new();
+        Reflect::set(&options, &"dedupeInputs".into(), &true.into()).unwrap();
+        let result = try_select(&inputs, &outputs, &threshold, Some(options.unchecked_ref()))
+            .unwrap()
+            .unwrap();
+        let count = |outputs: JsOutputArray| outputs.unchecked_into::<Array>().length();
+        let spent = count(result.selected()) + count(result.unselected());
+        assert_eq!(spent, 2);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_invalid_types() {
+        let js_output: JsOutput = Object::new().unchecked_into();
+        js_output.set_lovelace(1000);
+        let outputs: JsOutputArray = Array::of1(&js_output).unchecked_into();
+
+        let with_null: JsOutputArray = Array::of1(&JsValue::NULL).unchecked_into();
+        let failure = try_select(&with_null, &outputs, &js_output, None)
+            .err()
+            .unwrap();
+        assert_eq!(failure.code, ErrorCode::InvalidType);
+        assert_eq!(failure.field, "inputs[0]");
+
+        let not_iterable: JsOutputArray = JsValue::from(1).unchecked_into();
+        let failure = try_select(&not_iterable, &outputs, &js_output, None)
+            .err()
+            .unwrap();
+        assert_eq!(failure.code, ErrorCode::InvalidType);
+        assert_eq!(failure.field, "inputs");
+
+        let asset = Object::new();
+        Reflect::set(&asset, &"policyId".into(), &1.into()).unwrap();
+        Reflect::set(&asset, &"assetName".into(), &"".into()).unwrap();
+        Reflect::set(&asset, &"quantity".into(), &1.into()).unwrap();
+        Reflect::set(&js_output, &"assets".into(), &Array::of1(&asset)).unwrap();
+        assert_eq!(
+            Output::try_from(&js_output).err(),
+            Some(ErrorCode::InvalidType)
+        );
+        let failure = to_outputs(&[js_output.clone(), js_output.clone()])
+            .err()
+            .unwrap();
+        assert_eq!(failure.field, "[0].assets[0].policyId");
+
+        Reflect::set(&js_output, &"assets".into(), &1.into()).unwrap();
+        assert_eq!(
+            Output::try_from(&js_output).err(),
+            Some(ErrorCode::InvalidType)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_error_cause() {
+        let error: JsValue = JsError::from(ErrorCode::InvalidAmount).into();
+
+        assert!(Reflect::get(&error, &"cause".into())
+            .unwrap()
+            .is_undefined());
+        assert_eq!(
+            Reflect::get(&error, &"code".into()).unwrap().as_string(),
+            Some("BAD_AMOUNT".into())
+        );
+
+        let error: JsValue = JsError::from(Failure {
+            code: ErrorCode::InvalidAmount,
+            field: "inputs[1].assets[0].quantity".into(),
+            missing: None,
+        })
+        .into();
+        assert_eq!(
+            Reflect::get(&error, &"code".into()).unwrap().as_string(),
+            Some("BAD_ASSET".into())
+        );
+        let detail = Reflect::get(&error, &"detail".into()).unwrap();
+        assert_eq!(
+            Reflect::get(&detail, &"field".into()).unwrap().as_string(),
+            Some("inputs[1].assets[0].quantity".into())
+        );
+        let cause = Reflect::get(&error, &"cause".into()).unwrap();
+        assert_eq!(Reflect::get(&cause, &"code".into()).unwrap(), "BAD_AMOUNT");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_copies_extra_fields() {
+        let inputs: JsOutputArray = {
+            let result = Array::new();
+
+            for (decimals, label) in [(6, "first"), (2, "second")] {
+                let mut output = Output {
+                    value: 1000,
+                    assets: BTreeMap::new(),
+                    data: None,
+                };
+                output.insert_asset(("policy1".into(), "aname1".into()), 100);
+
+                let output: JsOutput = output.into();
+                let asset = output.assets().unchecked_into::<Array>().get(0);
+                Reflect::set(&asset, &"ticker".into(), &"TKN".into()).unwrap();
+                Reflect::set(&asset, &"decimals".into(), &decimals.into()).unwrap();
+                Reflect::set(&output, &"address".into(), &"addr1".into()).unwrap();
+                Reflect::set(&output, &"slot".into(), &1000.into()).unwrap();
+                Reflect::set(&output, &"label".into(), &label.into()).unwrap();
+                result.push(&output);
+            }
+
+            result.unchecked_into()
+        };
+        let outputs: JsOutputArray = {
+            let result = Array::new();
+
+            let mut output = Output {
+                value: 1500,
+                assets: BTreeMap::new(),
+                data: None,
+            };
+            output.insert_asset(("policy1".into(), "aname1".into()), 150);
+
+            let output: JsOutput = output.into();
+            result.push(&output);
+
+            result.unchecked_into()
+        };
+        let threshold: JsOutput = Output::zero().into();
+
+        let result = select(&inputs, &outputs, &threshold, None)
+            .unwrap()
+            .unwrap();
+        let excess = result.excess();
+        let asset = excess.assets().unchecked_into::<Array>().get(0);
+
+        assert_eq!(excess.lovelace(), 500);
+        assert!(Reflect::get(&excess, &"label".into())
+            .unwrap()
+            .is_undefined());
+        // The fields of a UTxO itself are not those of the change.
+        for key in ["address", "slot"] {
+            assert!(Reflect::get(&excess, &key.into()).unwrap().is_undefined());
+        }
+        assert_eq!(Reflect::get(&asset, &"ticker".into()).unwrap(), "TKN");
+        assert!(Reflect::get(&asset, &"decimals".into())
+            .unwrap()
+            .is_undefined());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_assets_iterable_or_missing() {
+        let bare: JsOutput = Object::new().unchecked_into();
+        bare.set_lovelace(1000);
+        let mut output = Output {
+            value: 1000,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        output.insert_asset(("policy1".into(), "aname1".into()), 100);
+        let tokens: JsOutput = output.into();
+        let asset = tokens.assets().unchecked_into::<Array>().get(0);
+        Reflect::set(&asset, &"ticker".into(), &"TKN".into()).unwrap();
+        let set = js_sys::Set::new(&JsValue::UNDEFINED);
+        set.add(&asset);
+        Reflect::set(&tokens, &"assets".into(), &set).unwrap();
+        let inputs: JsOutputArray = Array::of2(&bare, &tokens).unchecked_into();
+        let outputs: JsOutputArray = {
+            let output: JsOutput = Output {
+                value: 1500,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into();
+            Array::of1(&output).unchecked_into()
+        };
+        let threshold: JsOutput = Output::zero().into();
+
+        let result = select(&inputs, &outputs, &threshold, None)
+            .unwrap()
+            .unwrap();
+        let excess = result.excess();
+        let asset = excess.assets().unchecked_into::<Array>().get(0);
+
+        assert_eq!(excess.lovelace(), 500);
+        assert_eq!(Reflect::get(&asset, &"ticker".into()).unwrap(), "TKN");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_script_utxo() {
+        let output = |value| -> JsOutput {
+            Output {
+                value,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into()
+        };
+        let script = output(5000);
+        for (key, hex) in [
+            ("datumHash", "dd".repeat(32)),
+            ("datum", "d87980".into()),
+            ("scriptRef", "82024100".into()),
+        ] {
+            Reflect::set(&script, &key.into(), &hex.into()).unwrap();
+        }
+        let inputs: JsOutputArray = Array::of1(&script).unchecked_into();
+        let outputs: JsOutputArray = Array::of1(&output(3000)).unchecked_into();
+
+        // The change of a script UTxO spent alone holds neither its datum nor its script.
+        let excess = select(&inputs, &outputs, &output(0), None)
+            .unwrap()
+            .unwrap()
+            .excess();
+        assert_eq!(excess.lovelace(), 2000);
+        for key in ["datumHash", "datum", "scriptRef"] {
+            assert!(Reflect::get(&excess, &key.into()).unwrap().is_undefined());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_shuffle_outputs() {
+        let outputs: JsOutputArray = {
+            let result = Array::new();
+
+            for value in 1..=20 {
+                let output: JsOutput = Output {
+                    value,
+                    assets: BTreeMap::new(),
+                    data: None,
+                }
+                .into();
+                result.push(&output);
+            }
+
+            result.unchecked_into()
+        };
+        let lovelaces = |outputs: &JsOutputArray| -> Vec<u64> {
+            try_iter(outputs)
+                .unwrap()
+                .unwrap()
+                .map(|o| o.unwrap().unchecked_into::<JsOutput>().lovelace())
+                .collect()
+        };
+
+        let shuffled = lovelaces(&shuffle_outputs(&outputs, &42.into()).unwrap());
+
+        assert_eq!(shuffled.len(), 20);
+        assert_eq!(
+            shuffled,
+            lovelaces(&shuffle_outputs(&outputs, &JsValue::bigint_from_str("42")).unwrap())
+        );
+        assert_ne!(shuffled, lovelaces(&outputs));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_aggregate_by_address() {
+        let outputs: JsOutputArray = {
+            let result = Array::new();
+
+            for (value, address) in [
+                (1000, Some("addr1")),
+                (2000, Some("addr2")),
+                (3000, None),
+                (4000, Some("addr1")),
+            ] {
+                let mut output = Output {
+                    value,
+                    assets: BTreeMap::new(),
+                    data: None,
+                };
+                output.insert_asset(("policy1".into(), "aname1".into()), value);
+
+                let output: JsOutput = output.into();
+                if let Some(address) = address {
+                    output.set_address(address);
+                }
+                result.push(&output);
+            }
+
+            result.unchecked_into()
+        };
+
+        let aggregated: Vec<JsOutput> = try_iter(&aggregate_by_address(&outputs).unwrap())
+            .unwrap()
+            .unwrap()
+            .map(|o| o.unwrap().unchecked_into())
+            .collect();
+
+        assert_eq!(aggregated.len(), 3);
+        assert_eq!(aggregated[0].lovelace(), 5000);
+        assert_eq!(aggregated[0].address().unwrap(), "addr1");
+        assert_eq!(aggregated[0].assets().unchecked_into::<Array>().length(), 1);
+        assert_eq!(aggregated[1].lovelace(), 2000);
+        assert_eq!(aggregated[2].lovelace(), 3000);
+        assert!(aggregated[2].address().is_none());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_with_previous() {
+        let inputs: JsOutputArray = {
+            let result = Array::new();
+
+            for (index, value) in [4000, 3000, 2000, 1000].into_iter().enumerate() {
+                let output: JsOutput = Output {
+                    value,
+                    assets: BTreeMap::new(),
+                    data: None,
+                }
+                .into();
+                let out_point = OutPoint::new(&"ab".repeat(32), index as u32).unwrap();
+                output.set_data(&out_point.to_js());
+                result.push(&output);
+            }
+
+            result.unchecked_into()
+        };
+        let outputs = |value| -> JsOutputArray {
+            let output: JsOutput = Output {
+                value,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into();
+            Array::of1(&output).unchecked_into()
+        };
+        let threshold: JsOutput = Output::zero().into();
+        let lovelaces = |outputs: &JsOutputArray| -> Vec<u64> {
+            try_iter(outputs)
+                .unwrap()
+                .unwrap()
+                .map(|o| o.unwrap().unchecked_into::<JsOutput>().lovelace())
+                .collect()
+        };
+
+        let previous: JsOutputArray = Array::of2(
+            &inputs.unchecked_ref::<Array>().get(1),
+            &inputs.unchecked_ref::<Array>().get(2),
+        )
+        .unchecked_into();
+        let previous_result: SelectResult = Object::new().unchecked_into();
+        previous_result.set_selected(&previous);
+
+        let options: JsSelectOptions = Object::new().unchecked_into();
+        Reflect::set(&options, &"previous".into(), &previous_result).unwrap();
+
+        let result = select(
+            &inputs,
+            &outputs(5500),
+            &threshold,
+            Some(options.clone().unchecked_into()),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(lovelaces(&result.selected()), vec![3000, 2000, 4000]);
+        assert_eq!(result.excess().lovelace(), 3500);
+
+        let result = select(&inputs, &outputs(4500), &threshold, Some(options))
+            .unwrap()
+            .unwrap();
+        assert_eq!(lovelaces(&result.selected()), vec![3000, 2000]);
+
+        let result = select(&inputs, &outputs(4500), &threshold, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(lovelaces(&result.selected()), vec![4000, 3000]);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_with_options() {
+        let output = |value| -> JsOutput {
+            Output {
+                value,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into()
+        };
+        let inputs = Array::of3(&output(3000), &output(2000), &output(4000));
+        let request: SelectRequest = Object::new().unchecked_into();
+        Reflect::set(&request, &"inputs".into(), &inputs).unwrap();
+        Reflect::set(&request, &"outputs".into(), &Array::of1(&output(5500))).unwrap();
+        Reflect::set(&request, &"threshold".into(), &output(0)).unwrap();
+        Reflect::set(&request, &"strategy".into(), &"largestFirst".into()).unwrap();
+
+        let result = select_with_options(&request).unwrap().unwrap();
+        assert_eq!(result.selected().unchecked_ref::<Array>().length(), 2);
+        assert_eq!(result.excess().lovelace(), 1500);
+
+        Reflect::set(&request, &"outputs".into(), &Array::of1(&output(9500))).unwrap();
+        assert!(select_with_options(&request).unwrap().is_none());
+
+        Reflect::delete_property(request.unchecked_ref::<Object>(), &"threshold".into()).unwrap();
+        assert!(select_with_options(&request).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_mandatory() {
+        let output = |value| -> JsOutput {
+            Output {
+                value,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into()
+        };
+        let script = output(1000);
+        let inputs: JsOutputArray = Array::of2(&output(5000), &output(3000)).unchecked_into();
+        let outputs: JsOutputArray = Array::of1(&output(5500)).unchecked_into();
+        let options: JsSelectOptions = Object::new().unchecked_into();
+        Reflect::set(&options, &"mandatory".into(), &Array::of1(&script)).unwrap();
+
+        // 5000 is enough with the 1000 of the script UTxO, only passed as mandatory.
+        let result = select(&inputs, &outputs, &output(0), Some(options))
+            .unwrap()
+            .unwrap();
+        let selected: Array = result.selected().unchecked_into();
+        assert_eq!(selected.length(), 2);
+        assert!(Object::is(&selected.get(0), &script));
+        assert_eq!(result.excess().lovelace(), 500);
+        assert_eq!(result.unselected().unchecked_ref::<Array>().length(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_mandatory_auto() {
+        let output = |value| -> JsOutput {
+            Output {
+                value,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into()
+        };
+        let script = output(1000);
+        let inputs: JsOutputArray =
+            Array::of3(&output(4500), &output(5500), &script).unchecked_into();
+        let outputs: JsOutputArray = Array::of1(&output(5500)).unchecked_into();
+        let options: JsSelectOptions = Object::new().unchecked_into();
+        Reflect::set(&options, &"mandatory".into(), &Array::of1(&script)).unwrap();
+        Reflect::set(&options, &"strategy".into(), &"auto".into()).unwrap();
+        Reflect::set(&options, &"objective".into(), &"smallestChange".into()).unwrap();
+        Reflect::set(&options, &"maxInputs".into(), &2.into()).unwrap();
+
+        // 5500 alone has no change, but the script UTxO is spent, so 4500 with it.
+        let result = select(&inputs, &outputs, &output(0), Some(options))
+            .unwrap()
+            .unwrap();
+        let selected: Array = result.selected().unchecked_into();
+        assert_eq!(selected.length(), 2);
+        assert!(selected.includes(&script, 0));
+        assert_eq!(result.excess().lovelace(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_exclude() {
+        let inputs = Array::new();
+        for (index, value) in [5000, 3000].into_iter().enumerate() {
+            let output: JsOutput = Output {
+                value,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into();
+            let out_point = OutPoint::new(&"ab".repeat(32), index as u32).unwrap();
+            output.set_data(&out_point.to_js());
+            inputs.push(&output);
+        }
+        let inputs: JsOutputArray = inputs.unchecked_into();
+        let outputs: JsOutputArray = Array::new().unchecked_into();
+        let threshold: JsOutput = Output {
+            value: 1000,
+            assets: BTreeMap::new(),
+            data: None,
+        }
+        .into();
+        let options: JsSelectOptions = Object::new().unchecked_into();
+        let exclude = Array::of1(&format!("{}#0", "ab".repeat(32)).into());
+        Reflect::set(&options, &"exclude".into(), &exclude).unwrap();
+
+        let result = select(&inputs, &outputs, &threshold, Some(options))
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.excess().lovelace(), 3000);
+        let unselected: Array = result.unselected().unchecked_into();
+        assert_eq!(unselected.length(), 1);
+        assert_eq!(
+            unselected.get(0).unchecked_into::<JsOutput>().lovelace(),
+            5000
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_select_reference_inputs() {
+        let inputs = Array::new();
+        for (index, value) in [5000, 3000].into_iter().enumerate() {
+            let output: JsOutput = Output {
+                value,
+                assets: BTreeMap::new(),
+                data: None,
+            }
+            .into();
+            let out_point = OutPoint::new(&"cd".repeat(32), index as u32).unwrap();
+            output.set_data(&out_point.to_js());
+            inputs.push(&output);
+        }
+        let inputs: JsOutputArray = inputs.unchecked_into();
+        let outputs: JsOutputArray = Array::new().unchecked_into();
+        let threshold: JsOutput = Output {
+            value: 1000,
+            assets: BTreeMap::new(),
+            data: None,
+        }
+        .into();
+        let options: JsSelectOptions = Object::new().unchecked_into();
+        let references = Array::of1(&OutPoint::new(&"cd".repeat(32), 0).unwrap().to_js());
+        Reflect::set(&options, &"referenceInputs".into(), &references).unwrap();
+
+        let result = select(&inputs, &outputs, &threshold, Some(options))
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.excess().lovelace(), 2000);
+        let unselected: Array = result.unselected().unchecked_into();
+        assert_eq!(unselected.length(), 0);
+        let references: Array = Reflect::get(&result, &"referenceInputs".into())
+            .unwrap()
+            .unchecked_into();
+        assert_eq!(references.length(), 1);
+        assert_eq!(
+            references.get(0).unchecked_into::<JsOutput>().lovelace(),
+            5000
+        );
+    }
 }
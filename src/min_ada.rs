@@ -0,0 +1,297 @@
+/*!
+Minimum lovelace of outputs
+
+Since Babbage, an output should hold at least `(160 + size) * coinsPerUtxoByte` lovelace,
+where the size is of the output serialized in CBOR. The size is computed for an output
+of a base address without datum or script reference, unless they are given: the outputs
+of a datum hash only are serialized as arrays, those of an inline datum or a script
+reference as maps.
+*/
+use crate::{get_field, to_amount, ErrorCode, JsOutput, Output};
+use utxo::ExtOutput;
+use wasm_bindgen::prelude::*;
+
+/// The bytes of a base address, a header and two credentials.
+const BASE_ADDRESS_SIZE: u64 = 57;
+
+/// The bytes of the UTxO entry not in the serialized output.
+const UTXO_ENTRY_OVERHEAD: u64 = 160;
+
+/// The bytes of a datum hash.
+const DATUM_HASH_SIZE: u64 = 32;
+
+/// The bytes of the checksum of a bech32 string, in characters of 5 bits.
+const BECH32_CHECKSUM_LENGTH: u64 = 6;
+
+/// The datum of an output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Datum {
+    Hash,
+    /// Of the bytes of its CBOR.
+    Inline(u64),
+}
+
+/// What an output holds besides its value, of their sizes in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Extras {
+    /// Of a base address if `None`.
+    pub(crate) address: Option<u64>,
+    pub(crate) datum: Option<Datum>,
+    /// Of the CBOR of the script.
+    pub(crate) script_ref: Option<u64>,
+}
+
+impl Extras {
+    /// Reads the `address`, `datumHash`, `datum` and `scriptRef` of the output, in hex.
+    pub(crate) fn from_js(output: &JsOutput) -> Result<Self, ErrorCode> {
+        let get_hex = |key| -> Result<Option<String>, ErrorCode> {
+            let value = get_field(output, key)?;
+            if value.is_undefined() || value.is_null() {
+                return Ok(None);
+            }
+            value.as_string().map(Some).ok_or(ErrorCode::InvalidType)
+        };
+        let size = |hex: String| hex_size(&hex).ok_or(ErrorCode::InvalidType);
+
+        let address = match output.address() {
+            Some(address) => Some(address_size(&address).ok_or(ErrorCode::InvalidType)?),
+            None => None,
+        };
+        let datum = match (get_hex("datumHash")?, get_hex("datum")?) {
+            (None, None) => None,
+            (Some(_), None) => Some(Datum::Hash),
+            (None, Some(datum)) => Some(Datum::Inline(size(datum)?)),
+            (Some(_), Some(_)) => return Err(ErrorCode::InvalidType),
+        };
+        let script_ref = get_hex("scriptRef")?.map(size).transpose()?;
+
+        Ok(Self {
+            address,
+            datum,
+            script_ref,
+        })
+    }
+}
+
+/// The bytes in hex, `None` if it is not.
+fn hex_size(hex: &str) -> Option<u64> {
+    let valid = hex.len().is_multiple_of(2) && hex.bytes().all(|b| b.is_ascii_hexdigit());
+
+    valid.then_some(hex.len() as u64 / 2)
+}
+
+/// The bytes of an address in hex or bech32, `None` if it is neither.
+pub(crate) fn address_size(address: &str) -> Option<u64> {
+    if let Some(size) = hex_size(address) {
+        return Some(size);
+    }
+
+    let separator = address.rfind('1')?;
+    let data = (address.len() - separator - 1) as u64;
+
+    data.checked_sub(BECH32_CHECKSUM_LENGTH)
+        .map(|length| length * 5 / 8)
+        .filter(|size| *size > 0)
+}
+
+/// The bytes of an unsigned integer in CBOR, also the bytes of the header of a CBOR string or collection.
+pub(crate) fn uint_size(value: u64) -> u64 {
+    match value {
+        0..=23 => 1,
+        24..=0xff => 2,
+        0x100..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+fn bytes_size(length: u64) -> u64 {
+    uint_size(length) + length
+}
+
+/// The bytes of a value in CBOR, the policy IDs and asset names are in hex.
+pub(crate) fn value_size<D>(output: &ExtOutput<D, (String, String)>) -> u64 {
+    if output.assets.is_empty() {
+        return uint_size(output.value);
+    }
+
+    let mut policies: Vec<(&str, Vec<(&str, u64)>)> = Vec::new();
+
+    for ((policy_id, asset_name), quantity) in output.assets.iter() {
+        match policies.last_mut() {
+            Some((last, assets)) if last == policy_id => assets.push((asset_name, *quantity)),
+            _ => policies.push((policy_id, vec![(asset_name, *quantity)])),
+        }
+    }
+
+    let multi_asset: u64 = policies
+        .iter()
+        .map(|(policy_id, assets)| {
+            bytes_size(policy_id.len() as u64 / 2)
+                + uint_size(assets.len() as u64)
+                + assets
+                    .iter()
+                    .map(|(asset_name, quantity)| {
+                        bytes_size(asset_name.len() as u64 / 2) + uint_size(*quantity)
+                    })
+                    .sum::<u64>()
+        })
+        .sum();
+
+    1 + uint_size(output.value) + uint_size(policies.len() as u64) + multi_asset
+}
+
+/// The bytes of an output with the extras in CBOR.
+pub(crate) fn output_size<D>(output: &ExtOutput<D, (String, String)>, extras: &Extras) -> u64 {
+    let address = bytes_size(extras.address.unwrap_or(BASE_ADDRESS_SIZE));
+    let value = value_size(output);
+
+    match (extras.datum, extras.script_ref) {
+        (None, None) => 1 + address + value,
+        (Some(Datum::Hash), None) => 1 + address + value + bytes_size(DATUM_HASH_SIZE),
+        (datum, script_ref) => {
+            // A map of each field keyed, the datum as [0, hash] or [1, #6.24(bytes)].
+            let datum = datum.map_or(0, |datum| match datum {
+                Datum::Hash => 1 + 2 + bytes_size(DATUM_HASH_SIZE),
+                Datum::Inline(size) => 1 + 2 + 2 + bytes_size(size),
+            });
+            let script_ref = script_ref.map_or(0, |size| 1 + 2 + bytes_size(size));
+
+            1 + (1 + address) + (1 + value) + datum + script_ref
+        }
+    }
+}
+
+/**
+The minimum lovelace of an output holding the assets.
+
+The lovelace of the output itself is ignored, since it is what to compute.
+*/
+pub(crate) fn min_ada<D>(output: &ExtOutput<D, (String, String)>, coins_per_utxo_byte: u64) -> u64 {
+    min_ada_with(output, &Extras::default(), coins_per_utxo_byte)
+}
+
+/// The minimum lovelace of an output holding the assets and the extras.
+pub(crate) fn min_ada_with<D>(
+    output: &ExtOutput<D, (String, String)>,
+    extras: &Extras,
+    coins_per_utxo_byte: u64,
+) -> u64 {
+    let mut probe: ExtOutput<(), (String, String)> = ExtOutput {
+        value: 0,
+        assets: output.assets.clone(),
+        data: None,
+    };
+
+    loop {
+        let required =
+            (UTXO_ENTRY_OVERHEAD + output_size(&probe, extras)).saturating_mul(coins_per_utxo_byte);
+
+        if required <= probe.value {
+            return probe.value;
+        }
+
+        probe.value = required;
+    }
+}
+
+/**
+Compute the minimum lovelace of an output
+
+Implements the min-UTxO of Babbage, `(160 + size) * coinsPerUtxoByte`, where the size is
+of the output serialized in CBOR with its assets. The size of the `address` is of its bytes,
+in hex or bech32, or of a base address if it is missing. An output may also have a `datumHash`,
+an inline `datum` or a `scriptRef`, all in hex of their CBOR.
+The lovelace of the output is ignored, so this returns what it should hold at least.
+
+Raises errors when the types used are wrong, or the fields are not valid hex or bech32.
+*/
+#[wasm_bindgen(js_name = minAda)]
+pub fn min_ada_of_output(
+    output: &JsOutput,
+    #[wasm_bindgen(unchecked_param_type = "bigint")] coins_per_utxo_byte: &JsValue,
+) -> Result<u64, JsError> {
+    let coins_per_utxo_byte = to_amount(coins_per_utxo_byte)?;
+    let extras = Extras::from_js(output)?;
+    let output: Output = output.try_into()?;
+
+    Ok(min_ada_with(&output, &extras, coins_per_utxo_byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::min_ada::{
+        address_size, min_ada, min_ada_with, uint_size, value_size, Datum, Extras,
+    };
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_uint_size() {
+        assert_eq!(uint_size(23), 1);
+        assert_eq!(uint_size(24), 2);
+        assert_eq!(uint_size(65536), 5);
+        assert_eq!(uint_size(u64::MAX), 9);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_min_ada() {
+        let mut output: ExtOutput<(), (String, String)> = ExtOutput {
+            value: 0,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+
+        // (160 + 1 + 59 + 5) * 4310
+        assert_eq!(min_ada(&output, 4310), 969750);
+
+        output.insert_asset(("ab".repeat(28), "cd".repeat(4)), 1);
+        assert_eq!(value_size(&output), 1 + 1 + 1 + 30 + 1 + 5 + 1);
+        assert!(min_ada(&output, 4310) > 969750);
+        assert!(min_ada(&output, 4310) < 1_200_000);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_min_ada_with() {
+        let output: ExtOutput<(), (String, String)> = ExtOutput {
+            value: 0,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        let address = "addr1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgse35a3x";
+
+        assert_eq!(address_size(address), Some(57));
+        assert_eq!(address_size(&"61".repeat(29)), Some(29));
+        assert_eq!(address_size("not an address"), None);
+
+        let base = Extras::default();
+        assert_eq!(min_ada_with(&output, &base, 4310), min_ada(&output, 4310));
+        let enterprise = Extras {
+            address: Some(29),
+            ..base
+        };
+        assert_eq!(
+            min_ada_with(&output, &enterprise, 4310),
+            (160 + 1 + 31 + 5) * 4310
+        );
+        let hashed = Extras {
+            datum: Some(Datum::Hash),
+            ..base
+        };
+        assert_eq!(
+            min_ada_with(&output, &hashed, 4310),
+            (160 + 1 + 59 + 5 + 34) * 4310
+        );
+        // A map of 4 entries, inline datum of 10 bytes and script of 100 bytes.
+        let inline = Extras {
+            datum: Some(Datum::Inline(10)),
+            script_ref: Some(100),
+            ..base
+        };
+        assert_eq!(
+            min_ada_with(&output, &inline, 4310),
+            (160 + 1 + 60 + 6 + 16 + 105) * 4310
+        );
+    }
+}
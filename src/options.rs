@@ -0,0 +1,742 @@
+/*!
+Options of selection
+
+New behaviours of `select` are added as optional fields of `SelectOptions`,
+so the positional arguments never change.
+*/
+#[cfg(feature = "fee")]
+use crate::protocol::{JsProtocolParameters, ProtocolParameters};
+use crate::{
+    clock, collect_js_outputs, get_amount, get_count, get_field, iterate,
+    out_point::OutPoint,
+    strategy::{dust::DUST_MAX_INPUTS, registered},
+    to_amount, ErrorCode, JsOutput, Output,
+};
+use js_sys::{Function, Object};
+use std::collections::{BTreeMap, BTreeSet};
+use utxo::ExtOutput;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type DustTokenOptions = {
+  ratio?: number
+  quantity?: bigint
+}
+
+export type Objective = "fewestInputs" | "smallestChange" | "fewestChangeAssets"
+
+export type Strategy =
+  | "default"
+  | "auto"
+  | "randomImprove"
+  | "largestFirst"
+  | "branchAndBound"
+  | "knapsack"
+  | "dust"
+  | "fewestInputs"
+  | "oldestFirst"
+  | (string & {})
+
+export type SelectOptions = FeeSelectOptions & {
+  previous?: SelectResult
+  dustTokens?: DustTokenOptions
+  alternatives?: number
+  objective?: Objective
+  strategy?: Strategy
+  timeBudgetMs?: number
+  seed?: bigint
+  matchTolerance?: bigint
+  maxInputs?: number
+  assetWeights?: Array<AssetWeight>
+  minChange?: MinChange
+  absorbChange?: bigint
+  consolidate?: ConsolidateOptions
+  requireAsset?: RequireAssetOptions
+  profile?: boolean
+  avoidMixingAddresses?: boolean
+  dedupeInputs?: boolean
+  chunkSize?: number
+  transferable?: boolean
+  raiseInsufficient?: boolean
+  tokenDustFloor?: bigint | Array<Asset>
+  mandatory?: Array<Output>
+  exclude?: Array<OutPoint | string>
+  referenceInputs?: Array<OutPoint | string>
+  mergeChangeInto?: number | string
+  score?: (output: Output) => number
+}
+
+export type RequireAssetOptions = {
+  policyId: string
+  assetName: string
+  fallback?: boolean
+}
+
+export type ConsolidateOptions = {
+  maxInputs: number
+  minLovelace?: bigint
+  maxLovelace?: bigint
+}
+
+export type AssetWeight = {
+  policyId: string
+  assetName: string
+  weight: number
+}
+"#;
+
+#[cfg(feature = "fee")]
+#[wasm_bindgen(typescript_custom_section)]
+const TS_FEE_OPTIONS: &str = r#"
+export type FeeSelectOptions = {
+  excludeUnspendable?: ProtocolParameters
+  splitOversizedChange?: ProtocolParameters
+}
+
+export type MinChange = bigint | { coinsPerUtxoByte: bigint }
+
+export type AutoThreshold = ProtocolParameters
+"#;
+
+#[cfg(not(feature = "fee"))]
+#[wasm_bindgen(typescript_custom_section)]
+const TS_FEE_OPTIONS: &str = r#"
+export type FeeSelectOptions = {}
+
+export type MinChange = bigint
+
+export type AutoThreshold = never
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SelectOptions")]
+    pub type JsSelectOptions;
+}
+
+/// UTxOs recognised by their `OutPoint`, or by the objects themselves when they have none.
+#[derive(Clone, Default)]
+pub(crate) struct KnownUtxos {
+    out_points: BTreeSet<OutPoint>,
+    objects: Vec<JsOutput>,
+}
+
+impl KnownUtxos {
+    pub(crate) fn new(utxos: Vec<JsOutput>) -> Self {
+        let mut set = Self::default();
+
+        for utxo in utxos {
+            match OutPoint::of(&utxo) {
+                Some(out_point) => {
+                    set.out_points.insert(out_point);
+                }
+                None => set.objects.push(utxo),
+            }
+        }
+
+        set
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.out_points.is_empty() && self.objects.is_empty()
+    }
+
+    pub(crate) fn contains(&self, utxo: &JsOutput) -> bool {
+        match OutPoint::of(utxo) {
+            Some(out_point) => self.out_points.contains(&out_point),
+            None => self.objects.iter().any(|object| Object::is(object, utxo)),
+        }
+    }
+}
+
+/// The thresholds below which an asset of the excess is dust.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct DustTokens {
+    /// Of the quantity the inputs hold in total.
+    pub(crate) ratio: f64,
+    /// The quantity, regardless of the holdings.
+    pub(crate) quantity: u64,
+}
+
+impl Default for DustTokens {
+    fn default() -> Self {
+        Self {
+            ratio: 0.01,
+            quantity: 0,
+        }
+    }
+}
+
+impl DustTokens {
+    fn from_js(value: &JsValue) -> Result<Self, ErrorCode> {
+        let mut dust = Self::default();
+        let ratio = get_field(value, "ratio")?;
+
+        if !ratio.is_undefined() {
+            dust.ratio = ratio
+                .as_f64()
+                .filter(|r| (0.0..=1.0).contains(r))
+                .ok_or(ErrorCode::InvalidType)?;
+        }
+
+        if let Some(quantity) = get_amount(value, "quantity")? {
+            dust.quantity = quantity;
+        }
+
+        Ok(dust)
+    }
+}
+
+/// The quantities below which an asset left in the excess is token dust.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct TokenDustFloor {
+    /// Of the assets not listed.
+    pub(crate) quantity: u64,
+    pub(crate) assets: BTreeMap<(String, String), u64>,
+}
+
+impl TokenDustFloor {
+    pub(crate) fn of(&self, key: &(String, String)) -> u64 {
+        self.assets.get(key).copied().unwrap_or(self.quantity)
+    }
+
+    fn from_js(value: &JsValue) -> Result<Self, ErrorCode> {
+        if !value.is_object() {
+            return Ok(Self {
+                quantity: to_amount(value)?,
+                assets: BTreeMap::new(),
+            });
+        }
+
+        let mut assets = BTreeMap::new();
+        for asset in iterate(value)? {
+            let get_string = |key| {
+                get_field(&asset, key)?
+                    .as_string()
+                    .ok_or(ErrorCode::InvalidType)
+            };
+            let key = (get_string("policyId")?, get_string("assetName")?);
+            let quantity = get_amount(&asset, "quantity")?.ok_or(ErrorCode::InvalidType)?;
+            assets.insert(key, quantity);
+        }
+
+        Ok(Self {
+            quantity: 0,
+            assets,
+        })
+    }
+}
+
+/// The output to merge the change into, of the outputs passed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum MergeChange {
+    Index(usize),
+    /// The first output of the address.
+    Address(String),
+}
+
+impl MergeChange {
+    fn from_js(value: &JsValue) -> Result<Self, ErrorCode> {
+        if let Some(address) = value.as_string() {
+            return Ok(Self::Address(address));
+        }
+
+        match value.as_f64() {
+            Some(n) if n >= 0.0 && n.fract() == 0.0 && n <= u32::MAX as f64 => {
+                Ok(Self::Index(n as usize))
+            }
+            _ => Err(ErrorCode::InvalidType),
+        }
+    }
+
+    /// The index of the output to merge into, `None` if there is none.
+    pub(crate) fn index_of(&self, outputs: &[Output]) -> Option<usize> {
+        match self {
+            Self::Index(index) => (*index < outputs.len()).then_some(*index),
+            Self::Address(address) => outputs.iter().position(|output| {
+                output
+                    .data
+                    .and_then(|data| data.address())
+                    .is_some_and(|a| a == *address)
+            }),
+        }
+    }
+}
+
+/// What makes a selection better than another.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Objective {
+    #[default]
+    FewestInputs,
+    SmallestChange,
+    /// The fewest distinct assets in the excess, for the least minimum lovelace of the change.
+    FewestChangeAssets,
+}
+
+impl Objective {
+    fn from_js(value: &JsValue) -> Result<Self, ErrorCode> {
+        match value.as_string().as_deref() {
+            Some("fewestInputs") => Ok(Self::FewestInputs),
+            Some("smallestChange") => Ok(Self::SmallestChange),
+            Some("fewestChangeAssets") => Ok(Self::FewestChangeAssets),
+            _ => Err(ErrorCode::InvalidType),
+        }
+    }
+}
+
+/// The time the `auto` strategy takes at most by default.
+const TIME_BUDGET_MS: f64 = 50.0;
+
+/// How the inputs are selected.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) enum Strategy {
+    /// As `utxo::select`.
+    #[default]
+    Default,
+    /// The best by the objective of what the strategies find in the time budget.
+    Auto { budget_ms: f64 },
+    /// Random-Improve of CIP-2 with the seed of its randomness.
+    RandomImprove { seed: u64 },
+    /// Largest-First of CIP-2, for the fewest inputs.
+    LargestFirst,
+    /// Inputs matching exactly up to the tolerance of lovelace over, for no change.
+    BranchAndBound { tolerance: u64 },
+    /// The smallest change of random subsets, by the seed of their randomness.
+    Knapsack { seed: u64 },
+    /// A search for the fewest inputs, when the size of the transaction binds.
+    FewestInputs,
+    /// The smallest inputs first to consolidate dust, unless more than the cap are needed.
+    Dust { max_inputs: usize },
+    /// The strategy registered of the name, see [`crate::strategy`].
+    Registered(String),
+    /// In the order of the inputs sorted by the `score` option.
+    Scored,
+    /// From the inputs created at the earliest `slot`.
+    OldestFirst,
+}
+
+impl Strategy {
+    /// The seed of the randomness of the strategy, if random.
+    pub(crate) fn seed(&self) -> Option<u64> {
+        match self {
+            Self::RandomImprove { seed } | Self::Knapsack { seed } => Some(*seed),
+            _ => None,
+        }
+    }
+
+    fn from_js(options: &JsValue) -> Result<Self, ErrorCode> {
+        let strategy = get_field(options, "strategy")?;
+        let budget_ms = get_field(options, "timeBudgetMs")?;
+        let budget_ms = if budget_ms.is_undefined() {
+            TIME_BUDGET_MS
+        } else {
+            budget_ms
+                .as_f64()
+                .filter(|ms| *ms >= 0.0)
+                .ok_or(ErrorCode::InvalidType)?
+        };
+
+        let seed = || -> Result<u64, ErrorCode> {
+            Ok(get_amount(options, "seed")?.unwrap_or_else(|| clock::now().to_bits()))
+        };
+
+        if strategy.is_undefined() {
+            return Ok(Self::Default);
+        }
+
+        match strategy.as_string().as_deref() {
+            Some("default") => Ok(Self::Default),
+            Some("auto") => Ok(Self::Auto { budget_ms }),
+            Some("largestFirst") => Ok(Self::LargestFirst),
+            Some("branchAndBound") => Ok(Self::BranchAndBound {
+                tolerance: get_amount(options, "matchTolerance")?.unwrap_or_default(),
+            }),
+            Some("randomImprove") => Ok(Self::RandomImprove { seed: seed()? }),
+            Some("knapsack") => Ok(Self::Knapsack { seed: seed()? }),
+            Some("fewestInputs") => Ok(Self::FewestInputs),
+            Some("oldestFirst") => Ok(Self::OldestFirst),
+            Some("dust") => Ok(Self::Dust {
+                max_inputs: get_count(options, "maxInputs")?
+                    .map_or(DUST_MAX_INPUTS, |count| count as usize),
+            }),
+            Some(name) if registered(name).is_some() => Ok(Self::Registered(name.into())),
+            _ => Err(ErrorCode::InvalidType),
+        }
+    }
+}
+
+/// The lovelace any change must have at least.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MinChange {
+    Lovelace(u64),
+    /// The minimum lovelace of the change with its assets.
+    #[cfg(feature = "fee")]
+    MinAda {
+        coins_per_utxo_byte: u64,
+    },
+    /// The minimum lovelace of the change split into values of at most `max_value_size` bytes.
+    #[cfg(feature = "fee")]
+    Bundled {
+        coins_per_utxo_byte: u64,
+        max_value_size: u64,
+    },
+}
+
+impl MinChange {
+    #[cfg_attr(not(feature = "fee"), allow(unused_variables))]
+    pub(crate) fn of<D>(&self, change: &ExtOutput<D, (String, String)>) -> u64 {
+        match self {
+            Self::Lovelace(lovelace) => *lovelace,
+            #[cfg(feature = "fee")]
+            Self::MinAda {
+                coins_per_utxo_byte,
+            } => crate::min_ada::min_ada(change, *coins_per_utxo_byte),
+            #[cfg(feature = "fee")]
+            Self::Bundled {
+                coins_per_utxo_byte,
+                max_value_size,
+            } => crate::bundle::min_ada_of_bundles(change, *max_value_size, *coins_per_utxo_byte)
+                .unwrap_or(u64::MAX),
+        }
+    }
+
+    fn from_js(value: &JsValue) -> Result<Self, ErrorCode> {
+        if !value.is_object() {
+            return Ok(Self::Lovelace(to_amount(value)?));
+        }
+
+        #[cfg(feature = "fee")]
+        if let Some(coins_per_utxo_byte) = get_amount(value, "coinsPerUtxoByte")? {
+            return Ok(Self::MinAda {
+                coins_per_utxo_byte,
+            });
+        }
+
+        Err(ErrorCode::InvalidType)
+    }
+}
+
+/// Which inputs to merge into the change, whether or not they are needed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Consolidate {
+    pub(crate) max_inputs: usize,
+    pub(crate) min_lovelace: u64,
+    pub(crate) max_lovelace: u64,
+}
+
+impl Consolidate {
+    pub(crate) fn accepts(&self, lovelace: u64) -> bool {
+        (self.min_lovelace..=self.max_lovelace).contains(&lovelace)
+    }
+
+    fn from_js(value: &JsValue) -> Result<Self, ErrorCode> {
+        Ok(Self {
+            max_inputs: get_count(value, "maxInputs")?.ok_or(ErrorCode::InvalidType)? as usize,
+            min_lovelace: get_amount(value, "minLovelace")?.unwrap_or_default(),
+            max_lovelace: get_amount(value, "maxLovelace")?.unwrap_or(u64::MAX),
+        })
+    }
+}
+
+/// Select only from the inputs holding the asset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RequireAsset {
+    pub(crate) asset: (String, String),
+    /// Top lovelace up from the other inputs if those holding the asset are not enough.
+    pub(crate) fallback: bool,
+}
+
+impl RequireAsset {
+    fn from_js(value: &JsValue) -> Result<Self, ErrorCode> {
+        let get_string = |key| {
+            get_field(value, key)?
+                .as_string()
+                .ok_or(ErrorCode::InvalidType)
+        };
+        let fallback = get_field(value, "fallback")?;
+
+        Ok(Self {
+            asset: (get_string("policyId")?, get_string("assetName")?),
+            fallback: if fallback.is_undefined() {
+                false
+            } else {
+                fallback.as_bool().ok_or(ErrorCode::InvalidType)?
+            },
+        })
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct SelectOptions {
+    /// The inputs selected previously, to be kept if they are still available.
+    pub(crate) previous: KnownUtxos,
+    pub(crate) dust_tokens: DustTokens,
+    /// The number of alternative selections to return.
+    pub(crate) alternatives: usize,
+    pub(crate) objective: Objective,
+    pub(crate) strategy: Strategy,
+    /// Positive to prefer spending the inputs holding the asset, negative to avoid them.
+    pub(crate) asset_weights: BTreeMap<(String, String), f64>,
+    /// Non-zero change must have at least this lovelace.
+    pub(crate) min_change: Option<MinChange>,
+    /// The most lovelace of change below its minimum to donate to the fee.
+    pub(crate) absorb_change: u64,
+    pub(crate) consolidate: Option<Consolidate>,
+    pub(crate) require_asset: Option<RequireAsset>,
+    /// Leave the unspendable inputs out of selection, see `findUnspendable`.
+    #[cfg(feature = "fee")]
+    pub(crate) exclude_unspendable: Option<ProtocolParameters>,
+    /// Split the change whose value is larger than `maxValueSize` of the parameters.
+    #[cfg(feature = "fee")]
+    pub(crate) split_oversized_change: Option<ProtocolParameters>,
+    /// Record the timings of the phases of selection.
+    pub(crate) profile: bool,
+    /// The callback scoring the inputs, those of higher scores selected first.
+    pub(crate) score: Option<Function>,
+    /// Select from the inputs of as few addresses as possible.
+    pub(crate) avoid_mixing_addresses: bool,
+    /// Keep the first of the inputs of the same `OutPoint`, rather than raising an error.
+    pub(crate) dedupe_inputs: bool,
+    /// Copy the UTxOs of the result to plain objects, see `toTransferable`.
+    pub(crate) transferable: bool,
+    /// Raise an error of what is missing rather than returning nothing when the inputs are not enough.
+    pub(crate) raise_insufficient: bool,
+    /// The inputs selected at most.
+    pub(crate) max_inputs: Option<usize>,
+    pub(crate) token_dust_floor: Option<TokenDustFloor>,
+    /// The inputs to select regardless, e.g. a script UTxO being spent.
+    pub(crate) mandatory: Vec<JsOutput>,
+    /// The inputs never to select, e.g. those spent by pending transactions.
+    pub(crate) exclude: BTreeSet<OutPoint>,
+    /// The inputs only referenced by the transaction, never spent.
+    pub(crate) reference_inputs: BTreeSet<OutPoint>,
+    pub(crate) merge_change: Option<MergeChange>,
+}
+
+impl SelectOptions {
+    /// Adds the mandatory inputs missing from the inputs.
+    pub(crate) fn add_mandatory(&self, inputs: &mut Vec<JsOutput>) {
+        if self.mandatory.is_empty() {
+            return;
+        }
+
+        let known = KnownUtxos::new(inputs.clone());
+        let missing: Vec<JsOutput> = self
+            .mandatory
+            .iter()
+            .filter(|utxo| !known.contains(utxo))
+            .cloned()
+            .collect();
+
+        inputs.extend(missing);
+    }
+
+    /// Takes the reference inputs out of the inputs, returning them.
+    pub(crate) fn take_reference_inputs(&self, inputs: &mut Vec<JsOutput>) -> Vec<JsOutput> {
+        if self.reference_inputs.is_empty() {
+            return Vec::new();
+        }
+
+        let (references, spendable) = inputs.drain(..).partition(|input| {
+            OutPoint::of(input).is_some_and(|out_point| self.reference_inputs.contains(&out_point))
+        });
+        *inputs = spendable;
+
+        references
+    }
+}
+
+/// Reads the `OutPoint`s, or their keys, of the array at the key.
+fn get_out_points(options: &JsValue, key: &str) -> Result<BTreeSet<OutPoint>, ErrorCode> {
+    let mut out_points = BTreeSet::new();
+    let ids = get_field(options, key)?;
+
+    if !ids.is_undefined() {
+        for id in iterate(&ids)? {
+            let out_point = match id.as_string() {
+                Some(key) => OutPoint::from_key(&key),
+                None => OutPoint::from_js(&id),
+            };
+            out_points.insert(out_point.ok_or(ErrorCode::InvalidType)?);
+        }
+    }
+
+    Ok(out_points)
+}
+
+impl TryFrom<Option<&JsSelectOptions>> for SelectOptions {
+    type Error = ErrorCode;
+
+    fn try_from(value: Option<&JsSelectOptions>) -> Result<Self, Self::Error> {
+        let Some(options) = value else {
+            return Ok(Self::default());
+        };
+        let previous = get_field(options, "previous")?;
+        let previous = if previous.is_undefined() || previous.is_null() {
+            KnownUtxos::default()
+        } else {
+            let selected = get_field(&previous, "selected")?;
+            KnownUtxos::new(collect_js_outputs(selected.unchecked_ref())?)
+        };
+
+        let dust_tokens = get_field(options, "dustTokens")?;
+        let dust_tokens = if dust_tokens.is_undefined() {
+            DustTokens::default()
+        } else {
+            DustTokens::from_js(&dust_tokens)?
+        };
+
+        let alternatives = get_count(options, "alternatives")?.unwrap_or_default() as usize;
+        let objective = get_field(options, "objective")?;
+        let objective = if objective.is_undefined() {
+            Objective::default()
+        } else {
+            Objective::from_js(&objective)?
+        };
+
+        let score = get_field(options, "score")?;
+        let score = if score.is_undefined() {
+            None
+        } else {
+            Some(
+                score
+                    .dyn_into::<Function>()
+                    .map_err(|_| ErrorCode::InvalidType)?,
+            )
+        };
+        let strategy = match (Strategy::from_js(options)?, &score) {
+            (Strategy::Default, Some(_)) => Strategy::Scored,
+            (_, Some(_)) => return Err(ErrorCode::InvalidType),
+            (strategy, None) => strategy,
+        };
+
+        let mut asset_weights = BTreeMap::new();
+        let weights = get_field(options, "assetWeights")?;
+        if !weights.is_undefined() {
+            for weight in iterate(&weights)? {
+                let get_string = |key| {
+                    get_field(&weight, key)?
+                        .as_string()
+                        .ok_or(ErrorCode::InvalidType)
+                };
+                let key = (get_string("policyId")?, get_string("assetName")?);
+                let value = get_field(&weight, "weight")?
+                    .as_f64()
+                    .filter(|w| w.is_finite())
+                    .ok_or(ErrorCode::InvalidType)?;
+                asset_weights.insert(key, value);
+            }
+        }
+
+        let min_change = get_field(options, "minChange")?;
+        #[cfg_attr(not(feature = "fee"), allow(unused_mut))]
+        let mut min_change = if min_change.is_undefined() {
+            None
+        } else {
+            Some(MinChange::from_js(&min_change)?)
+        };
+
+        let absorb_change = get_amount(options, "absorbChange")?.unwrap_or_default();
+        let consolidate = get_field(options, "consolidate")?;
+        let consolidate = if consolidate.is_undefined() {
+            None
+        } else {
+            Some(Consolidate::from_js(&consolidate)?)
+        };
+        let require_asset = get_field(options, "requireAsset")?;
+        let require_asset = if require_asset.is_undefined() {
+            None
+        } else {
+            Some(RequireAsset::from_js(&require_asset)?)
+        };
+        let get_flag = |key| {
+            let flag = get_field(options, key)?;
+            if flag.is_undefined() {
+                Ok(false)
+            } else {
+                flag.as_bool().ok_or(ErrorCode::InvalidType)
+            }
+        };
+        let profile = get_flag("profile")?;
+        let avoid_mixing_addresses = get_flag("avoidMixingAddresses")?;
+        let dedupe_inputs = get_flag("dedupeInputs")?;
+        let transferable = get_flag("transferable")?;
+        let raise_insufficient = get_flag("raiseInsufficient")?;
+        let max_inputs = get_count(options, "maxInputs")?.map(|count| count as usize);
+        let mandatory = get_field(options, "mandatory")?;
+        let mandatory = if mandatory.is_undefined() {
+            Vec::new()
+        } else {
+            collect_js_outputs(mandatory.unchecked_ref())?
+        };
+        let exclude = get_out_points(options, "exclude")?;
+        let reference_inputs = get_out_points(options, "referenceInputs")?;
+        let merge_change = get_field(options, "mergeChangeInto")?;
+        let merge_change = if merge_change.is_undefined() {
+            None
+        } else {
+            Some(MergeChange::from_js(&merge_change)?)
+        };
+        let token_dust_floor = get_field(options, "tokenDustFloor")?;
+        let token_dust_floor = if token_dust_floor.is_undefined() {
+            None
+        } else {
+            Some(TokenDustFloor::from_js(&token_dust_floor)?)
+        };
+
+        #[cfg(feature = "fee")]
+        let get_params = |key| -> Result<Option<ProtocolParameters>, ErrorCode> {
+            let params = get_field(options, key)?;
+            if params.is_undefined() || params.is_null() {
+                Ok(None)
+            } else {
+                Ok(Some(ProtocolParameters::try_from(
+                    params.unchecked_ref::<JsProtocolParameters>(),
+                )?))
+            }
+        };
+        #[cfg(feature = "fee")]
+        let exclude_unspendable = get_params("excludeUnspendable")?;
+        #[cfg(feature = "fee")]
+        let split_oversized_change = get_params("splitOversizedChange")?;
+        #[cfg(feature = "fee")]
+        if let Some(params) = split_oversized_change {
+            if matches!(min_change, None | Some(MinChange::MinAda { .. })) {
+                min_change = Some(MinChange::Bundled {
+                    coins_per_utxo_byte: params.coins_per_utxo_byte,
+                    max_value_size: params.max_value_size,
+                });
+            }
+        }
+
+        Ok(Self {
+            previous,
+            dust_tokens,
+            alternatives,
+            objective,
+            strategy,
+            asset_weights,
+            min_change,
+            absorb_change,
+            consolidate,
+            require_asset,
+            #[cfg(feature = "fee")]
+            exclude_unspendable,
+            #[cfg(feature = "fee")]
+            split_oversized_change,
+            profile,
+            score,
+            avoid_mixing_addresses,
+            dedupe_inputs,
+            transferable,
+            raise_insufficient,
+            max_inputs,
+            token_dust_floor,
+            mandatory,
+            exclude,
+            reference_inputs,
+            merge_change,
+        })
+    }
+}
@@ -0,0 +1,154 @@
+/*!
+Identities of UTxOs
+
+A UTxO is identified by the hash of the transaction creating it and its index
+in the outputs of that transaction, which are the `hash` and `index` of its `data`.
+*/
+use crate::{raise, ErrorKind, JsOutput};
+use js_sys::{Object, Reflect};
+use std::collections::BTreeSet;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "OutPoint")]
+    pub type JsOutPoint;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct OutPoint {
+    /// The transaction hash in lower case hex.
+    pub(crate) hash: String,
+    pub(crate) index: u32,
+}
+
+impl OutPoint {
+    pub(crate) fn new(hash: &str, index: u32) -> Option<Self> {
+        let valid = hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit());
+
+        valid.then(|| Self {
+            hash: hash.to_ascii_lowercase(),
+            index,
+        })
+    }
+
+    /// The canonical key `hash#index`.
+    pub(crate) fn key(&self) -> String {
+        format!("{}#{}", self.hash, self.index)
+    }
+
+    pub(crate) fn from_key(key: &str) -> Option<Self> {
+        let (hash, index) = key.split_once('#')?;
+
+        if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        Self::new(hash, index.parse().ok()?)
+    }
+
+    /// Reads an `OutPoint` object, i.e. `{ hash, index }`.
+    pub(crate) fn from_js(value: &JsValue) -> Option<Self> {
+        if !value.is_object() {
+            return None;
+        }
+
+        let hash = Reflect::get(value, &"hash".into()).ok()?.as_string()?;
+        let index = Reflect::get(value, &"index".into()).ok()?.as_f64()?;
+
+        if index < 0.0 || index.fract() != 0.0 || index > u32::MAX as f64 {
+            return None;
+        }
+
+        Self::new(&hash, index as u32)
+    }
+
+    /// The identity of an output, from its `data`.
+    pub(crate) fn of(output: &JsOutput) -> Option<Self> {
+        Self::from_js(&Reflect::get(output, &"data".into()).ok()?)
+    }
+
+    pub(crate) fn to_js(&self) -> JsOutPoint {
+        let result = Object::new();
+        Reflect::set(&result, &"hash".into(), &self.hash.as_str().into()).expect("Unreachable");
+        Reflect::set(&result, &"index".into(), &self.index.into()).expect("Unreachable");
+        result.unchecked_into()
+    }
+}
+
+/**
+Make the canonical key of an OutPoint
+
+Returns `hash#index` with the hash in lower case, the same identity the selector
+uses to recognise UTxOs, so it can be used as the key of Maps and Sets in JS.
+
+Raises errors when the hash is not 32 bytes in hex or the index is not an integer.
+*/
+#[wasm_bindgen(js_name = outPointKey)]
+pub fn out_point_key(id: &JsOutPoint) -> Result<String, JsError> {
+    OutPoint::from_js(id)
+        .map(|out_point| out_point.key())
+        .ok_or_else(|| raise(ErrorKind::BadOutPoint, "Invalid OutPoint"))
+}
+
+/**
+Parse the key made by `outPointKey` back to an OutPoint
+
+Raises errors when the key is malformed.
+*/
+#[wasm_bindgen(js_name = parseOutPointKey)]
+pub fn parse_out_point_key(key: &str) -> Result<JsOutPoint, JsError> {
+    OutPoint::from_key(key)
+        .map(|out_point| out_point.to_js())
+        .ok_or_else(|| raise(ErrorKind::BadOutPoint, "Invalid OutPoint key"))
+}
+
+/// The indices of the UTxOs of the same `OutPoint` as one before them, never of those without one.
+pub(crate) fn repeated(utxos: &[JsOutput]) -> Vec<usize> {
+    let mut seen = BTreeSet::new();
+
+    utxos
+        .iter()
+        .enumerate()
+        .filter(|(_, utxo)| OutPoint::of(utxo).is_some_and(|out_point| !seen.insert(out_point)))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::out_point::{out_point_key, parse_out_point_key, OutPoint};
+    use js_sys::Reflect;
+    use wasm_bindgen_test::*;
+
+    const HASH: &str = "09D34606ABDCD0B10EBC89307CBFA0B469F9144194137B45B7A04B273961ADD8";
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_out_point_key() {
+        let out_point = OutPoint::new(HASH, 3).unwrap();
+        let key = out_point.key();
+
+        assert_eq!(key, format!("{}#3", HASH.to_ascii_lowercase()));
+        assert_eq!(OutPoint::from_key(&key), Some(out_point));
+        assert_eq!(OutPoint::from_key(&format!("{HASH}#+3")), None);
+        assert_eq!(OutPoint::from_key(&format!("{HASH}#")), None);
+        assert_eq!(OutPoint::from_key("abc#3"), None);
+        assert_eq!(OutPoint::new(&HASH[2..], 3), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_js_out_point_key() {
+        let id = OutPoint::new(HASH, 3).unwrap().to_js();
+        Reflect::set(&id, &"hash".into(), &HASH.into()).unwrap();
+
+        let key = out_point_key(&id).unwrap();
+        let parsed = parse_out_point_key(&key).unwrap();
+
+        assert_eq!(
+            Reflect::get(&parsed, &"hash".into()).unwrap(),
+            HASH.to_ascii_lowercase()
+        );
+        assert_eq!(Reflect::get(&parsed, &"index".into()).unwrap(), 3);
+        assert!(parse_out_point_key("nohash").is_err());
+    }
+}
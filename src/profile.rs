@@ -0,0 +1,86 @@
+/*!
+Profiling of selection
+
+With the `profile` option, `select` records how long each of its phases takes,
+returned as `timings` of the result and added as measures of `performance`
+named `cardano-utxo-wasm:<phase>` where the API is available, e.g. for the devtools of browsers.
+*/
+use crate::clock;
+use js_sys::{Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type Timings = {
+  parse: number
+  select: number
+  build: number
+  total: number
+}
+"#;
+
+/// The timings of the phases in milliseconds, recorded only if enabled.
+pub(crate) struct Profiler {
+    enabled: bool,
+    start: f64,
+    last: f64,
+    phases: Vec<(&'static str, f64)>,
+}
+
+impl Profiler {
+    /// Starts profiling from the time, taken before whether to profile is known.
+    pub(crate) fn new(enabled: bool, start: f64) -> Self {
+        Self {
+            enabled,
+            start,
+            last: start,
+            phases: Vec::new(),
+        }
+    }
+
+    /// Ends the phase of the name, started at the end of the last one.
+    pub(crate) fn end(&mut self, phase: &'static str) {
+        if !self.enabled {
+            return;
+        }
+
+        let now = clock::now();
+        clock::measure(&format!("cardano-utxo-wasm:{phase}"), self.last, now);
+        self.phases.push((phase, now - self.last));
+        self.last = now;
+    }
+
+    /// Sets the timings of the phases and their total to the result, if enabled.
+    pub(crate) fn report(&self, result: &JsValue) {
+        if !self.enabled {
+            return;
+        }
+
+        let timings = Object::new();
+        for (phase, ms) in self.phases.iter() {
+            Reflect::set(&timings, &(*phase).into(), &(*ms).into()).expect("Unreachable");
+        }
+        Reflect::set(&timings, &"total".into(), &(self.last - self.start).into())
+            .expect("Unreachable");
+        Reflect::set(result, &"timings".into(), &timings).expect("Unreachable");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::profile::Profiler;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_profiler() {
+        let mut profiler = Profiler::new(false, 0.0);
+        profiler.end("parse");
+        assert!(profiler.phases.is_empty());
+
+        let mut profiler = Profiler::new(true, crate::clock::now());
+        profiler.end("parse");
+        profiler.end("select");
+        let phases: Vec<&str> = profiler.phases.iter().map(|(phase, _)| *phase).collect();
+        assert_eq!(phases, vec!["parse", "select"]);
+    }
+}
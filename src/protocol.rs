@@ -0,0 +1,127 @@
+/*!
+Protocol parameters
+
+The parameters of the ledger that fee and min-ADA computations depend on.
+The presets of the networks are those since the Babbage era, the same on all of them.
+*/
+use crate::{
+    collateral::{COLLATERAL_PERCENTAGE, MAX_COLLATERAL_INPUTS},
+    fee::LinearFee,
+    get_amount, get_count, ErrorCode,
+};
+use js_sys::{Object, Reflect};
+use wasm_bindgen::{prelude::*, JsCast};
+
+/// The maximum bytes of the value of an output on mainnet.
+const MAX_VALUE_SIZE: u64 = 5000;
+
+/// The maximum bytes of a transaction on mainnet.
+const MAX_TX_SIZE: u64 = 16384;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type ProtocolParameters = FeeParams & {
+  coinsPerUtxoByte: bigint
+  maxValueSize?: number
+  maxTxSize?: number
+  collateralPercentage?: number
+  maxCollateralInputs?: number
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "ProtocolParameters")]
+    pub type JsProtocolParameters;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ProtocolParameters {
+    pub(crate) fee: LinearFee,
+    pub(crate) coins_per_utxo_byte: u64,
+    pub(crate) max_value_size: u64,
+    pub(crate) max_tx_size: u64,
+}
+
+/// The parameters of mainnet, preprod and preview.
+pub(crate) const PRESET: ProtocolParameters = ProtocolParameters {
+    fee: LinearFee {
+        coefficient: 44,
+        constant: 155381,
+    },
+    coins_per_utxo_byte: 4310,
+    max_value_size: MAX_VALUE_SIZE,
+    max_tx_size: MAX_TX_SIZE,
+};
+
+impl ProtocolParameters {
+    /// The object of the parameters, with the collateral parameters of the protocol.
+    fn to_js(self) -> JsProtocolParameters {
+        let result = Object::new();
+        let set = |key: &str, value: JsValue| {
+            Reflect::set(&result, &key.into(), &value).expect("Unreachable");
+        };
+        set("minFeeA", self.fee.coefficient.into());
+        set("minFeeB", self.fee.constant.into());
+        set("coinsPerUtxoByte", self.coins_per_utxo_byte.into());
+        set("maxValueSize", (self.max_value_size as u32).into());
+        set("maxTxSize", (self.max_tx_size as u32).into());
+        set(
+            "collateralPercentage",
+            (COLLATERAL_PERCENTAGE as u32).into(),
+        );
+        set("maxCollateralInputs", (MAX_COLLATERAL_INPUTS as u32).into());
+
+        result.unchecked_into()
+    }
+}
+
+/// The protocol parameters of mainnet.
+#[wasm_bindgen(js_name = mainnetParams)]
+pub fn mainnet_params() -> JsProtocolParameters {
+    PRESET.to_js()
+}
+
+/// The protocol parameters of the preprod testnet.
+#[wasm_bindgen(js_name = preprodParams)]
+pub fn preprod_params() -> JsProtocolParameters {
+    PRESET.to_js()
+}
+
+/// The protocol parameters of the preview testnet.
+#[wasm_bindgen(js_name = previewParams)]
+pub fn preview_params() -> JsProtocolParameters {
+    PRESET.to_js()
+}
+
+impl TryFrom<&JsProtocolParameters> for ProtocolParameters {
+    type Error = ErrorCode;
+
+    fn try_from(value: &JsProtocolParameters) -> Result<Self, Self::Error> {
+        Ok(Self {
+            fee: LinearFee::from_js(value)?,
+            coins_per_utxo_byte: get_amount(value, "coinsPerUtxoByte")?
+                .ok_or(ErrorCode::InvalidType)?,
+            max_value_size: get_count(value, "maxValueSize")?.map_or(MAX_VALUE_SIZE, u64::from),
+            max_tx_size: get_count(value, "maxTxSize")?.map_or(MAX_TX_SIZE, u64::from),
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::protocol::{mainnet_params, ProtocolParameters, PRESET};
+    use wasm_bindgen_test::*;
+
+    /// The parameters of mainnet in the Babbage era.
+    pub(crate) fn mainnet() -> ProtocolParameters {
+        PRESET
+    }
+
+    #[wasm_bindgen_test]
+    fn test_presets() {
+        let params = mainnet_params();
+
+        assert_eq!(ProtocolParameters::try_from(&params), Ok(mainnet()));
+    }
+}
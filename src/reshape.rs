@@ -0,0 +1,389 @@
+/*!
+Planning the reshape of a wallet
+
+Wallets serving many transactions want their UTxOs in a layout, like ten pieces of 100 ADA
+plus one vault of the tokens. Getting there from the current UTxOs takes one or more
+self-transactions, since a transaction can only be as large as the maximum transaction size.
+*/
+use crate::{
+    collect_js_outputs,
+    fee::TxShape,
+    get_amount, get_count, iterate,
+    min_ada::min_ada,
+    protocol::{JsProtocolParameters, ProtocolParameters},
+    raise,
+    split::{piece, Piece},
+    to_outputs, ErrorCode, ErrorKind, JsOutput, JsOutputArray, Output,
+};
+use js_sys::{Array, Object, Reflect};
+use std::collections::{BTreeMap, BTreeSet};
+use utxo::{try_sum, ExtOutput, Select};
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type TargetLayout = Array<{
+  lovelace: bigint
+  count: number
+}>
+
+export type ReshapeTx = {
+  inputs: Array<Output>
+  outputs: Array<Output>
+  fee: bigint
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "TargetLayout")]
+    pub type JsTargetLayout;
+
+    #[wasm_bindgen(typescript_type = "Array<ReshapeTx>")]
+    pub type ReshapeTxArray;
+}
+
+/// Where an input of a reshaping transaction comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Source {
+    /// The UTxO of the index.
+    Utxo(usize),
+    /// The output of an earlier transaction of the plan.
+    Produced { tx: usize, output: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ReshapeTx {
+    pub(crate) inputs: Vec<Source>,
+    pub(crate) outputs: Vec<Piece>,
+    pub(crate) fee: u64,
+}
+
+fn shape(inputs: &[&(Source, Piece)], outputs: u64) -> TxShape {
+    let assets: BTreeSet<&(String, String)> =
+        inputs.iter().flat_map(|(_, p)| p.assets.keys()).collect();
+
+    TxShape {
+        inputs: inputs.len() as u64,
+        outputs,
+        assets: assets.len() as u64,
+        witnesses: inputs.len() as u64,
+    }
+}
+
+/// Push the transaction to the plan, returning its vault to spend next.
+fn produce(tx: ReshapeTx, txs: &mut Vec<ReshapeTx>) -> (Source, Piece) {
+    let vault = tx.outputs.last().expect("Unreachable").clone();
+    let source = Source::Produced {
+        tx: txs.len(),
+        output: tx.outputs.len() - 1,
+    };
+    txs.push(tx);
+    (source, vault)
+}
+
+/**
+Make a transaction spending the inputs into the pieces and a vault of what remains.
+
+Returns `None` if the vault would hold less than its minimum lovelace.
+*/
+fn transact(
+    inputs: &[&(Source, Piece)],
+    pieces: &[u64],
+    params: &ProtocolParameters,
+) -> Option<ReshapeTx> {
+    let total: Vec<Piece> = inputs.iter().map(|(_, p)| p.clone()).collect();
+    let total = try_sum(&total)?;
+    let fee = params
+        .fee
+        .fee(shape(inputs, pieces.len() as u64 + 1).size())?;
+    let spent = pieces.iter().try_fold(fee, |acc, p| acc.checked_add(*p))?;
+    let vault = total.checked_sub(&piece(spent, BTreeMap::new()))?;
+
+    if vault.value < min_ada(&vault, params.coins_per_utxo_byte) {
+        return None;
+    }
+
+    let mut outputs: Vec<Piece> = pieces
+        .iter()
+        .map(|lovelace| piece(*lovelace, BTreeMap::new()))
+        .collect();
+    outputs.push(vault);
+
+    Some(ReshapeTx {
+        inputs: inputs.iter().map(|(s, _)| *s).collect(),
+        outputs,
+        fee,
+    })
+}
+
+/// Take the first pieces of the counts of lovelace, as many as given at most.
+fn take(missing: &mut Vec<(u64, u64)>, pieces: u64) -> Vec<u64> {
+    let mut taken = Vec::new();
+
+    for (lovelace, count) in missing.iter_mut() {
+        let some = (*count).min(pieces - taken.len() as u64);
+        taken.extend((0..some).map(|_| *lovelace));
+        *count -= some;
+    }
+    missing.retain(|(_, count)| *count > 0);
+
+    taken
+}
+
+/**
+Plan the transactions to reshape the UTxOs into the layout of pure ADA pieces and a vault.
+
+The UTxOs already of the layout are kept. The others are consolidated in batches
+until they can be spent by one transaction, then distributed into the missing pieces.
+Each transaction puts what remains in its last output, the vault, which the next transaction spends.
+Returns `None` if a piece is less than the minimum lovelace of a pure ADA output,
+or the UTxOs are not enough for the layout.
+*/
+pub(crate) fn plan(
+    utxos: &[Piece],
+    layout: &[(u64, u64)],
+    params: &ProtocolParameters,
+) -> Option<Vec<ReshapeTx>> {
+    let min = min_ada(&piece(0, BTreeMap::new()), params.coins_per_utxo_byte);
+
+    if layout
+        .iter()
+        .any(|(lovelace, count)| *count > 0 && *lovelace < min.max(1))
+    {
+        return None;
+    }
+
+    let mut missing: Vec<(u64, u64)> = layout
+        .iter()
+        .copied()
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    let mut pool: Vec<(Source, Piece)> = Vec::new();
+
+    for (index, utxo) in utxos.iter().enumerate() {
+        match missing
+            .iter_mut()
+            .find(|(lovelace, _)| utxo.assets.is_empty() && *lovelace == utxo.value)
+        {
+            Some((_, count)) => {
+                *count -= 1;
+                missing.retain(|(_, count)| *count > 0);
+            }
+            None => pool.push((Source::Utxo(index), utxo.clone())),
+        }
+    }
+
+    // The lovelace of the pieces missing is more than all the UTxOs hold.
+    let needed = missing.iter().try_fold(0u64, |total, (lovelace, count)| {
+        total.checked_add(lovelace.checked_mul(*count)?)
+    });
+    let held = pool
+        .iter()
+        .fold(0u64, |total, (_, utxo)| total.saturating_add(utxo.value));
+    if needed.is_none_or(|needed| needed > held) {
+        return None;
+    }
+    let remaining = |missing: &[(u64, u64)]| {
+        missing
+            .iter()
+            .fold(0u64, |total, (_, count)| total.saturating_add(*count))
+    };
+
+    let fits = |inputs: &[&(Source, Piece)], outputs: u64| {
+        shape(inputs, outputs).size() <= params.max_tx_size
+    };
+    let mut txs: Vec<ReshapeTx> = Vec::new();
+
+    if pool.is_empty() || (missing.is_empty() && pool.len() == 1) {
+        return missing.is_empty().then_some(txs);
+    }
+
+    while !fits(&pool.iter().collect::<Vec<_>>(), 2) {
+        let mut batch: Vec<&(Source, Piece)> = Vec::new();
+
+        for input in pool.iter() {
+            batch.push(input);
+
+            if !fits(&batch, 1) {
+                batch.pop();
+                break;
+            }
+        }
+
+        if batch.len() < 2 {
+            return None;
+        }
+
+        let tx = transact(&batch, &[], params)?;
+        pool = pool.split_off(batch.len());
+        pool.push(produce(tx, &mut txs));
+    }
+
+    loop {
+        let inputs: Vec<&(Source, Piece)> = pool.iter().collect();
+        let mut count = 0;
+
+        while count < remaining(&missing) && fits(&inputs, count + 2) {
+            count += 1;
+        }
+
+        if count == 0 && !missing.is_empty() {
+            return None;
+        }
+
+        let tx = transact(&inputs, &take(&mut missing, count), params)?;
+        pool = vec![produce(tx, &mut txs)];
+
+        if missing.is_empty() {
+            return Some(txs);
+        }
+    }
+}
+
+/**
+Plan the self-transactions to reshape the UTxOs into a layout
+
+The layout is the counts of pure ADA pieces of the lovelace,
+plus one vault of all the assets and the lovelace remaining.
+The UTxOs already of the layout are kept, the others are spent
+by transactions within the maximum transaction size.
+The vault of each transaction is spent by the next one, where its object
+is the same in the `outputs` of the earlier and the `inputs` of the later transaction.
+The outputs have no addresses, which should be those of the wallet.
+
+Raises errors when the types used are wrong, a piece of the layout is less than
+the minimum lovelace of a pure ADA output, or the UTxOs are not enough for the layout.
+*/
+#[wasm_bindgen(js_name = planReshape)]
+pub fn plan_reshape(
+    utxos: &JsOutputArray,
+    target_layout: &JsTargetLayout,
+    params: &JsProtocolParameters,
+) -> Result<ReshapeTxArray, JsError> {
+    let js_utxos = collect_js_outputs(utxos)?;
+    let utxos: Vec<Piece> = to_outputs(&js_utxos)?
+        .into_iter()
+        .map(|o| piece(o.value, o.assets))
+        .collect();
+    let layout: Vec<(u64, u64)> = iterate(target_layout)?
+        .iter()
+        .map(|pieces| {
+            let lovelace = get_amount(pieces, "lovelace")?.ok_or(ErrorCode::InvalidType)?;
+            let count = get_count(pieces, "count")?.ok_or(ErrorCode::InvalidType)?;
+            Ok((lovelace, count.into()))
+        })
+        .collect::<Result<_, ErrorCode>>()?;
+    let params: ProtocolParameters = params.try_into()?;
+    let min = min_ada(&piece(0, BTreeMap::new()), params.coins_per_utxo_byte);
+    if layout.iter().any(|(lovelace, _)| *lovelace < min.max(1)) {
+        return Err(raise(
+            ErrorKind::BadAmount,
+            "Pieces should hold the minimum lovelace of a pure ADA output",
+        ));
+    }
+    let txs = plan(&utxos, &layout, &params).ok_or_else(|| {
+        raise(
+            ErrorKind::InsufficientInput,
+            "UTxOs are not enough for the layout",
+        )
+    })?;
+
+    let mut produced: Vec<Vec<JsOutput>> = Vec::new();
+    let result = Array::new();
+
+    for ReshapeTx {
+        inputs,
+        outputs,
+        fee,
+    } in txs
+    {
+        let inputs: Array = inputs
+            .iter()
+            .map(|source| match source {
+                Source::Utxo(index) => &js_utxos[*index],
+                Source::Produced { tx, output } => &produced[*tx][*output],
+            })
+            .collect();
+        let outputs: Vec<JsOutput> = outputs
+            .into_iter()
+            .map(|p| {
+                let output: Output = ExtOutput {
+                    value: p.value,
+                    assets: p.assets,
+                    data: None,
+                };
+                output.into()
+            })
+            .collect();
+
+        let tx = Object::new();
+        Reflect::set(&tx, &"inputs".into(), &inputs).expect("Unreachable");
+        Reflect::set(&tx, &"outputs".into(), &outputs.iter().collect::<Array>())
+            .expect("Unreachable");
+        Reflect::set(&tx, &"fee".into(), &fee.into()).expect("Unreachable");
+        result.push(&tx);
+        produced.push(outputs);
+    }
+
+    Ok(result.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        protocol::tests::mainnet,
+        reshape::{plan, Source},
+        split::piece,
+    };
+    use std::collections::BTreeMap;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_plan_reshape() {
+        let params = mainnet();
+        let mut utxos = vec![
+            piece(100_000_000, BTreeMap::new()),
+            piece(500_000_000, BTreeMap::new()),
+            piece(700_000_000, BTreeMap::new()),
+        ];
+        utxos[2].insert_asset(("ab".repeat(28), "cd".repeat(4)), 10);
+
+        let txs = plan(&utxos, &[(100_000_000, 10)], &params).unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].inputs, vec![Source::Utxo(1), Source::Utxo(2)]);
+        assert_eq!(txs[0].outputs.len(), 10);
+        let vault = txs[0].outputs.last().unwrap();
+        assert_eq!(vault.assets, utxos[2].assets);
+        assert_eq!(vault.value + txs[0].fee, 300_000_000);
+
+        assert!(plan(&utxos, &[(100_000_000, 14)], &params).is_none());
+        // Pieces below the minimum of pure ADA, or more than the UTxOs could ever hold.
+        assert!(plan(&utxos, &[(0, 1)], &params).is_none());
+        assert!(plan(&utxos, &[(900_000, 1)], &params).is_none());
+        assert!(plan(&utxos, &[(100_000_000, u32::MAX.into())], &params).is_none());
+        assert_eq!(
+            plan(&utxos[..1], &[(100_000_000, 1)], &params),
+            Some(vec![])
+        );
+
+        // A transaction too small for all, the inputs are consolidated first.
+        let params = crate::protocol::ProtocolParameters {
+            max_tx_size: 800,
+            ..params
+        };
+        let utxos: Vec<_> = (0..10)
+            .map(|i| piece(10_000_000 + i, BTreeMap::new()))
+            .collect();
+        let txs = plan(&utxos, &[(20_000_000, 3)], &params).unwrap();
+        assert!(txs.len() > 1);
+        assert_eq!(txs.iter().map(|tx| tx.outputs.len() - 1).sum::<usize>(), 3);
+        assert_eq!(
+            txs.last().unwrap().inputs,
+            vec![Source::Produced {
+                tx: txs.len() - 2,
+                output: txs[txs.len() - 2].outputs.len() - 1
+            }]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+/*!
+Seeded pseudo-random numbers
+
+SplitMix64 is small, fast and reproducible on every platform,
+which is all the fixtures and stochastic helpers need. It is not
+suitable for cryptography.
+*/
+
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `[0, 1)`.
+    #[cfg_attr(not(feature = "fixtures"), allow(dead_code))]
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a number in `[0, bound)` without modulo bias, `bound` must not be zero.
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+
+        loop {
+            let value = self.next_u64();
+
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Returns a number in `[min, max]`.
+    #[cfg_attr(not(feature = "fixtures"), allow(dead_code))]
+    pub(crate) fn between(&mut self, min: u64, max: u64) -> u64 {
+        match (max - min).checked_add(1) {
+            Some(bound) => min + self.below(bound),
+            None => self.next_u64(),
+        }
+    }
+
+    /// Shuffles the items in place with Fisher-Yates.
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::Rng;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_rng_is_reproducible() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+        let xs: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..8).map(|_| b.next_u64()).collect();
+        let zs: Vec<u64> = (0..8).map(|_| c.next_u64()).collect();
+
+        assert_eq!(xs, ys);
+        assert_ne!(xs, zs);
+
+        for _ in 0..100 {
+            let value = a.between(10, 20);
+            assert!((10..=20).contains(&value));
+            assert!(a.next_f64() < 1.0);
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_rng_shuffle() {
+        let items: Vec<u32> = (0..50).collect();
+        let shuffle = |seed| {
+            let mut items = items.clone();
+            Rng::new(seed).shuffle(&mut items);
+            items
+        };
+
+        assert_eq!(shuffle(1), shuffle(1));
+        assert_ne!(shuffle(1), shuffle(2));
+        assert_ne!(shuffle(1), items);
+
+        let mut sorted = shuffle(1);
+        sorted.sort();
+        assert_eq!(sorted, items);
+    }
+}
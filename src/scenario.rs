@@ -0,0 +1,326 @@
+/*!
+Owned selection scenarios
+
+The selection functions exported to JS borrow their inputs from JS objects.
+The types here own their values instead, so pure-Rust consumers and test
+harnesses can capture a selection, store it (as JSON, CBOR or any other
+format supported by serde when the `serde` feature is enabled) and replay it
+later with the same result. A scenario records the options of `select`
+that do not read JS objects, the strategy and `maxInputs`.
+*/
+use crate::{options::Strategy, selection, Output};
+use std::collections::BTreeMap;
+use utxo::{try_sum, ExtOutput};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An asset of a [`Value`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct AssetValue {
+    pub policy_id: String,
+    pub asset_name: String,
+    pub quantity: u64,
+}
+
+/// The lovelace and assets of an output, without any attached data.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Value {
+    pub lovelace: u64,
+    pub assets: Vec<AssetValue>,
+}
+
+impl Value {
+    fn to_ext_output<D>(&self, data: Option<D>) -> ExtOutput<D, (String, String)> {
+        let mut output = ExtOutput {
+            value: self.lovelace,
+            assets: BTreeMap::new(),
+            data,
+        };
+
+        for asset in self.assets.iter() {
+            output.insert_asset(
+                (asset.policy_id.clone(), asset.asset_name.clone()),
+                asset.quantity,
+            );
+        }
+
+        output
+    }
+}
+
+impl From<u64> for Value {
+    fn from(lovelace: u64) -> Self {
+        Self {
+            lovelace,
+            assets: Vec::new(),
+        }
+    }
+}
+
+impl<D> From<&ExtOutput<D, (String, String)>> for Value {
+    fn from(value: &ExtOutput<D, (String, String)>) -> Self {
+        Self {
+            lovelace: value.value,
+            assets: value
+                .assets
+                .iter()
+                .map(|((policy_id, asset_name), quantity)| AssetValue {
+                    policy_id: policy_id.clone(),
+                    asset_name: asset_name.clone(),
+                    quantity: *quantity,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The result of [`select_indices`], referring to its inputs by index.
+pub type SelectionIndices = ScenarioResult;
+
+/**
+Selects with the same algorithm as `select`, from inputs of any type converting into [`Value`].
+
+The result refers to the inputs by their indices, so embedders outside WASM
+keep their own UTxO types and need not borrow them into outputs.
+Returns `None` if the inputs are not enough for the outputs plus threshold,
+or if the outputs overflowed.
+*/
+pub fn select_indices<T: Into<Value>>(
+    inputs: impl IntoIterator<Item = T>,
+    outputs: impl IntoIterator<Item = T>,
+    threshold: impl Into<Value>,
+) -> Option<SelectionIndices> {
+    Scenario {
+        inputs: inputs.into_iter().map(Into::into).collect(),
+        outputs: outputs.into_iter().map(Into::into).collect(),
+        threshold: threshold.into(),
+        options: ScenarioOptions::default(),
+    }
+    .replay()
+}
+
+/// The `strategy` option of a [`Scenario`], the strategies of `select` not reading JS objects.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum ScenarioStrategy {
+    #[default]
+    Default,
+    RandomImprove {
+        seed: u64,
+    },
+    LargestFirst,
+    BranchAndBound {
+        tolerance: u64,
+    },
+    Knapsack {
+        seed: u64,
+    },
+    FewestInputs,
+    Dust {
+        max_inputs: usize,
+    },
+    /// The strategy registered of the name, see [`crate::strategy`].
+    Registered(String),
+}
+
+impl From<&ScenarioStrategy> for Strategy {
+    fn from(value: &ScenarioStrategy) -> Self {
+        match value {
+            ScenarioStrategy::Default => Self::Default,
+            ScenarioStrategy::RandomImprove { seed } => Self::RandomImprove { seed: *seed },
+            ScenarioStrategy::LargestFirst => Self::LargestFirst,
+            ScenarioStrategy::BranchAndBound { tolerance } => Self::BranchAndBound {
+                tolerance: *tolerance,
+            },
+            ScenarioStrategy::Knapsack { seed } => Self::Knapsack { seed: *seed },
+            ScenarioStrategy::FewestInputs => Self::FewestInputs,
+            ScenarioStrategy::Dust { max_inputs } => Self::Dust {
+                max_inputs: *max_inputs,
+            },
+            ScenarioStrategy::Registered(name) => Self::Registered(name.clone()),
+        }
+    }
+}
+
+/// The options of a [`Scenario`], as the options of `select` of the same names.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", default))]
+pub struct ScenarioOptions {
+    pub strategy: ScenarioStrategy,
+    pub max_inputs: Option<usize>,
+}
+
+/// The arguments of a selection.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Scenario {
+    pub inputs: Vec<Value>,
+    pub outputs: Vec<Value>,
+    pub threshold: Value,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub options: ScenarioOptions,
+}
+
+/// The result of a selection, referring to the inputs of its [`Scenario`] by index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScenarioResult {
+    pub selected: Vec<usize>,
+    pub unselected: Vec<usize>,
+    pub excess: Value,
+}
+
+/// The inputs of a scenario by their indices, its total output and threshold.
+type IndexedScenario = (
+    Vec<ExtOutput<usize, (String, String)>>,
+    ExtOutput<usize, (String, String)>,
+    ExtOutput<usize, (String, String)>,
+);
+
+impl Scenario {
+    /// Converts to the inputs by their indices, the total output and threshold.
+    pub(crate) fn to_ext_outputs(&self) -> Option<IndexedScenario> {
+        let inputs = self
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| input.to_ext_output(Some(index)))
+            .collect();
+        let outputs: Vec<ExtOutput<usize, (String, String)>> = self
+            .outputs
+            .iter()
+            .map(|output| output.to_ext_output(None))
+            .collect();
+
+        Some((
+            inputs,
+            try_sum(&outputs)?,
+            self.threshold.to_ext_output(None),
+        ))
+    }
+
+    /// Captures the arguments of a selection made on JS outputs.
+    pub fn capture(inputs: &[Output], outputs: &[Output], threshold: &Output) -> Self {
+        Self {
+            inputs: inputs.iter().map(Value::from).collect(),
+            outputs: outputs.iter().map(Value::from).collect(),
+            threshold: threshold.into(),
+            options: ScenarioOptions::default(),
+        }
+    }
+
+    /**
+    Replays the selection with the same algorithm as `select` and the options.
+
+    A selection of more than `max_inputs` is made again by the `fewestInputs` strategy.
+    Returns `None` if the inputs are not enough for the outputs plus threshold,
+    within `max_inputs`, or if the outputs overflowed.
+    */
+    pub fn replay(&self) -> Option<ScenarioResult> {
+        let (inputs, total_output, threshold) = self.to_ext_outputs()?;
+        let replay = |strategy: &Strategy| {
+            let mut inputs = inputs.clone();
+
+            selection::select_core(&mut inputs[..], &total_output, &threshold, strategy).map(
+                |(selected, unselected, excess)| ScenarioResult {
+                    selected: selected.iter().filter_map(|i| i.data).collect(),
+                    unselected: unselected.iter().filter_map(|i| i.data).collect(),
+                    excess: (&excess).into(),
+                },
+            )
+        };
+        let result = replay(&Strategy::from(&self.options.strategy))?;
+
+        match self.options.max_inputs {
+            Some(max) if result.selected.len() > max => {
+                replay(&Strategy::FewestInputs).filter(|result| result.selected.len() <= max)
+            }
+            _ => Some(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scenario::{
+        select_indices, AssetValue, Scenario, ScenarioOptions, ScenarioStrategy, Value,
+    };
+    use wasm_bindgen_test::*;
+
+    fn scenario() -> Scenario {
+        let lovelace = |lovelace| Value {
+            lovelace,
+            assets: Vec::new(),
+        };
+
+        Scenario {
+            inputs: vec![
+                lovelace(3000),
+                Value {
+                    lovelace: 2000,
+                    assets: vec![AssetValue {
+                        policy_id: "policy1".into(),
+                        asset_name: "aname1".into(),
+                        quantity: 10,
+                    }],
+                },
+                lovelace(7000),
+            ],
+            outputs: vec![lovelace(6000), lovelace(1000)],
+            threshold: lovelace(500),
+            options: ScenarioOptions::default(),
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_scenario_replay() {
+        let result = scenario().replay().unwrap();
+
+        assert_eq!(result.selected, vec![2, 0]);
+        assert_eq!(result.unselected, vec![1]);
+        assert_eq!(result.excess.lovelace, 3000);
+        assert!(result.excess.assets.is_empty());
+
+        let mut scenario = scenario();
+        scenario.options.max_inputs = Some(1);
+        assert_eq!(scenario.replay(), None);
+        scenario.options.strategy = ScenarioStrategy::LargestFirst;
+        scenario.options.max_inputs = None;
+        assert_eq!(scenario.replay().unwrap().selected, vec![2, 0]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_select_indices() {
+        let result = select_indices([3000u64, 2000, 7000], [6000, 1000], 500u64).unwrap();
+
+        assert_eq!(result.selected, vec![2, 0]);
+        assert_eq!(result.unselected, vec![1]);
+        assert_eq!(result.excess, Value::from(3000));
+        assert_eq!(select_indices([3000u64], [6000], 0u64), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_scenario_json_round_trip() {
+        let mut scenario = scenario();
+        scenario.options = ScenarioOptions {
+            strategy: ScenarioStrategy::RandomImprove { seed: 7 },
+            max_inputs: Some(2),
+        };
+        let json = serde_json::to_string(&scenario).unwrap();
+
+        assert!(json.contains(r#""policyId":"policy1""#));
+        assert!(json.contains(r#""maxInputs":2"#));
+
+        let replayed: Scenario = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(replayed, scenario);
+        assert_eq!(replayed.replay(), scenario.replay());
+    }
+}
@@ -0,0 +1,1348 @@
+/*!
+Selection with constraints
+
+Builds on `utxo::select` for the constraints of `SelectOptions`.
+*/
+use crate::{
+    clock,
+    options::{
+        DustTokens, KnownUtxos, MinChange, Objective, SelectOptions, Strategy, TokenDustFloor,
+    },
+    out_point::OutPoint,
+    strategy::{
+        self, branch_and_bound, dust, fewest_inputs, knapsack, largest_first::LargestFirst,
+        random_improve::RandomImprove, InOrder,
+    },
+    total::Total,
+    Output,
+};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, VecDeque},
+};
+use utxo::{ExtOutput, Select};
+
+pub(crate) struct Selection<'o> {
+    pub(crate) selected: Vec<Output<'o>>,
+    pub(crate) unselected: Vec<Output<'o>>,
+    pub(crate) excess: Output<'o>,
+}
+
+/// What the excess must cover beyond the outputs.
+pub(crate) enum Threshold<'o> {
+    /// At least the output.
+    Output(Output<'o>),
+    /// The fee reserve, plus either no change or change of at least `min_change` lovelace.
+    Change { fee_reserve: u64, min_change: u64 },
+}
+
+/// An output of the lovelace only.
+fn lovelace<'o>(value: u64) -> Output<'o> {
+    Output {
+        value,
+        ..Output::zero()
+    }
+}
+
+/// The selections tried at most for the change to reach its minimum.
+const MIN_CHANGE_ATTEMPTS: usize = 3;
+
+/// A selection for a threshold of either form.
+pub(crate) struct Thresholded<'o> {
+    pub(crate) selection: Selection<'o>,
+    /// The threshold output the excess covers.
+    pub(crate) threshold: Output<'o>,
+    /// The threshold the change is beyond, without the minimum of the change.
+    pub(crate) base: Output<'o>,
+    /// The lovelace of the change below its minimum, left to the fee.
+    pub(crate) donated: u64,
+}
+
+/**
+Selects for the output plus the threshold of either form.
+
+Change beyond the threshold must be none or at least its minimum,
+from the threshold itself or `options.min_change`:
+another selection is made for the threshold plus the minimum otherwise,
+retried since the minimum lovelace of the change depends on its assets.
+Change of lovelace only up to `options.absorb_change` is donated to the fee instead.
+*/
+pub(crate) fn select_with_threshold<'o>(
+    inputs: Vec<Output<'o>>,
+    output: &Output<'o>,
+    threshold: &Threshold<'o>,
+    options: &SelectOptions,
+) -> Option<Thresholded<'o>> {
+    let (base, min_change) = match (threshold, options.min_change) {
+        (Threshold::Output(threshold), None) => {
+            return select(inputs, output, threshold, options).map(|selection| Thresholded {
+                selection,
+                threshold: threshold.clone(),
+                base: threshold.clone(),
+                donated: 0,
+            })
+        }
+        (Threshold::Output(threshold), Some(min_change)) => (threshold.clone(), min_change),
+        (
+            Threshold::Change {
+                fee_reserve,
+                min_change,
+            },
+            _,
+        ) => (lovelace(*fee_reserve), MinChange::Lovelace(*min_change)),
+    };
+    let mut threshold = base.clone();
+
+    for _ in 0..MIN_CHANGE_ATTEMPTS {
+        let selection = select(inputs.clone(), output, &threshold, options)?;
+        let change = selection.excess.saturating_sub(&base);
+        let min = min_change.of(&change);
+
+        let lovelace_only = change.assets.values().all(|q| *q == 0);
+
+        if (change.value == 0 && lovelace_only) || change.value >= min {
+            return Some(Thresholded {
+                selection,
+                threshold,
+                base,
+                donated: 0,
+            });
+        }
+
+        if lovelace_only && change.value <= options.absorb_change {
+            return Some(Thresholded {
+                selection,
+                threshold,
+                base,
+                donated: change.value,
+            });
+        }
+
+        threshold = base.checked_add(&lovelace(min))?;
+    }
+
+    None
+}
+
+/**
+Selects for the output plus threshold with the strategy of the options.
+
+With `options.token_dust_floor`, the unselected inputs holding an asset left in the excess
+below its floor are selected as well, see [`include_token_dust`].
+*/
+pub(crate) fn select<'o>(
+    inputs: Vec<Output<'o>>,
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+    options: &SelectOptions,
+) -> Option<Selection<'o>> {
+    if !options.mandatory.is_empty() {
+        return select_mandatory(inputs, output, threshold, options);
+    }
+
+    let selection = select_capped(inputs, output, threshold, options)?;
+
+    match &options.token_dust_floor {
+        Some(floor) => Some(include_token_dust(selection, floor, options.max_inputs)),
+        None => Some(selection),
+    }
+}
+
+/**
+Selects the mandatory inputs of `options.mandatory`, then only as many other inputs
+as needed by the options, counting the value of the mandatory toward the output plus threshold.
+*/
+fn select_mandatory<'o>(
+    inputs: Vec<Output<'o>>,
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+    options: &SelectOptions,
+) -> Option<Selection<'o>> {
+    let mandatory = KnownUtxos::new(options.mandatory.clone());
+    let (mut pinned, pool): (Vec<Output>, Vec<Output>) = inputs
+        .into_iter()
+        .partition(|input| input.data.is_some_and(|data| mandatory.contains(data)));
+    let goal = Total::of(&pinned).shortfall(&output.checked_add(threshold)?);
+
+    let unselected = if goal.value == 0 && goal.assets.values().all(|q| *q == 0) {
+        pool
+    } else {
+        let others = SelectOptions {
+            mandatory: Vec::new(),
+            max_inputs: match options.max_inputs {
+                Some(max) => Some(max.checked_sub(pinned.len())?),
+                None => None,
+            },
+            ..options.clone()
+        };
+        let mut selection = select(pool, &goal, &Output::zero(), &others)?;
+        pinned.append(&mut selection.selected);
+        selection.unselected
+    };
+
+    let excess = Total::of(&pinned).excess(output).ok()?;
+
+    Some(Selection {
+        selected: pinned,
+        unselected,
+        excess,
+    })
+}
+
+/**
+Selects all the unselected inputs holding each asset of the excess in a quantity
+below its floor, so the change has either none of the asset or all the inputs hold.
+
+An asset is left as it is if its inputs would be more than `max_inputs` in total.
+*/
+pub(crate) fn include_token_dust<'o>(
+    mut selection: Selection<'o>,
+    floor: &TokenDustFloor,
+    max_inputs: Option<usize>,
+) -> Selection<'o> {
+    let dust: Vec<(String, String)> = selection
+        .excess
+        .assets
+        .iter()
+        .filter(|(key, quantity)| **quantity > 0 && **quantity < floor.of(key))
+        .map(|(key, _)| key.clone())
+        .collect();
+
+    for key in dust {
+        let (mut holders, rest): (Vec<Output>, Vec<Output>) = selection
+            .unselected
+            .into_iter()
+            .partition(|input| input.assets.get(&key).is_some_and(|q| *q > 0));
+        let mut total = Total::of(&holders);
+        total.add(&selection.excess);
+        let excess = total.to_output().ok();
+        let within = max_inputs.is_none_or(|max| selection.selected.len() + holders.len() <= max);
+
+        match excess {
+            Some(excess) if within => {
+                selection.excess = excess;
+                selection.selected.append(&mut holders);
+                selection.unselected = rest;
+            }
+            _ => {
+                selection.unselected = rest;
+                selection.unselected.append(&mut holders);
+            }
+        }
+    }
+
+    selection
+}
+
+/**
+Selects for the output plus threshold with the strategy of the options, of `options.max_inputs`.
+
+A selection of more than `options.max_inputs` is made again by the `fewestInputs` strategy,
+and `None` is returned if that is still more.
+*/
+fn select_capped<'o>(
+    inputs: Vec<Output<'o>>,
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+    options: &SelectOptions,
+) -> Option<Selection<'o>> {
+    let Some(max_inputs) = options.max_inputs else {
+        return select_uncapped(inputs, output, threshold, options);
+    };
+    let selection = select_uncapped(inputs.clone(), output, threshold, options)?;
+
+    if selection.selected.len() <= max_inputs {
+        return Some(selection);
+    }
+
+    let options = SelectOptions {
+        strategy: Strategy::FewestInputs,
+        ..options.clone()
+    };
+
+    select_uncapped(inputs, output, threshold, &options)
+        .filter(|selection| selection.selected.len() <= max_inputs)
+}
+
+/// Whether the inputs are enough for the output plus threshold only beyond `options.max_inputs`.
+pub(crate) fn exceeds_max_inputs<'o>(
+    inputs: Vec<Output<'o>>,
+    output: &Output<'o>,
+    threshold: &Threshold<'o>,
+    options: &SelectOptions,
+) -> bool {
+    let uncapped = SelectOptions {
+        max_inputs: None,
+        ..options.clone()
+    };
+
+    options.max_inputs.is_some()
+        && select_with_threshold(inputs, output, threshold, &uncapped).is_some()
+}
+
+/**
+What the inputs lack for the output plus threshold, of the lovelace and each asset.
+
+Only the inputs the options may select count: none `exclude` or `excludeUnspendable`
+takes out, and those of `requireAsset` unless it may fall back to the others.
+The threshold of change counts its fee reserve only. All the lovelace and assets are zero
+when the sum of those inputs covers them, e.g. when only `maxInputs` fails the selection.
+Returns `None` if the output plus threshold overflowed.
+*/
+pub(crate) fn shortfall<'o>(
+    inputs: &[Output<'o>],
+    output: &Output<'o>,
+    threshold: &Threshold<'o>,
+    options: &SelectOptions,
+) -> Option<Output<'o>> {
+    let goal = match threshold {
+        Threshold::Output(threshold) => output.checked_add(threshold)?,
+        Threshold::Change { fee_reserve, .. } => output.checked_add(&lovelace(*fee_reserve))?,
+    };
+    let (mut inputs, _) = exclude(inputs.to_vec(), options);
+
+    if let Some(required) = options.require_asset.as_ref().filter(|r| !r.fallback) {
+        inputs.retain(|input| input.assets.contains_key(&required.asset));
+    }
+
+    Some(Total::of(&inputs).shortfall(&goal))
+}
+
+/// Selects for the output plus threshold with the strategy of the options, of any inputs.
+fn select_uncapped<'o>(
+    inputs: Vec<Output<'o>>,
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+    options: &SelectOptions,
+) -> Option<Selection<'o>> {
+    if options.avoid_mixing_addresses {
+        return select_apart(inputs, output, threshold, options, |input| {
+            input.data.and_then(|data| data.address())
+        });
+    }
+
+    select_joint(inputs, output, threshold, options)
+}
+
+/**
+Selects from the inputs of as few addresses as possible, grouped by the address of each.
+
+The inputs of each address are tried alone, from the address of most lovelace,
+then those of the addresses of most lovelace together, one more address each time.
+Inputs of no address are grouped as if of the same address.
+*/
+fn select_apart<'o>(
+    inputs: Vec<Output<'o>>,
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+    options: &SelectOptions,
+    address: impl Fn(&Output<'o>) -> Option<String>,
+) -> Option<Selection<'o>> {
+    let mut by_address: BTreeMap<Option<String>, Vec<Output>> = BTreeMap::new();
+    for input in inputs {
+        by_address.entry(address(&input)).or_default().push(input);
+    }
+    let mut groups: Vec<Vec<Output>> = by_address.into_values().collect();
+    groups.sort_by_key(|group| {
+        Reverse(
+            group
+                .iter()
+                .fold(0u64, |total, input| total.saturating_add(input.value)),
+        )
+    });
+
+    if groups.len() <= 1 {
+        return select_joint(groups.concat(), output, threshold, options);
+    }
+
+    let select_from = |pool: Vec<Output<'o>>, mut rest: Vec<Output<'o>>| {
+        let mut selection = select_joint(pool, output, threshold, options)?;
+        selection.unselected.append(&mut rest);
+        Some(selection)
+    };
+
+    for index in 0..groups.len() {
+        let mut others = groups.clone();
+        let pool = others.remove(index);
+
+        if let Some(selection) = select_from(pool, others.concat()) {
+            return Some(selection);
+        }
+    }
+
+    (2..=groups.len()).find_map(|count| {
+        let (pool, rest) = groups.split_at(count);
+        select_from(pool.concat(), rest.concat())
+    })
+}
+
+/// Selects from all the inputs together with the strategy of the options.
+fn select_joint<'o>(
+    inputs: Vec<Output<'o>>,
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+    options: &SelectOptions,
+) -> Option<Selection<'o>> {
+    match &options.strategy {
+        Strategy::Auto { budget_ms } => {
+            let deadline = clock::now() + *budget_ms;
+
+            explore(&inputs, output, threshold, options, deadline)
+                .into_iter()
+                .min_by(|a, b| compare(options, a, b))
+        }
+        _ => select_default(inputs, output, threshold, options),
+    }
+}
+
+/// The outputs in selection, of any data.
+pub(crate) type Outputs<'i, D> = &'i mut [ExtOutput<D, (String, String)>];
+
+/// The selected and unselected inputs, and the excess, as `utxo::select` returns.
+pub(crate) type Selected<'i, D> = (
+    Outputs<'i, D>,
+    Outputs<'i, D>,
+    ExtOutput<D, (String, String)>,
+);
+
+/**
+Selects as `utxo::select`, by a faster path when nothing has assets.
+
+Without assets `utxo::select` selects the largest inputs first,
+so sorting them by lovelace gives the same selection without comparing assets.
+*/
+pub(crate) fn select_fast<'i, D: Clone>(
+    inputs: Outputs<'i, D>,
+    output: &ExtOutput<D, (String, String)>,
+    threshold: &ExtOutput<D, (String, String)>,
+) -> Option<Selected<'i, D>> {
+    let lovelace_only = output.assets.is_empty()
+        && threshold.assets.is_empty()
+        && inputs.iter().all(|input| input.assets.is_empty());
+
+    if !lovelace_only {
+        return utxo::select(inputs, output, threshold);
+    }
+
+    let goal = output.value.checked_add(threshold.value)?;
+    let mut total: u64 = 0;
+    let mut count = 0;
+
+    inputs.sort_unstable_by_key(|input| Reverse(input.value));
+
+    while total < goal || count == 0 {
+        total = total.checked_add(inputs.get(count)?.value)?;
+        count += 1;
+    }
+
+    let excess = ExtOutput {
+        value: total - output.value,
+        ..ExtOutput::zero()
+    };
+    let (selected, unselected) = inputs.split_at_mut(count);
+
+    Some((selected, unselected, excess))
+}
+
+/// Selects as `select_fast`, or by the strategy built in or registered by Rust embedders.
+pub(crate) fn select_core<'i, D: Clone>(
+    inputs: Outputs<'i, D>,
+    output: &ExtOutput<D, (String, String)>,
+    threshold: &ExtOutput<D, (String, String)>,
+    strategy: &Strategy,
+) -> Option<Selected<'i, D>> {
+    match strategy {
+        Strategy::LargestFirst => strategy::select_by(&LargestFirst, inputs, output, threshold),
+        Strategy::BranchAndBound { tolerance } => {
+            branch_and_bound::select(inputs, output, threshold, *tolerance)
+        }
+        Strategy::Knapsack { seed } => knapsack::select(inputs, output, threshold, *seed),
+        Strategy::FewestInputs => fewest_inputs::select(inputs, output, threshold),
+        Strategy::Dust { max_inputs } => dust::select(inputs, output, threshold, *max_inputs),
+        Strategy::RandomImprove { seed } => {
+            strategy::select_by(&RandomImprove::new(*seed), inputs, output, threshold)
+        }
+        Strategy::Registered(name) => {
+            strategy::select_by(&*strategy::registered(name)?, inputs, output, threshold)
+        }
+        Strategy::Scored | Strategy::OldestFirst => {
+            strategy::select_by(&InOrder, inputs, output, threshold)
+        }
+        _ => select_fast(inputs, output, threshold),
+    }
+}
+
+/// The inputs neither `exclude` nor `excludeUnspendable` takes out, then those they take out.
+fn exclude<'o>(
+    inputs: Vec<Output<'o>>,
+    options: &SelectOptions,
+) -> (Vec<Output<'o>>, Vec<Output<'o>>) {
+    #[cfg(feature = "fee")]
+    let (inputs, mut excluded): (Vec<Output>, Vec<Output>) = match &options.exclude_unspendable {
+        Some(params) => inputs
+            .into_iter()
+            .partition(|input| !crate::health::is_unspendable(input, params)),
+        None => (inputs, Vec::new()),
+    };
+    #[cfg(not(feature = "fee"))]
+    let mut excluded: Vec<Output> = Vec::new();
+
+    let (inputs, mut listed): (Vec<Output>, Vec<Output>) = if options.exclude.is_empty() {
+        (inputs, Vec::new())
+    } else {
+        inputs.into_iter().partition(|input| {
+            input
+                .data
+                .and_then(OutPoint::of)
+                .is_none_or(|out_point| !options.exclude.contains(&out_point))
+        })
+    };
+    excluded.append(&mut listed);
+
+    (inputs, excluded)
+}
+
+/// Selects for the output plus threshold as `utxo::select`, obeying the options.
+fn select_default<'o>(
+    inputs: Vec<Output<'o>>,
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+    options: &SelectOptions,
+) -> Option<Selection<'o>> {
+    let (inputs, mut excluded) = exclude(inputs, options);
+    let mut selection = match &options.require_asset {
+        Some(required) => {
+            let (carrying, mut others): (Vec<Output>, Vec<Output>) = inputs
+                .into_iter()
+                .partition(|input| input.assets.contains_key(&required.asset));
+
+            match select_spendable(carrying.clone(), output, threshold, options) {
+                Some(selection) => {
+                    excluded.append(&mut others);
+                    selection
+                }
+                None if required.fallback => {
+                    select_pinned(carrying, others, output, threshold, &options.strategy)?
+                }
+                None => return None,
+            }
+        }
+        None => select_spendable(inputs, output, threshold, options)?,
+    };
+
+    selection.unselected.append(&mut excluded);
+    Some(selection)
+}
+
+fn select_spendable<'o>(
+    mut inputs: Vec<Output<'o>>,
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+    options: &SelectOptions,
+) -> Option<Selection<'o>> {
+    if options.previous.is_empty() && options.consolidate.is_none() {
+        let (selected, unselected, excess) =
+            select_core(&mut inputs[..], output, threshold, &options.strategy)?;
+
+        return Some(Selection {
+            selected: selected.to_vec(),
+            unselected: unselected.to_vec(),
+            excess,
+        });
+    }
+
+    let (mut pinned, mut pool): (Vec<Output>, Vec<Output>) =
+        inputs.into_iter().partition(|input| {
+            input
+                .data
+                .is_some_and(|data| options.previous.contains(data))
+        });
+
+    if let Some(consolidate) = &options.consolidate {
+        let (mut merged, rest): (Vec<Output>, Vec<Output>) = pool
+            .into_iter()
+            .partition(|input| consolidate.accepts(input.value));
+        merged.sort_by_key(|input| input.value);
+        let mut left = merged.split_off(merged.len().min(consolidate.max_inputs));
+
+        pinned.append(&mut merged);
+        pool = rest;
+        pool.append(&mut left);
+    }
+
+    select_pinned(pinned, pool, output, threshold, &options.strategy)
+}
+
+/**
+Selects all the pinned inputs, then only as many inputs of the pool as needed.
+
+The pinned inputs count toward the output plus threshold.
+*/
+pub(crate) fn select_pinned<'o>(
+    mut pinned: Vec<Output<'o>>,
+    mut pool: Vec<Output<'o>>,
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+    strategy: &Strategy,
+) -> Option<Selection<'o>> {
+    let goal = Total::of(&pinned).shortfall(&output.checked_add(threshold)?);
+
+    let count = if goal.value == 0 && goal.assets.is_empty() {
+        0
+    } else {
+        let (selected, _, _) = select_core(&mut pool[..], &goal, &Output::zero(), strategy)?;
+        selected.len()
+    };
+
+    let unselected = pool.split_off(count);
+    pinned.append(&mut pool);
+
+    let excess = Total::of(&pinned).excess(output).ok()?;
+
+    Some(Selection {
+        selected: pinned,
+        unselected,
+        excess,
+    })
+}
+
+/// The selections tried at most for alternatives and the `auto` strategy.
+const ALTERNATIVE_ATTEMPTS: usize = 64;
+
+impl Objective {
+    /// The lower the better.
+    fn rank(&self, selection: &Selection) -> (u64, u64, u64) {
+        let inputs = selection.selected.len() as u64;
+        let assets = selection.excess.assets.values().filter(|q| **q > 0).count() as u64;
+        let change = selection.excess.value;
+
+        match self {
+            Objective::FewestInputs => (inputs, assets, change),
+            Objective::SmallestChange => (change, assets, inputs),
+            Objective::FewestChangeAssets => (assets, inputs, change),
+        }
+    }
+}
+
+/// The sum of the weights of the assets of each selected input.
+fn weight(options: &SelectOptions, selection: &Selection) -> f64 {
+    selection
+        .selected
+        .iter()
+        .flat_map(|input| input.assets.keys())
+        .filter_map(|key| options.asset_weights.get(key))
+        .sum()
+}
+
+/// Orders the selections from the best, by the asset weights then the objective.
+fn compare(options: &SelectOptions, a: &Selection, b: &Selection) -> Ordering {
+    weight(options, b)
+        .total_cmp(&weight(options, a))
+        .then_with(|| options.objective.rank(a).cmp(&options.objective.rank(b)))
+}
+
+/// Whether the inputs are the same, by the objects they are converted from.
+fn is_same_input(a: &Output, b: &Output) -> bool {
+    let data = match (a.data, b.data) {
+        (Some(a), Some(b)) => std::ptr::eq(a, b),
+        (a, b) => a.is_none() && b.is_none(),
+    };
+
+    data && a.value == b.value && a.assets == b.assets
+}
+
+/// Whether the selections consist of the same inputs in any order.
+fn is_same(a: &Selection, b: &Selection) -> bool {
+    let count = |inputs: &[Output], input: &Output| {
+        inputs.iter().filter(|i| is_same_input(i, input)).count()
+    };
+
+    a.selected.len() == b.selected.len()
+        && a.selected
+            .iter()
+            .all(|input| count(&a.selected, input) == count(&b.selected, input))
+}
+
+/// Up to `options.alternatives` distinct selections, ranked by `options.objective`.
+pub(crate) fn alternatives<'o>(
+    inputs: &[Output<'o>],
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+    options: &SelectOptions,
+) -> Vec<Selection<'o>> {
+    let mut found = explore(inputs, output, threshold, options, f64::INFINITY);
+
+    found.sort_by(|a, b| compare(options, a, b));
+    found.truncate(options.alternatives);
+    found
+}
+
+/**
+Distinct selections, the first of which is the default one.
+
+Starting from the default selection of all the inputs, each selection found is varied
+by excluding one of its inputs in turn, so the next ones are made without it.
+Mandatory inputs are never excluded, and every selection is made by [`select`]
+within the options, e.g. of `options.max_inputs`, by the default strategy for `auto`.
+No more are tried once the deadline in milliseconds of `clock::now` has passed.
+*/
+fn explore<'o>(
+    inputs: &[Output<'o>],
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+    options: &SelectOptions,
+    deadline: f64,
+) -> Vec<Selection<'o>> {
+    let options = SelectOptions {
+        strategy: match &options.strategy {
+            Strategy::Auto { .. } => Strategy::Default,
+            strategy => strategy.clone(),
+        },
+        ..options.clone()
+    };
+    let mandatory = KnownUtxos::new(options.mandatory.clone());
+    let mut found: Vec<Selection> = Vec::new();
+    let mut exclusions: VecDeque<Vec<usize>> = VecDeque::from([vec![]]);
+    let mut attempts = 0;
+
+    while let Some(excluded) = exclusions.pop_front() {
+        if attempts == ALTERNATIVE_ATTEMPTS || (attempts > 0 && clock::now() > deadline) {
+            break;
+        }
+        attempts += 1;
+
+        let (pool, mut left): (Vec<_>, Vec<_>) = inputs
+            .iter()
+            .enumerate()
+            .partition(|(index, _)| !excluded.contains(index));
+        let pool = pool.into_iter().map(|(_, input)| input.clone()).collect();
+
+        let Some(mut selection) = select(pool, output, threshold, &options) else {
+            continue;
+        };
+
+        if found.iter().any(|other| is_same(other, &selection)) {
+            continue;
+        }
+
+        for input in &selection.selected {
+            if input.data.is_some_and(|data| mandatory.contains(data)) {
+                continue;
+            }
+            if let Some(index) = inputs.iter().position(|i| is_same_input(i, input)) {
+                let mut next = excluded.clone();
+                next.push(index);
+                exclusions.push_back(next);
+            }
+        }
+
+        left.sort_by_key(|(index, _)| *index);
+        selection
+            .unselected
+            .extend(left.into_iter().map(|(_, input)| input.clone()));
+        found.push(selection);
+    }
+
+    found
+}
+
+/// The assets of the excess that are dust, compared to what the inputs hold in total.
+pub(crate) fn dust_tokens(
+    excess: &Output,
+    inputs: &[&[Output]],
+    thresholds: &DustTokens,
+) -> Vec<((String, String), u64)> {
+    let mut holdings: BTreeMap<&(String, String), u64> = BTreeMap::new();
+
+    for input in inputs.iter().flat_map(|inputs| inputs.iter()) {
+        for (key, quantity) in input.assets.iter() {
+            if excess.assets.contains_key(key) {
+                let holding = holdings.entry(key).or_default();
+                *holding = holding.saturating_add(*quantity);
+            }
+        }
+    }
+
+    excess
+        .assets
+        .iter()
+        .filter(|(key, quantity)| {
+            let holding = holdings.get(key).copied().unwrap_or_default();
+            **quantity <= thresholds.quantity
+                || (**quantity as f64) < thresholds.ratio * holding as f64
+        })
+        .map(|(key, quantity)| (key.clone(), *quantity))
+        .collect()
+}
+
+/// Whether the input has anything the goal still wants.
+fn is_wanted(input: &Output, goal: &Output) -> bool {
+    (goal.value > 0 && input.value > 0) || input.assets.keys().any(|k| goal.assets.contains_key(k))
+}
+
+/// The inputs selected and unselected of each pool.
+pub(crate) type PoolSelection<'o> = (Vec<Output<'o>>, Vec<Output<'o>>);
+
+/**
+Selects from the pools in order, each pool only until its cap of lovelace is reached.
+
+A later pool is only selected from for what the earlier pools cannot cover.
+Returns the selection of each pool and the excess of all.
+*/
+pub(crate) fn select_pools<'o>(
+    pools: Vec<(Vec<Output<'o>>, Option<u64>)>,
+    output: &Output<'o>,
+    threshold: &Output<'o>,
+) -> Option<(Vec<PoolSelection<'o>>, Output<'o>)> {
+    let mut goal = output.checked_add(threshold)?;
+    let mut selections = Vec::with_capacity(pools.len());
+
+    for (mut inputs, cap) in pools {
+        let cap = cap.unwrap_or(u64::MAX);
+        let mut taken: u64 = 0;
+        let mut count = 0;
+
+        while goal.value > 0 || !goal.assets.is_empty() {
+            let best = inputs[count..]
+                .iter()
+                .enumerate()
+                .filter(|(_, input)| {
+                    taken.checked_add(input.value).is_some_and(|t| t <= cap)
+                        && is_wanted(input, &goal)
+                })
+                .min_by(|(_, a), (_, b)| a.compare(b, &goal))
+                .map(|(index, _)| count + index);
+
+            let Some(best) = best else {
+                break;
+            };
+
+            inputs.swap(count, best);
+            taken += inputs[count].value;
+            goal = goal.saturating_sub(&inputs[count]);
+            count += 1;
+        }
+
+        let unselected = inputs.split_off(count);
+        selections.push((inputs, unselected));
+    }
+
+    if goal.value > 0 || !goal.assets.is_empty() {
+        return None;
+    }
+
+    let selected: Vec<Output> = selections
+        .iter()
+        .flat_map(|(selected, _)| selected.iter().cloned())
+        .collect();
+    let excess = Total::of(&selected).excess(output).ok()?;
+
+    Some((selections, excess))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        options::{
+            Consolidate, DustTokens, MinChange, Objective, RequireAsset, SelectOptions, Strategy,
+            TokenDustFloor,
+        },
+        selection::{
+            alternatives, dust_tokens, exceeds_max_inputs, include_token_dust, select,
+            select_apart, select_fast, select_pinned, select_pools, select_with_threshold,
+            shortfall, Threshold,
+        },
+        Output,
+    };
+    use std::collections::BTreeMap;
+    use wasm_bindgen_test::*;
+
+    fn value(output: &Output) -> (u64, Option<u64>) {
+        (output.value, output.assets.values().next().copied())
+    }
+
+    fn output(value: u64, quantity: u64) -> Output<'static> {
+        let mut output = Output {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        output.insert_asset(("policy1".into(), "aname1".into()), quantity);
+        output
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_select_pinned() {
+        let pool = vec![output(5000, 0), output(1000, 10), output(2000, 0)];
+        let threshold = output(100, 0);
+
+        let selection = select_pinned(
+            vec![output(3000, 0)],
+            pool.clone(),
+            &output(2000, 0),
+            &threshold,
+            &Strategy::Default,
+        )
+        .unwrap();
+        assert_eq!(selection.selected.len(), 1);
+        assert_eq!(selection.unselected.len(), 3);
+        assert_eq!(value(&selection.excess), (1000, None));
+
+        let selection = select_pinned(
+            vec![output(3000, 0)],
+            pool.clone(),
+            &output(3500, 5),
+            &threshold,
+            &Strategy::Default,
+        )
+        .unwrap();
+        assert_eq!(selection.selected.len(), 2);
+        assert_eq!(value(&selection.selected[1]), (1000, Some(10)));
+        assert_eq!(value(&selection.excess), (500, Some(5)));
+
+        assert!(select_pinned(
+            vec![output(3000, 0)],
+            pool,
+            &output(20000, 0),
+            &threshold,
+            &Strategy::Default
+        )
+        .is_none());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_select_apart() {
+        let inputs = vec![
+            output(3000, 0),
+            output(12000, 0),
+            output(5000, 0),
+            output(8000, 0),
+            output(1000, 5),
+        ];
+        let options = SelectOptions::default();
+        let address = |input: &Output| match input.value {
+            12000 | 5000 => Some("addr1".to_string()),
+            8000 | 3000 => Some("addr2".to_string()),
+            _ => None,
+        };
+        let values = |inputs: &[Output]| {
+            let mut values: Vec<u64> = inputs.iter().map(|i| i.value).collect();
+            values.sort();
+            values
+        };
+        let select =
+            |target| select_apart(inputs.clone(), &target, &output(0, 0), &options, address);
+
+        // 12000 + 5000 of one address rather than 12000 + 8000 of both.
+        let selection = select(output(16000, 0)).unwrap();
+        assert_eq!(values(&selection.selected), vec![5000, 12000]);
+        assert_eq!(selection.unselected.len(), 3);
+
+        let selection = select(output(10000, 0)).unwrap();
+        assert_eq!(values(&selection.selected), vec![12000]);
+
+        // Mixed when unavoidable, from the addresses of most lovelace.
+        let selection = select(output(22000, 0)).unwrap();
+        assert_eq!(values(&selection.selected), vec![5000, 8000, 12000]);
+        let selection = select(output(1000, 5)).unwrap();
+        assert_eq!(values(&selection.selected), vec![1000]);
+        let selection = select(output(20000, 5)).unwrap();
+        assert_eq!(values(&selection.selected), vec![1000, 8000, 12000]);
+        assert!(select(output(30000, 0)).is_none());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_shortfall() {
+        let inputs = vec![output(3000, 2), output(1000, 0)];
+        let shortfall = |target: Output<'static>, threshold| {
+            shortfall(&inputs, &target, &threshold, &SelectOptions::default()).unwrap()
+        };
+
+        let mut goal = output(5000, 3);
+        goal.insert_asset(("policy2".into(), "aname2".into()), 4);
+        let lacking = shortfall(goal, Threshold::Output(output(0, 0)));
+        let mut expected = output(1000, 1);
+        expected.insert_asset(("policy2".into(), "aname2".into()), 4);
+        assert_eq!(lacking.value, expected.value);
+        assert_eq!(lacking.assets, expected.assets);
+
+        let lacking = shortfall(output(3500, 1), Threshold::Output(output(1000, 0)));
+        assert_eq!(value(&lacking), (500, None));
+        let change = Threshold::Change {
+            fee_reserve: 200,
+            min_change: 1_000_000,
+        };
+        assert_eq!(value(&shortfall(output(4000, 2), change)), (200, None));
+
+        // Only the inputs of the required asset count, if the others may not top them up.
+        let mut options = SelectOptions {
+            require_asset: Some(RequireAsset {
+                asset: ("policy1".into(), "aname1".into()),
+                fallback: false,
+            }),
+            ..SelectOptions::default()
+        };
+        let target = output(3500, 0);
+        let threshold = Threshold::Output(output(0, 0));
+        let lacking = super::shortfall(&inputs, &target, &threshold, &options).unwrap();
+        assert_eq!(value(&lacking), (500, None));
+        options.require_asset.as_mut().unwrap().fallback = true;
+        let lacking = super::shortfall(&inputs, &target, &threshold, &options).unwrap();
+        assert_eq!(value(&lacking), (0, None));
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_max_inputs() {
+        let inputs = vec![output(6500, 1), output(500, 6), output(7000, 0)];
+        let mut options = SelectOptions {
+            max_inputs: Some(2),
+            ..SelectOptions::default()
+        };
+        let select = |target, options: &SelectOptions| {
+            select(inputs.clone(), &target, &output(0, 0), options)
+        };
+
+        // The default spends the holder of the most lovelace first, so all three.
+        assert_eq!(
+            select(output(7500, 6), &SelectOptions::default())
+                .unwrap()
+                .selected
+                .len(),
+            3
+        );
+        let selection = select(output(7500, 6), &options).unwrap();
+        assert_eq!(selection.selected.len(), 2);
+        assert_eq!(selection.excess.value, 0);
+
+        options.max_inputs = Some(1);
+        assert!(select(output(7500, 6), &options).is_none());
+        assert!(exceeds_max_inputs(
+            inputs.clone(),
+            &output(10000, 6),
+            &Threshold::Output(output(0, 0)),
+            &options
+        ));
+        assert!(!exceeds_max_inputs(
+            inputs.clone(),
+            &output(90000, 0),
+            &Threshold::Output(output(0, 0)),
+            &options
+        ));
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_include_token_dust() {
+        let inputs = vec![output(8000, 9), output(1000, 4), output(3000, 0)];
+        let selection = |max_inputs| {
+            let selection = select(
+                inputs.clone(),
+                &output(5000, 8),
+                &output(0, 0),
+                &SelectOptions::default(),
+            )
+            .unwrap();
+            let floor = TokenDustFloor {
+                quantity: 10,
+                assets: BTreeMap::new(),
+            };
+            include_token_dust(selection, &floor, max_inputs)
+        };
+
+        // 1 token left is below the floor, so the other 4 are added.
+        let dusted = selection(None);
+        assert_eq!(dusted.selected.len(), 2);
+        assert_eq!(value(&dusted.excess), (4000, Some(5)));
+        assert_eq!(dusted.unselected.len(), 1);
+
+        let kept = selection(Some(1));
+        assert_eq!(kept.selected.len(), 1);
+        assert_eq!(value(&kept.excess), (3000, Some(1)));
+        assert_eq!(kept.unselected.len(), 2);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_alternatives() {
+        let inputs = vec![
+            output(3000, 0),
+            output(12000, 0),
+            output(5000, 0),
+            output(8000, 0),
+        ];
+        let mut options = SelectOptions {
+            alternatives: 3,
+            ..SelectOptions::default()
+        };
+
+        let found = alternatives(&inputs, &output(10000, 0), &output(0, 0), &options);
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].selected.len(), 1);
+        let mut values: Vec<Vec<u64>> = found
+            .iter()
+            .map(|s| {
+                let mut values: Vec<u64> = s.selected.iter().map(|i| i.value).collect();
+                values.sort();
+                values
+            })
+            .collect();
+        values.dedup();
+        assert_eq!(values.len(), 3);
+
+        options.objective = Objective::SmallestChange;
+        let found = alternatives(&inputs, &output(10000, 0), &output(0, 0), &options);
+        assert!(found
+            .windows(2)
+            .all(|pair| pair[0].excess.value <= pair[1].excess.value));
+
+        // Without 12000, the others are only enough together, more than `maxInputs`.
+        options.max_inputs = Some(2);
+        let found = alternatives(&inputs, &output(15000, 0), &output(0, 0), &options);
+        assert!(!found.is_empty());
+        assert!(found.iter().all(|s| s.selected.len() <= 2));
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_auto_strategy() {
+        let inputs = vec![
+            output(3000, 0),
+            output(12000, 0),
+            output(7000, 0),
+            output(4000, 0),
+        ];
+        let mut options = SelectOptions {
+            objective: Objective::SmallestChange,
+            ..SelectOptions::default()
+        };
+        let select = |options: &SelectOptions| {
+            select(inputs.clone(), &output(10000, 0), &output(0, 0), options)
+                .unwrap()
+                .excess
+                .value
+        };
+
+        let default = select(&options);
+        options.strategy = Strategy::Auto { budget_ms: 1000.0 };
+        assert_eq!(select(&options), 0);
+        assert!(default > 0);
+
+        // 3000 and 7000 match exactly, but only one input is allowed.
+        options.max_inputs = Some(1);
+        assert_eq!(select(&options), 2000);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_fewest_change_assets() {
+        let mut junk = output(12000, 0);
+        junk.insert_asset(("policy2".into(), "junk".into()), 1);
+        let inputs = vec![junk, output(7000, 0), output(4000, 0)];
+        let mut options = SelectOptions {
+            strategy: Strategy::Auto { budget_ms: 1000.0 },
+            ..SelectOptions::default()
+        };
+        let select = |options: &SelectOptions| {
+            select(inputs.clone(), &output(10000, 0), &output(0, 0), options).unwrap()
+        };
+
+        assert_eq!(select(&options).selected.len(), 1);
+        options.objective = Objective::FewestChangeAssets;
+        let selection = select(&options);
+        assert_eq!(selection.selected.len(), 2);
+        assert_eq!(value(&selection.excess), (1000, None));
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_asset_weights() {
+        let mut voucher = output(10000, 0);
+        voucher.insert_asset(("policy2".into(), "voucher".into()), 1);
+        let inputs = vec![output(10000, 1), voucher];
+        let key = ("policy2".to_string(), "voucher".to_string());
+        let mut options = SelectOptions {
+            alternatives: 2,
+            ..SelectOptions::default()
+        };
+        let first = |options: &SelectOptions| {
+            alternatives(&inputs, &output(10000, 0), &output(0, 0), options)[0]
+                .selected
+                .iter()
+                .any(|input| input.assets.contains_key(&key))
+        };
+
+        options.asset_weights.insert(key.clone(), 1.0);
+        assert!(first(&options));
+        options.asset_weights.insert(key.clone(), -1.0);
+        assert!(!first(&options));
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_select_with_threshold() {
+        let excess = |inputs: &[u64], fee_reserve, min_change| {
+            let inputs = inputs.iter().map(|value| output(*value, 0)).collect();
+            let threshold = Threshold::Change {
+                fee_reserve,
+                min_change,
+            };
+            select_with_threshold(
+                inputs,
+                &output(10000, 0),
+                &threshold,
+                &SelectOptions::default(),
+            )
+            .map(|thresholded| thresholded.selection.excess.value)
+        };
+
+        assert_eq!(excess(&[10200], 200, 1000), Some(200));
+        assert_eq!(excess(&[10200], 100, 50), Some(200));
+        assert_eq!(excess(&[10200], 100, 1000), None);
+        assert!(excess(&[10200, 12000], 100, 1000).is_some_and(|excess| excess >= 1100));
+
+        let options = SelectOptions {
+            min_change: Some(MinChange::Lovelace(1000)),
+            ..SelectOptions::default()
+        };
+        let inputs = vec![output(10200, 0), output(900, 0)];
+        let threshold = Threshold::Output(output(100, 0));
+        let thresholded =
+            select_with_threshold(inputs.clone(), &output(10000, 0), &threshold, &options).unwrap();
+        assert_eq!(thresholded.threshold.value, 1100);
+        assert_eq!(thresholded.base.value, 100);
+        assert_eq!(thresholded.selection.excess.value, 1100);
+        assert_eq!(thresholded.donated, 0);
+
+        let options = SelectOptions {
+            absorb_change: 100,
+            ..options
+        };
+        let thresholded =
+            select_with_threshold(inputs, &output(10000, 0), &threshold, &options).unwrap();
+        assert_eq!(thresholded.selection.excess.value, 200);
+        assert_eq!(thresholded.donated, 100);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_consolidate() {
+        let inputs = vec![
+            output(500, 0),
+            output(90000, 0),
+            output(300, 0),
+            output(700, 0),
+            output(100, 0),
+        ];
+        let options = SelectOptions {
+            consolidate: Some(Consolidate {
+                max_inputs: 3,
+                min_lovelace: 200,
+                max_lovelace: 1000,
+            }),
+            ..SelectOptions::default()
+        };
+        let selection = select(inputs.clone(), &output(0, 0), &output(0, 0), &options).unwrap();
+        let values: Vec<u64> = selection.selected.iter().map(|i| i.value).collect();
+        assert_eq!(values, vec![300, 500, 700]);
+        assert_eq!(selection.excess.value, 1500);
+
+        // More inputs are selected if the merged ones are not enough for the threshold.
+        let selection = select(inputs, &output(0, 0), &output(2000, 0), &options).unwrap();
+        assert_eq!(selection.selected.len(), 4);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_select_fast() {
+        let mut rng = crate::rng::Rng::new(7);
+        let inputs: Vec<Output> = (0..200)
+            .map(|i| output(rng.below(1_000_000) * 200 + i, 0))
+            .collect();
+
+        for goal in [0, 1, 5_000_000, 50_000_000, 100_000_000_000] {
+            let mut slow = inputs.clone();
+            let mut fast = inputs.clone();
+            let expected = utxo::select(&mut slow[..], &output(goal, 0), &output(1000, 0));
+            let actual = select_fast(&mut fast[..], &output(goal, 0), &output(1000, 0));
+
+            match (expected, actual) {
+                (Some((s1, _, e1)), Some((s2, _, e2))) => {
+                    let mut s1: Vec<u64> = s1.iter().map(|i| i.value).collect();
+                    let s2: Vec<u64> = s2.iter().map(|i| i.value).collect();
+                    s1.sort_by_key(|v| std::cmp::Reverse(*v));
+                    assert_eq!(s1, s2);
+                    assert_eq!(e1.value, e2.value);
+                    assert!(e2.assets.is_empty());
+                }
+                (None, None) => {}
+                _ => panic!("different feasibility for {goal}"),
+            }
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_require_asset() {
+        let inputs = vec![output(3000, 10), output(50000, 0), output(4000, 20)];
+        let mut options = SelectOptions {
+            require_asset: Some(RequireAsset {
+                asset: ("policy1".into(), "aname1".into()),
+                fallback: false,
+            }),
+            ..SelectOptions::default()
+        };
+        let selected = |goal, options: &SelectOptions| {
+            select(inputs.clone(), &output(goal, 0), &output(0, 0), options).map(|selection| {
+                let mut values: Vec<u64> = selection.selected.iter().map(|i| i.value).collect();
+                values.sort();
+                (values, selection.unselected.len())
+            })
+        };
+
+        assert_eq!(selected(6000, &options), Some((vec![3000, 4000], 1)));
+        assert_eq!(selected(10000, &options), None);
+
+        options.require_asset.as_mut().unwrap().fallback = true;
+        assert_eq!(
+            selected(10000, &options),
+            Some((vec![3000, 4000, 50000], 0))
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_dust_tokens() {
+        let mut excess = output(1000, 5);
+        excess.insert_asset(("policy2".into(), "aname2".into()), 1);
+        let mut holder = output(2000, 1000);
+        holder.insert_asset(("policy2".into(), "aname2".into()), 1);
+        let inputs = vec![holder];
+
+        let dust = dust_tokens(&excess, &[&inputs], &DustTokens::default());
+        assert_eq!(dust, vec![(("policy1".into(), "aname1".into()), 5)]);
+
+        let thresholds = DustTokens {
+            ratio: 0.0,
+            quantity: 1,
+        };
+        let dust = dust_tokens(&excess, &[&inputs], &thresholds);
+        assert_eq!(dust, vec![(("policy2".into(), "aname2".into()), 1)]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_select_pools() {
+        let hot = vec![output(1000, 0), output(500, 0)];
+        let treasury = vec![output(3000, 0), output(2000, 0), output(800, 10)];
+        let threshold = output(100, 0);
+        let pools = |cap| vec![(hot.clone(), None), (treasury.clone(), cap)];
+
+        let (selections, excess) = select_pools(pools(None), &output(1200, 0), &threshold).unwrap();
+        assert_eq!(selections[0].0.len(), 2);
+        assert_eq!(selections[1].0.len(), 0);
+        assert_eq!(value(&excess), (300, None));
+
+        // The treasury gives at most 2500, so not its 3000.
+        let (selections, _) =
+            select_pools(pools(Some(2500)), &output(2000, 0), &threshold).unwrap();
+        assert_eq!(selections[0].0.len(), 2);
+        assert_eq!(value(&selections[1].0[0]), (2000, None));
+        assert_eq!(selections[1].1.len(), 2);
+
+        let (selections, excess) =
+            select_pools(pools(Some(1000)), &output(0, 5), &threshold).unwrap();
+        assert_eq!(value(&selections[1].0[0]), (800, Some(10)));
+        assert_eq!(value(&excess), (1800, Some(5)));
+
+        assert!(select_pools(pools(Some(2500)), &output(5000, 0), &threshold).is_none());
+    }
+}
@@ -0,0 +1,195 @@
+/*!
+Transaction skeletons
+
+A skeleton is what a transaction is made of, in plain JSON,
+for builders of any serialization library to complete and sign.
+*/
+use crate::{
+    collect_js_outputs, get_amount, get_field, out_point::OutPoint, raise, to_outputs, ErrorCode,
+    ErrorKind, Output, SelectResult,
+};
+use js_sys::{Array, Object, Reflect};
+use std::collections::BTreeMap;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type SkeletonOptions = {
+  outputs?: Array<Output>
+  changeOutputs: Array<Output>
+  fee: bigint
+  ttl?: number
+}
+
+export type SkeletonValue = {
+  lovelace: string
+  assets: { [policyId: string]: { [assetName: string]: string } }
+}
+
+export type TxSkeleton = {
+  inputs: Array<OutPoint>
+  outputs: Array<{ address: string, value: SkeletonValue }>
+  fee: string
+  ttl?: number
+  mint: { [policyId: string]: { [assetName: string]: string } }
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SkeletonOptions")]
+    pub type JsSkeletonOptions;
+
+    #[wasm_bindgen(typescript_type = "TxSkeleton")]
+    pub type TxSkeleton;
+}
+
+/// The assets grouped by policy ID, with quantities as decimal strings.
+fn assets_json(assets: &BTreeMap<(String, String), u64>) -> Object {
+    let result = Object::new();
+    let mut policies: BTreeMap<&str, Object> = BTreeMap::new();
+
+    for ((policy_id, asset_name), quantity) in assets.iter() {
+        let policy = policies.entry(policy_id).or_insert_with(Object::new);
+        Reflect::set(policy, &asset_name.into(), &quantity.to_string().into())
+            .expect("Unreachable");
+    }
+
+    for (policy_id, policy) in policies {
+        Reflect::set(&result, &policy_id.into(), &policy).expect("Unreachable");
+    }
+
+    result
+}
+
+fn output_json(address: &str, output: &Output) -> Object {
+    let value = Object::new();
+    Reflect::set(&value, &"lovelace".into(), &output.value.to_string().into())
+        .expect("Unreachable");
+    Reflect::set(&value, &"assets".into(), &assets_json(&output.assets)).expect("Unreachable");
+
+    let result = Object::new();
+    Reflect::set(&result, &"address".into(), &address.into()).expect("Unreachable");
+    Reflect::set(&result, &"value".into(), &value).expect("Unreachable");
+    result
+}
+
+/**
+Make the skeleton of the transaction of a selection
+
+The `inputs` are the `OutPoint`s of the selected UTxOs, and the `outputs` are
+the `outputs` of the options followed by the `changeOutputs`, all with their addresses.
+Amounts are decimal strings, so the skeleton survives `JSON.stringify`.
+Selection mints nothing, so `mint` is empty for builders to fill.
+
+Raises errors when the types used are wrong, or any `OutPoint` or address is missing.
+*/
+#[wasm_bindgen(js_name = toTxSkeleton)]
+pub fn to_tx_skeleton(
+    result: &SelectResult,
+    options: &JsSkeletonOptions,
+) -> Result<TxSkeleton, JsError> {
+    let selected = collect_js_outputs(get_field(result, "selected")?.unchecked_ref())?;
+    let payments = get_field(options, "outputs")?;
+    let mut js_outputs = if payments.is_undefined() {
+        Vec::new()
+    } else {
+        collect_js_outputs(payments.unchecked_ref())?
+    };
+    js_outputs.extend(collect_js_outputs(
+        get_field(options, "changeOutputs")?.unchecked_ref(),
+    )?);
+    let fee = get_amount(options, "fee")?.ok_or(ErrorCode::InvalidType)?;
+    let ttl = get_field(options, "ttl")?;
+
+    let inputs = Array::new();
+    for utxo in selected.iter() {
+        let out_point = OutPoint::of(utxo).ok_or_else(|| {
+            raise(
+                ErrorKind::MissingOutPoint,
+                "Selected UTxOs should have their OutPoint as data",
+            )
+        })?;
+        inputs.push(&out_point.to_js());
+    }
+
+    let outputs = Array::new();
+    for (js_output, output) in js_outputs.iter().zip(to_outputs(&js_outputs)?) {
+        let address = get_field(js_output, "address")?
+            .as_string()
+            .ok_or_else(|| {
+                raise(
+                    ErrorKind::MissingAddress,
+                    "Outputs should have their addresses",
+                )
+            })?;
+        outputs.push(&output_json(&address, &output));
+    }
+
+    let skeleton = Object::new();
+    let set = |key: &str, value: &JsValue| {
+        Reflect::set(&skeleton, &key.into(), value).expect("Unreachable");
+    };
+
+    set("inputs", &inputs);
+    set("outputs", &outputs);
+    set("fee", &fee.to_string().into());
+
+    if !ttl.is_undefined() {
+        let ttl = ttl
+            .as_f64()
+            .filter(|t| *t >= 0.0 && t.fract() == 0.0)
+            .ok_or(ErrorCode::InvalidType)?;
+        set("ttl", &ttl.into());
+    }
+
+    set("mint", &Object::new());
+
+    Ok(skeleton.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{out_point::OutPoint, skeleton::to_tx_skeleton, JsOutput, Output, SelectResult};
+    use js_sys::{Array, Object, Reflect, JSON};
+    use std::collections::BTreeMap;
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_to_tx_skeleton() {
+        let mut output = Output {
+            value: 2_000_000,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        output.insert_asset(("policy1".into(), "6d79".into()), 5);
+
+        let utxo: JsOutput = output.clone().into();
+        utxo.set_data(&OutPoint::new(&"ab".repeat(32), 1).unwrap().to_js());
+        let change: JsOutput = output.into();
+        change.set_address("addr1");
+
+        let result: SelectResult = Object::new().unchecked_into();
+        result.set_selected(&Array::of1(&utxo).unchecked_into());
+
+        let options = Object::new();
+        Reflect::set(&options, &"changeOutputs".into(), &Array::of1(&change)).unwrap();
+        Reflect::set(&options, &"fee".into(), &JsValue::from(170000u64)).unwrap();
+        Reflect::set(&options, &"ttl".into(), &1000.into()).unwrap();
+
+        let skeleton = to_tx_skeleton(&result, options.unchecked_ref()).unwrap();
+        assert_eq!(
+            JSON::stringify(&skeleton).unwrap(),
+            format!(
+                r#"{{"inputs":[{{"hash":"{}","index":1}}],"outputs":[{{"address":"addr1","value":{{"lovelace":"2000000","assets":{{"policy1":{{"6d79":"5"}}}}}}}}],"fee":"170000","ttl":1000,"mint":{{}}}}"#,
+                "ab".repeat(32)
+            )
+        );
+
+        Reflect::set(&options, &"changeOutputs".into(), &Array::of1(&utxo)).unwrap();
+        assert!(to_tx_skeleton(&result, options.unchecked_ref()).is_err());
+    }
+}
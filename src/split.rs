@@ -0,0 +1,303 @@
+/*!
+Planning the split of a UTxO
+
+A large UTxO can only be spent by one transaction at a time.
+Splitting it into pieces lets independent transactions run concurrently.
+*/
+use crate::{
+    fee::TxShape,
+    get_amount, get_count,
+    min_ada::min_ada,
+    protocol::{JsProtocolParameters, ProtocolParameters},
+    raise, ErrorKind, JsOutput, JsOutputArray, Output,
+};
+use js_sys::{Array, Object, Reflect};
+use std::collections::BTreeMap;
+use utxo::ExtOutput;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type SplitBy = { pieces: number } | { targetLovelace: bigint }
+
+export type SplitPlan = {
+  outputs: Array<Output>
+  fee: bigint
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SplitBy")]
+    pub type JsSplitBy;
+
+    #[wasm_bindgen(typescript_type = "SplitPlan")]
+    pub type SplitPlan;
+}
+
+pub(crate) type Piece = ExtOutput<(), (String, String)>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SplitBy {
+    /// Into the number of pieces of about the same lovelace.
+    Pieces(u64),
+    /// Into pieces of the lovelace, and one more piece of what remains.
+    Target(u64),
+}
+
+fn shape_of(source: &Piece, outputs: u64) -> TxShape {
+    TxShape {
+        inputs: 1,
+        outputs,
+        assets: source.assets.len() as u64,
+        witnesses: 1,
+    }
+}
+
+fn fee_of(params: &ProtocolParameters, source: &Piece, outputs: u64) -> Option<u64> {
+    params.fee.fee(shape_of(source, outputs).size())
+}
+
+/// Whether the transaction splitting the source into the outputs fits in `maxTxSize`.
+fn fits(params: &ProtocolParameters, source: &Piece, outputs: u64) -> bool {
+    shape_of(source, outputs).size() <= params.max_tx_size
+}
+
+/// The most outputs up to `limit` the transaction splitting the source fits in `maxTxSize`.
+fn max_outputs(params: &ProtocolParameters, source: &Piece, limit: u64) -> u64 {
+    let (mut low, mut high) = (0, limit);
+
+    while low < high {
+        let middle = low + (high - low).div_ceil(2);
+
+        if fits(params, source, middle) {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+
+    low
+}
+
+pub(crate) fn piece(value: u64, assets: BTreeMap<(String, String), u64>) -> Piece {
+    ExtOutput {
+        value,
+        assets,
+        data: None,
+    }
+}
+
+/**
+Plan the pieces of the source, and the fee of the transaction splitting it.
+
+The first piece holds all the assets of the source.
+Returns `None` if the source is too small to split as planned,
+or the transaction splitting it does not fit in `maxTxSize`.
+*/
+pub(crate) fn plan(
+    source: &Piece,
+    by: SplitBy,
+    params: &ProtocolParameters,
+) -> Option<(Vec<Piece>, u64)> {
+    let first_min = min_ada(source, params.coins_per_utxo_byte);
+    let other_min = min_ada(&piece(0, BTreeMap::new()), params.coins_per_utxo_byte);
+
+    match by {
+        SplitBy::Pieces(0) => None,
+        SplitBy::Pieces(count) if !fits(params, source, count) => None,
+        SplitBy::Pieces(count) => {
+            let fee = fee_of(params, source, count)?;
+            let available = source.value.checked_sub(fee)?;
+            let share = available / count;
+            let (first, share) = if share + available % count >= first_min {
+                (share + available % count, share)
+            } else if count > 1 {
+                let rest = available.checked_sub(first_min)?;
+                (first_min + rest % (count - 1), rest / (count - 1))
+            } else {
+                return None;
+            };
+
+            if count > 1 && share < other_min {
+                return None;
+            }
+
+            let mut pieces = vec![piece(first, source.assets.clone())];
+            pieces.extend((1..count).map(|_| piece(share, BTreeMap::new())));
+
+            Some((pieces, fee))
+        }
+        SplitBy::Target(target) if target == 0 || target < other_min => None,
+        SplitBy::Target(target) => {
+            // The pieces of the target, and one more of what remains if it fits.
+            let most = max_outputs(params, source, source.value / target + 1);
+
+            for count in (1..=(source.value / target).min(most)).rev() {
+                let targets = target * count;
+
+                let remainder = fee_of(params, source, count + 1)
+                    .filter(|_| count < most)
+                    .and_then(|fee| {
+                        Some((source.value.checked_sub(targets)?.checked_sub(fee)?, fee))
+                    });
+
+                if let Some((remainder, fee)) = remainder.filter(|(r, _)| *r >= first_min) {
+                    let mut pieces = vec![piece(remainder, source.assets.clone())];
+                    pieces.extend((0..count).map(|_| piece(target, BTreeMap::new())));
+
+                    return Some((pieces, fee));
+                }
+
+                if !source.assets.is_empty() {
+                    continue;
+                }
+
+                // Without assets to hold, what remains goes to the first piece.
+                let remainder = fee_of(params, source, count).and_then(|fee| {
+                    Some((source.value.checked_sub(targets)?.checked_sub(fee)?, fee))
+                });
+
+                if let Some((remainder, fee)) = remainder {
+                    let mut pieces = vec![piece(target + remainder, BTreeMap::new())];
+                    pieces.extend((1..count).map(|_| piece(target, BTreeMap::new())));
+
+                    return Some((pieces, fee));
+                }
+            }
+
+            None
+        }
+    }
+}
+
+/**
+Plan the split of a UTxO into pieces
+
+Splits either into a number of `pieces` of about the same lovelace,
+or into pieces of `targetLovelace` each, plus one piece of what remains,
+as fit in a transaction of `maxTxSize`.
+The fee of the splitting transaction is estimated from its size and deducted,
+and every piece holds at least its minimum lovelace.
+The first piece holds all the assets of the UTxO.
+Any address of the UTxO is copied to the pieces.
+
+Raises errors when the types used are wrong, when the UTxO is too small to split as planned,
+or the pieces are more than a transaction fits.
+*/
+#[wasm_bindgen(js_name = planSplit)]
+pub fn plan_split(
+    utxo: &JsOutput,
+    by: &JsSplitBy,
+    params: &JsProtocolParameters,
+) -> Result<SplitPlan, JsError> {
+    let by = match (get_count(by, "pieces")?, get_amount(by, "targetLovelace")?) {
+        (Some(pieces), None) => SplitBy::Pieces(pieces.into()),
+        (None, Some(target)) => SplitBy::Target(target),
+        _ => {
+            return Err(raise(
+                ErrorKind::BadOptions,
+                "Either pieces or targetLovelace should be given",
+            ))
+        }
+    };
+    let source: Output = utxo.try_into()?;
+    let source = piece(source.value, source.assets);
+    let (pieces, fee) = plan(&source, by, &params.try_into()?)
+        .ok_or_else(|| raise(ErrorKind::Unsplittable, "UTxO is too small to split"))?;
+
+    let address = utxo.address();
+    let outputs = Array::new();
+
+    for piece in pieces {
+        let output: Output = ExtOutput {
+            value: piece.value,
+            assets: piece.assets,
+            data: None,
+        };
+        let output: JsOutput = output.into();
+
+        if let Some(address) = address.as_deref() {
+            output.set_address(address);
+        }
+
+        outputs.push(&output);
+    }
+
+    let outputs: JsOutputArray = outputs.unchecked_into();
+    let result = Object::new();
+    Reflect::set(&result, &"outputs".into(), &outputs).expect("Unreachable");
+    Reflect::set(&result, &"fee".into(), &fee.into()).expect("Unreachable");
+
+    Ok(result.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fee::TxShape,
+        min_ada::min_ada,
+        protocol::tests::mainnet,
+        split::{piece, plan, SplitBy},
+    };
+    use std::collections::BTreeMap;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_plan_split() {
+        let params = mainnet();
+        let mut source = piece(100_000_000, BTreeMap::new());
+        source.insert_asset(("ab".repeat(28), "cd".repeat(4)), 10);
+
+        let (pieces, fee) = plan(&source, SplitBy::Pieces(4), &params).unwrap();
+        assert_eq!(pieces.len(), 4);
+        assert_eq!(
+            pieces.iter().map(|p| p.value).sum::<u64>() + fee,
+            100_000_000
+        );
+        assert_eq!(pieces[0].assets, source.assets);
+        assert!(pieces[1..].iter().all(|p| p.assets.is_empty()));
+
+        let (pieces, fee) = plan(&source, SplitBy::Target(30_000_000), &params).unwrap();
+        assert_eq!(pieces.len(), 4);
+        assert!(pieces[1..].iter().all(|p| p.value == 30_000_000));
+        assert_eq!(pieces[0].value + 90_000_000 + fee, 100_000_000);
+        assert!(pieces[0].value >= min_ada(&source, params.coins_per_utxo_byte));
+
+        // Nothing remains for the assets beside three pieces.
+        let (pieces, _) = plan(&source, SplitBy::Target(33_000_000), &params).unwrap();
+        assert_eq!(pieces.len(), 3);
+        assert_eq!(pieces[0].assets, source.assets);
+
+        source.assets.clear();
+        let (pieces, fee) = plan(&source, SplitBy::Target(33_000_000), &params).unwrap();
+        assert_eq!(pieces.len(), 3);
+        assert_eq!(pieces[0].value + 66_000_000 + fee, 100_000_000);
+
+        assert!(plan(&source, SplitBy::Pieces(0), &params).is_none());
+        assert!(plan(&source, SplitBy::Pieces(200), &params).is_none());
+        assert!(plan(&source, SplitBy::Target(100), &params).is_none());
+
+        // Free outputs of no target, or more than a transaction fits.
+        let mut params = params;
+        params.coins_per_utxo_byte = 0;
+        assert!(plan(&source, SplitBy::Target(0), &params).is_none());
+        assert!(plan(&source, SplitBy::Pieces(1000), &params).is_none());
+        let (pieces, fee) = plan(&source, SplitBy::Target(1000), &params).unwrap();
+        assert!(pieces.len() < 1000);
+        assert!(
+            TxShape {
+                inputs: 1,
+                outputs: pieces.len() as u64,
+                assets: 0,
+                witnesses: 1,
+            }
+            .size()
+                <= params.max_tx_size
+        );
+        assert_eq!(
+            pieces.iter().map(|p| p.value).sum::<u64>() + fee,
+            100_000_000
+        );
+    }
+}
@@ -0,0 +1,188 @@
+/*!
+Branch-and-bound for selections without change
+
+As in Bitcoin Core, the subsets of the inputs are searched depth first from the largest input,
+for one exactly matching the goal: all its assets, and its lovelace up to a tolerance over,
+so no change output is needed. Branches already over the goal, or that can no longer
+reach it, are cut. Only inputs of no assets beyond the goal can be in a match.
+The search gives up after a bounded number of steps, then `select` falls back to its default.
+*/
+use crate::{
+    selection::select_fast,
+    strategy::{arrange_selected, Outputs, Selected},
+};
+use std::{cmp::Reverse, collections::BTreeMap};
+use utxo::{ExtOutput, Select};
+
+/// The steps searched at most for a match.
+const BNB_ATTEMPTS: usize = 100_000;
+
+/**
+Finds the indices of the inputs matching the goal exactly: its assets,
+and its lovelace up to `tolerance` over, preferring the least lovelace over.
+A match is of one input at least, even for a goal of nothing.
+
+Returns `None` if there is no match, or none is found in the bounded steps.
+*/
+pub(crate) fn find<D>(
+    inputs: &[ExtOutput<D, (String, String)>],
+    goal: &ExtOutput<D, (String, String)>,
+    tolerance: u64,
+) -> Option<Vec<usize>> {
+    let wanted: BTreeMap<&(String, String), u64> = goal
+        .assets
+        .iter()
+        .filter(|(_, quantity)| **quantity > 0)
+        .map(|(key, quantity)| (key, *quantity))
+        .collect();
+    let mut pool: Vec<usize> = (0..inputs.len())
+        .filter(|index| {
+            inputs[*index]
+                .assets
+                .iter()
+                .all(|(key, quantity)| *quantity == 0 || wanted.contains_key(key))
+        })
+        .collect();
+    pool.sort_by_key(|index| Reverse(inputs[*index].value));
+
+    // The lovelace of the pool from each position to the end.
+    let mut remaining = vec![0u64; pool.len() + 1];
+    for position in (0..pool.len()).rev() {
+        remaining[position] = remaining[position + 1].saturating_add(inputs[pool[position]].value);
+    }
+
+    let lower = goal.value;
+    let upper = goal.value.saturating_add(tolerance);
+    let mut included: Vec<usize> = Vec::new();
+    let mut lovelace: u64 = 0;
+    let mut assets: BTreeMap<&(String, String), u64> = BTreeMap::new();
+    let mut best: Option<(u64, Vec<usize>)> = None;
+    let mut position = 0;
+
+    for _ in 0..BNB_ATTEMPTS {
+        let over = lovelace > upper || assets.iter().any(|(key, q)| *q > wanted[key]);
+        let short = lovelace.saturating_add(remaining[position]) < lower;
+        let matched = !over && !included.is_empty() && lovelace >= lower && assets == wanted;
+
+        if matched
+            && best
+                .as_ref()
+                .is_none_or(|(excess, _)| lovelace - lower < *excess)
+        {
+            best = Some((lovelace - lower, included.clone()));
+
+            if lovelace == lower {
+                break;
+            }
+        }
+
+        if over || short || matched || position == pool.len() {
+            // Backtrack to the branch excluding the last included input.
+            let Some(last) = included.pop() else {
+                break;
+            };
+            let input = &inputs[pool[last]];
+            lovelace -= input.value;
+            for (key, quantity) in input.assets.iter().filter(|(_, q)| **q > 0) {
+                let held = assets.get_mut(key).expect("Unreachable");
+                *held -= quantity;
+                if *held == 0 {
+                    assets.remove(key);
+                }
+            }
+            position = last + 1;
+            continue;
+        }
+
+        let input = &inputs[pool[position]];
+        lovelace = lovelace.saturating_add(input.value);
+        for (key, quantity) in input.assets.iter().filter(|(_, q)| **q > 0) {
+            let held = assets.entry(key).or_default();
+            *held = held.saturating_add(*quantity);
+        }
+        included.push(position);
+        position += 1;
+    }
+
+    best.map(|(_, included)| included.into_iter().map(|p| pool[p]).collect())
+}
+
+/// Selects the inputs matching the output plus threshold exactly, see [`find`], or as `select_fast`.
+pub(crate) fn select<'i, D: Clone>(
+    inputs: Outputs<'i, D>,
+    output: &ExtOutput<D, (String, String)>,
+    threshold: &ExtOutput<D, (String, String)>,
+    tolerance: u64,
+) -> Option<Selected<'i, D>> {
+    let goal = output.checked_add(threshold)?;
+
+    match find(inputs, &goal, tolerance) {
+        Some(matched) => arrange_selected(inputs, &matched, output),
+        None => select_fast(inputs, output, threshold),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::branch_and_bound::{find, select};
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    fn output(value: u64, quantity: u64) -> ExtOutput<(), (String, String)> {
+        let mut output = ExtOutput {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        if quantity > 0 {
+            output.insert_asset(("policy1".into(), "aname1".into()), quantity);
+        }
+        output
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_branch_and_bound() {
+        let inputs = vec![
+            output(5000, 0),
+            output(3000, 0),
+            output(2500, 0),
+            output(1500, 4),
+            output(1200, 0),
+        ];
+
+        let find = |goal, tolerance| {
+            find(&inputs, &goal, tolerance).map(|mut matched| {
+                matched.sort();
+                matched
+            })
+        };
+
+        assert_eq!(find(output(6700, 0), 0), Some(vec![1, 2, 4]));
+        assert_eq!(find(output(6000, 0), 300), Some(vec![0, 4]));
+        assert_eq!(find(output(6500, 4), 0), Some(vec![0, 3]));
+        assert_eq!(find(output(5700, 0), 0), None);
+        assert_eq!(find(output(6500, 5), 1000), None);
+        assert_eq!(find(output(0, 0), 0), None);
+        assert_eq!(find(output(0, 0), 1200), Some(vec![4]));
+
+        let mut pool = inputs.clone();
+        let (selected, unselected, excess) =
+            select(&mut pool[..], &output(6000, 0), &output(700, 0), 0).unwrap();
+        assert_eq!(selected.len(), 3);
+        assert_eq!(unselected.len(), 2);
+        assert_eq!(excess, output(700, 0));
+
+        // Without a match, as `select_fast`.
+        let (selected, _, excess) =
+            select(&mut pool[..], &output(5700, 0), &output(0, 0), 0).unwrap();
+        assert_eq!(selected.len(), 2);
+        assert_eq!(excess, output(2300, 0));
+
+        // A goal of nothing is matched by one input at least.
+        let (selected, _, excess) =
+            select(&mut pool[..], &output(0, 0), &output(0, 0), 1500).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(excess, output(1200, 0));
+    }
+}
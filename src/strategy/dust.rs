@@ -0,0 +1,132 @@
+/*!
+Consolidation of dust while paying
+
+Inputs are selected from the smallest, so a payment spends as many small UTxOs as it can
+and a wallet merges its dust into the change without a transaction of its own.
+For each asset of the goal, the smallest holders of it come first, then the smallest of any.
+When the smallest inputs would be more than the cap, `select` falls back to its default.
+*/
+use crate::{
+    selection::select_fast,
+    strategy::{arrange_selected, Outputs, Selected},
+};
+use utxo::{ExtOutput, Select};
+
+/// The inputs the mode selects at most by default.
+pub(crate) const DUST_MAX_INPUTS: usize = 20;
+
+/**
+Finds the indices of the smallest inputs covering the goal, no more than `max_inputs`.
+
+Returns `None` if the inputs are not enough, or more than `max_inputs` of them are needed.
+*/
+pub(crate) fn find<D: Clone>(
+    inputs: &[ExtOutput<D, (String, String)>],
+    goal: &ExtOutput<D, (String, String)>,
+    max_inputs: usize,
+) -> Option<Vec<usize>> {
+    let mut order: Vec<usize> = (0..inputs.len()).collect();
+    order.sort_by_key(|index| inputs[*index].value);
+
+    let mut selected: Vec<usize> = Vec::new();
+    let mut is_selected = vec![false; inputs.len()];
+    let mut total = ExtOutput::zero();
+    let held = |total: &ExtOutput<D, (String, String)>, key| -> u64 {
+        total.assets.get(key).copied().unwrap_or(0)
+    };
+    // Adds the input, failing beyond the cap as no smallest inputs can be the selection then.
+    let add = |index: usize,
+               total: &mut ExtOutput<D, (String, String)>,
+               selected: &mut Vec<usize>,
+               is_selected: &mut [bool]| {
+        *total = total.checked_add(&inputs[index])?;
+        is_selected[index] = true;
+        selected.push(index);
+        (selected.len() <= max_inputs).then_some(())
+    };
+
+    for (key, quantity) in goal.assets.iter().filter(|(_, q)| **q > 0) {
+        for index in order.iter() {
+            if held(&total, key) >= *quantity {
+                break;
+            }
+            if held(&inputs[*index], key) > 0 && !is_selected[*index] {
+                add(*index, &mut total, &mut selected, &mut is_selected)?;
+            }
+        }
+    }
+
+    for index in order.iter() {
+        if total.value >= goal.value && !selected.is_empty() {
+            break;
+        }
+        if !is_selected[*index] {
+            add(*index, &mut total, &mut selected, &mut is_selected)?;
+        }
+    }
+
+    total.checked_sub(goal).map(|_| selected)
+}
+
+/// Selects the smallest inputs, see [`find`], or as `select_fast` if more than `max_inputs` are needed.
+pub(crate) fn select<'i, D: Clone>(
+    inputs: Outputs<'i, D>,
+    output: &ExtOutput<D, (String, String)>,
+    threshold: &ExtOutput<D, (String, String)>,
+    max_inputs: usize,
+) -> Option<Selected<'i, D>> {
+    let goal = output.checked_add(threshold)?;
+
+    match find(inputs, &goal, max_inputs) {
+        Some(selected) => arrange_selected(inputs, &selected, output),
+        None => select_fast(inputs, output, threshold),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::dust::{find, select};
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    fn output(value: u64, quantity: u64) -> ExtOutput<(), (String, String)> {
+        let mut output = ExtOutput {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        if quantity > 0 {
+            output.insert_asset(("policy1".into(), "aname1".into()), quantity);
+        }
+        output
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_dust() {
+        let inputs = vec![
+            output(50000, 0),
+            output(1200, 0),
+            output(900, 0),
+            output(3000, 4),
+            output(1500, 0),
+            output(20000, 0),
+        ];
+
+        assert_eq!(find(&inputs, &output(3000, 0), 20), Some(vec![2, 1, 4]));
+        assert_eq!(find(&inputs, &output(3000, 2), 20), Some(vec![3]));
+        assert_eq!(find(&inputs, &output(4000, 2), 20), Some(vec![3, 2, 1]));
+        assert_eq!(find(&inputs, &output(3000, 0), 2), None);
+        assert_eq!(find(&inputs, &output(90000, 0), 20), None);
+        let dust: Vec<_> = (0..50_000).map(|_| output(1, 0)).collect();
+        assert_eq!(find(&dust, &output(40_000, 0), 20), None);
+
+        // Beyond the cap, as `select_fast`.
+        let mut pool = inputs.clone();
+        pool.remove(3);
+        let (selected, _, excess) =
+            select(&mut pool[..], &output(3000, 0), &output(0, 0), 2).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(excess, output(47000, 0));
+    }
+}
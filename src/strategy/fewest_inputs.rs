@@ -0,0 +1,157 @@
+/*!
+The fewest inputs
+
+When the size of a transaction binds, the subsets of the inputs are searched depth first
+from the largest input for the fewest covering the goal. A branch is cut once the largest
+inputs left could only cover the lovelace with no fewer inputs than the best found.
+The search gives up after a bounded number of steps with the best found so far,
+and `select` falls back to Largest-First when it has found none.
+*/
+use crate::strategy::{
+    arrange_selected, largest_first::LargestFirst, select_by, Outputs, Selected,
+};
+use std::{cmp::Reverse, collections::BTreeMap};
+use utxo::{ExtOutput, Select};
+
+/// The steps searched at most for the fewest inputs.
+const FEWEST_INPUTS_ATTEMPTS: usize = 100_000;
+
+/**
+Finds the indices of the fewest inputs covering the goal, one at least even for a goal of nothing.
+
+Returns `None` if the inputs are not enough, or none are found in the bounded steps.
+*/
+pub(crate) fn find<D>(
+    inputs: &[ExtOutput<D, (String, String)>],
+    goal: &ExtOutput<D, (String, String)>,
+) -> Option<Vec<usize>> {
+    let mut order: Vec<usize> = (0..inputs.len()).collect();
+    order.sort_by_key(|index| Reverse(inputs[*index].value));
+
+    // The lovelace of the inputs in the order up to each position.
+    let mut prefix = vec![0u64; order.len() + 1];
+    for (position, index) in order.iter().enumerate() {
+        prefix[position + 1] = prefix[position].saturating_add(inputs[*index].value);
+    }
+
+    let wanted: BTreeMap<&(String, String), u64> = goal
+        .assets
+        .iter()
+        .filter(|(_, quantity)| **quantity > 0)
+        .map(|(key, quantity)| (key, *quantity))
+        .collect();
+    let mut included: Vec<usize> = Vec::new();
+    let mut lovelace: u64 = 0;
+    let mut held: BTreeMap<&(String, String), u64> = BTreeMap::new();
+    let mut best: Option<Vec<usize>> = None;
+    let mut position = 0;
+
+    for _ in 0..FEWEST_INPUTS_ATTEMPTS {
+        let covers = !included.is_empty()
+            && lovelace >= goal.value
+            && wanted
+                .iter()
+                .all(|(key, quantity)| held.get(key).copied().unwrap_or(0) >= *quantity);
+
+        if covers {
+            best = Some(included.iter().map(|p| order[*p]).collect());
+
+            if included.len() == 1 {
+                break;
+            }
+        }
+
+        // The fewest of the inputs left that may cover the lovelace still missing.
+        let needed = goal.value.saturating_sub(lovelace);
+        let more = prefix[position..].partition_point(|sum| sum - prefix[position] < needed);
+        let bound = best.as_ref().map_or(usize::MAX, |best| best.len());
+        let cut = position + more > order.len() || included.len() + more.max(1) >= bound;
+
+        if covers || cut || position == order.len() {
+            // Backtrack to the branch excluding the last included input.
+            let Some(last) = included.pop() else {
+                break;
+            };
+            let input = &inputs[order[last]];
+            lovelace -= input.value;
+            for (key, quantity) in input.assets.iter().filter(|(k, _)| wanted.contains_key(k)) {
+                *held.get_mut(key).expect("Unreachable") -= quantity;
+            }
+            position = last + 1;
+            continue;
+        }
+
+        let input = &inputs[order[position]];
+        lovelace = lovelace.saturating_add(input.value);
+        for (key, quantity) in input.assets.iter().filter(|(k, _)| wanted.contains_key(k)) {
+            let total = held.entry(key).or_default();
+            *total = total.saturating_add(*quantity);
+        }
+        included.push(position);
+        position += 1;
+    }
+
+    best
+}
+
+/// Selects the fewest inputs, see [`find`], or by Largest-First.
+pub(crate) fn select<'i, D: Clone>(
+    inputs: Outputs<'i, D>,
+    output: &ExtOutput<D, (String, String)>,
+    threshold: &ExtOutput<D, (String, String)>,
+) -> Option<Selected<'i, D>> {
+    let goal = output.checked_add(threshold)?;
+
+    match find(inputs, &goal) {
+        Some(selected) => arrange_selected(inputs, &selected, output),
+        None => select_by(&LargestFirst, inputs, output, threshold),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::fewest_inputs::find;
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    fn output(value: u64, quantity: u64) -> ExtOutput<(), (String, String)> {
+        let mut output = ExtOutput {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        if quantity > 0 {
+            output.insert_asset(("policy1".into(), "aname1".into()), quantity);
+        }
+        output
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_fewest_inputs() {
+        let inputs = vec![
+            output(1000, 3),
+            output(9000, 0),
+            output(1000, 3),
+            output(7000, 0),
+            output(6000, 0),
+            output(2000, 2),
+        ];
+        let find = |goal| {
+            find(&inputs, &goal).map(|mut selected| {
+                selected.sort();
+                selected
+            })
+        };
+
+        assert_eq!(find(output(8000, 0)), Some(vec![1]));
+        assert_eq!(find(output(15000, 0)), Some(vec![1, 3]));
+        let selected = find(output(10000, 5)).unwrap();
+        assert_eq!(selected.len(), 3);
+        assert!(selected.contains(&5));
+        assert_eq!(find(output(30000, 0)), None);
+        assert_eq!(find(output(1000, 9)), None);
+        assert_eq!(find(output(0, 0)), Some(vec![1]));
+        assert_eq!(super::find(&inputs[..0], &output(0, 0)), None);
+    }
+}
@@ -0,0 +1,217 @@
+/*!
+Knapsack for the smallest change
+
+As the knapsack solver of Bitcoin Core, random subsets of the inputs are tried in passes
+from the largest input: each input is included at random, then the others are tried,
+and whenever the subset covers the goal its last input is taken out again to look for less.
+The subset kept is that of the fewest assets beyond the goal, then of the least lovelace over it,
+so the change locks as little minimum lovelace as possible. Single inputs are tried first.
+*/
+use crate::{
+    rng::Rng,
+    selection::select_fast,
+    strategy::{arrange_selected, Outputs, Selected},
+};
+use std::{cmp::Reverse, collections::BTreeMap};
+use utxo::{ExtOutput, Select};
+
+/// The random subsets tried.
+const KNAPSACK_ATTEMPTS: usize = 500;
+
+type Key = (String, String);
+
+/// What a subset holds, of the assets of the goal and those beyond.
+#[derive(Default)]
+struct Totals<'i> {
+    lovelace: u64,
+    wanted: BTreeMap<&'i Key, u64>,
+    /// The number of the inputs holding each asset beyond the goal.
+    extra: BTreeMap<&'i Key, usize>,
+}
+
+impl<'i> Totals<'i> {
+    fn add<D>(&mut self, input: &'i ExtOutput<D, Key>, goal: &ExtOutput<D, Key>) {
+        self.lovelace = self.lovelace.saturating_add(input.value);
+
+        for (key, quantity) in input.assets.iter().filter(|(_, q)| **q > 0) {
+            if goal.assets.get(key).is_some_and(|q| *q > 0) {
+                let held = self.wanted.entry(key).or_default();
+                *held = held.saturating_add(*quantity);
+            } else {
+                *self.extra.entry(key).or_default() += 1;
+            }
+        }
+    }
+
+    fn remove<D>(&mut self, input: &'i ExtOutput<D, Key>, goal: &ExtOutput<D, Key>) {
+        self.lovelace -= input.value;
+
+        for (key, quantity) in input.assets.iter().filter(|(_, q)| **q > 0) {
+            if goal.assets.get(key).is_some_and(|q| *q > 0) {
+                *self.wanted.get_mut(key).expect("Unreachable") -= quantity;
+            } else {
+                let count = self.extra.get_mut(key).expect("Unreachable");
+                *count -= 1;
+                if *count == 0 {
+                    self.extra.remove(key);
+                }
+            }
+        }
+    }
+
+    fn covers<D>(&self, goal: &ExtOutput<D, Key>) -> bool {
+        self.lovelace >= goal.value
+            && goal
+                .assets
+                .iter()
+                .all(|(key, q)| self.wanted.get(key).copied().unwrap_or(0) >= *q)
+    }
+
+    /// The lower the better, once it covers the goal.
+    fn score<D>(&self, goal: &ExtOutput<D, Key>) -> (usize, u64) {
+        (self.extra.len(), self.lovelace - goal.value)
+    }
+}
+
+/**
+Finds the indices of the inputs covering the goal with the smallest change found.
+
+Returns `None` if the inputs are not enough.
+*/
+pub(crate) fn find<D>(
+    inputs: &[ExtOutput<D, Key>],
+    goal: &ExtOutput<D, Key>,
+    rng: &mut Rng,
+) -> Option<Vec<usize>> {
+    let mut order: Vec<usize> = (0..inputs.len()).collect();
+    order.sort_by_key(|index| Reverse(inputs[*index].value));
+
+    let mut best: Option<((usize, u64), Vec<usize>)> = None;
+    let mut consider = |totals: &Totals, included: &mut dyn Iterator<Item = usize>| {
+        let score = totals.score(goal);
+
+        if best.as_ref().is_none_or(|(best, _)| score < *best) {
+            best = Some((score, included.collect()));
+        }
+    };
+
+    for index in order.iter() {
+        let mut totals = Totals::default();
+        totals.add(&inputs[*index], goal);
+
+        if totals.covers(goal) {
+            consider(&totals, &mut std::iter::once(*index));
+        }
+    }
+
+    for _ in 0..KNAPSACK_ATTEMPTS {
+        let mut included = vec![false; order.len()];
+        let mut totals = Totals::default();
+        let mut reached = false;
+
+        for pass in 0..2 {
+            if reached {
+                break;
+            }
+
+            for (position, index) in order.iter().enumerate() {
+                let take = if pass == 0 {
+                    rng.next_u64() & 1 == 1
+                } else {
+                    !included[position]
+                };
+
+                if !take || included[position] {
+                    continue;
+                }
+
+                totals.add(&inputs[*index], goal);
+                included[position] = true;
+
+                if totals.covers(goal) {
+                    reached = true;
+                    consider(
+                        &totals,
+                        &mut order
+                            .iter()
+                            .zip(included.iter())
+                            .filter(|(_, included)| **included)
+                            .map(|(index, _)| *index),
+                    );
+                    totals.remove(&inputs[*index], goal);
+                    included[position] = false;
+                }
+            }
+        }
+    }
+
+    best.map(|(_, selected)| selected)
+}
+
+/// Selects the inputs of the smallest change found, see [`find`], or as `select_fast`.
+pub(crate) fn select<'i, D: Clone>(
+    inputs: Outputs<'i, D>,
+    output: &ExtOutput<D, Key>,
+    threshold: &ExtOutput<D, Key>,
+    seed: u64,
+) -> Option<Selected<'i, D>> {
+    let goal = output.checked_add(threshold)?;
+
+    match find(inputs, &goal, &mut Rng::new(seed)) {
+        Some(selected) => arrange_selected(inputs, &selected, output),
+        None => select_fast(inputs, output, threshold),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rng::Rng, strategy::knapsack::find};
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    fn output(value: u64, assets: &[(&str, u64)]) -> ExtOutput<(), (String, String)> {
+        let mut output = ExtOutput {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        for (name, quantity) in assets {
+            output.insert_asset(("policy1".into(), name.to_string()), *quantity);
+        }
+        output
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_knapsack() {
+        let inputs = vec![
+            output(9000, &[]),
+            output(4000, &[]),
+            output(3500, &[]),
+            output(2600, &[]),
+            output(6100, &[("junk", 1)]),
+        ];
+        let find = |goal| {
+            find(&inputs, &goal, &mut Rng::new(1)).map(|mut selected| {
+                selected.sort();
+                selected
+            })
+        };
+
+        // 3500 + 2600 with no change beats the single UTxO of junk.
+        assert_eq!(find(output(6100, &[])), Some(vec![2, 3]));
+        assert_eq!(find(output(8500, &[])), Some(vec![0]));
+        assert_eq!(find(output(10100, &[])), Some(vec![1, 2, 3]));
+        assert_eq!(find(output(30000, &[])), None);
+
+        let inputs = vec![output(2000, &[("token", 5)]), output(5000, &[])];
+        assert_eq!(
+            crate::strategy::knapsack::find(
+                &inputs,
+                &output(3000, &[("token", 3)]),
+                &mut Rng::new(1)
+            ),
+            Some(vec![1, 0])
+        );
+    }
+}
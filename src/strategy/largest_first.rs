@@ -0,0 +1,81 @@
+/*!
+Largest-First of CIP-2
+
+Inputs are selected from the largest, so a payment is covered by as few inputs as possible.
+For each asset of the goal, the inputs holding most of it come first,
+then the inputs of most lovelace for what remains.
+*/
+use crate::strategy::{Candidate, SelectionStrategy};
+use std::cmp::Reverse;
+
+pub(crate) struct LargestFirst;
+
+impl SelectionStrategy for LargestFirst {
+    fn order(&self, candidates: &mut [Candidate], goal: &Candidate) {
+        candidates.sort_by_key(|candidate| Reverse(candidate.value));
+
+        // The selected are moved to the front, the largest holders of each asset first.
+        let mut count = 0;
+
+        for (key, quantity) in goal.assets.iter().filter(|(_, q)| **q > 0) {
+            let held = |candidate: &Candidate| candidate.assets.get(key).copied().unwrap_or(0);
+            let mut total = candidates[..count].iter().fold(0u64, |total, candidate| {
+                total.saturating_add(held(candidate))
+            });
+
+            candidates[count..].sort_by_key(|candidate| Reverse(held(candidate)));
+
+            while total < *quantity && count < candidates.len() && held(&candidates[count]) > 0 {
+                total = total.saturating_add(held(&candidates[count]));
+                count += 1;
+            }
+
+            candidates[count..].sort_by_key(|candidate| Reverse(candidate.value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        scenario::{AssetValue, Scenario, Value},
+        strategy::largest_first::LargestFirst,
+    };
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_largest_first() {
+        let token = |lovelace, quantity| Value {
+            lovelace,
+            assets: vec![AssetValue {
+                policy_id: "policy1".into(),
+                asset_name: "aname1".into(),
+                quantity,
+            }],
+        };
+        let scenario = Scenario {
+            inputs: vec![
+                Value::from(3000),
+                Value::from(9000),
+                token(1000, 2),
+                Value::from(5000),
+                token(1000, 8),
+            ],
+            outputs: vec![Value::from(12000)],
+            threshold: Value::from(0),
+            ..Scenario::default()
+        };
+
+        let result = scenario.replay_with(&LargestFirst).unwrap();
+        assert_eq!(result.selected, vec![1, 3]);
+        assert_eq!(result.excess, Value::from(2000));
+
+        let scenario = Scenario {
+            outputs: vec![token(12000, 9)],
+            ..scenario
+        };
+        let result = scenario.replay_with(&LargestFirst).unwrap();
+        assert_eq!(result.selected, vec![4, 2, 1, 3]);
+        assert_eq!(result.excess, token(4000, 1));
+    }
+}
@@ -0,0 +1,369 @@
+/*!
+Selection strategies of Rust embedders
+
+A [`SelectionStrategy`] decides in which order the inputs are selected, when enough are,
+and how the selection is improved afterwards. Once registered by name with
+[`register_strategy`], `select` runs it for the `strategy` option of the name,
+still within the options: unspendable inputs are excluded, required assets and
+previous inputs are kept, and the selection must cover the outputs plus threshold,
+whatever the strategy returns.
+
+```
+use cardano_utxo_wasm::strategy::{register_strategy, Candidate, SelectionStrategy};
+
+struct SmallestFirst;
+
+impl SelectionStrategy for SmallestFirst {
+    fn order(&self, candidates: &mut [Candidate], _goal: &Candidate) {
+        candidates.sort_by_key(|candidate| candidate.value);
+    }
+}
+
+register_strategy("smallestFirst", SmallestFirst);
+```
+*/
+pub(crate) mod branch_and_bound;
+pub(crate) mod dust;
+pub(crate) mod fewest_inputs;
+pub(crate) mod knapsack;
+pub(crate) mod largest_first;
+pub(crate) mod oldest_first;
+pub(crate) mod random_improve;
+pub(crate) mod score;
+
+use crate::{
+    scenario::{Scenario, ScenarioResult},
+    selection::{Outputs, Selected},
+};
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+use utxo::{try_sum, ExtOutput, Select};
+
+/// An input to select, its data is its index in the inputs.
+pub type Candidate = ExtOutput<usize, (String, String)>;
+
+/// How inputs are selected, see the [module](self).
+pub trait SelectionStrategy {
+    /**
+    Orders the candidates, those first are selected first. Keeps the order by default.
+
+    The order is discarded if any candidate is changed, lost or duplicated.
+    */
+    fn order(&self, candidates: &mut [Candidate], goal: &Candidate) {
+        let _ = (candidates, goal);
+    }
+
+    /**
+    Whether the selected candidates of the total are enough for the goal.
+
+    Only asked once the total covers the goal, so accepts right away by default.
+    */
+    fn accept(&self, selected: &[Candidate], total: &Candidate, goal: &Candidate) -> bool {
+        let _ = (selected, total, goal);
+        true
+    }
+
+    /**
+    Improves the accepted selection by moving candidates between the selected and unselected.
+
+    The improvement is discarded if the selected no longer cover the goal,
+    or any candidate is changed, lost or duplicated. Does nothing by default.
+    */
+    fn improve(
+        &self,
+        selected: &mut Vec<Candidate>,
+        unselected: &mut Vec<Candidate>,
+        goal: &Candidate,
+    ) {
+        let _ = (selected, unselected, goal);
+    }
+}
+
+/// Selects the inputs in the order they are, for those sorted before selection.
+pub(crate) struct InOrder;
+
+impl SelectionStrategy for InOrder {}
+
+thread_local! {
+    static STRATEGIES: RefCell<BTreeMap<String, Rc<dyn SelectionStrategy>>> =
+        RefCell::new(BTreeMap::new());
+}
+
+/**
+Registers the strategy of the name, replacing any registered before.
+
+The names of the strategies built in, e.g. `default` and `auto`, are never looked up here.
+*/
+pub fn register_strategy(name: impl Into<String>, strategy: impl SelectionStrategy + 'static) {
+    STRATEGIES.with(|strategies| {
+        strategies
+            .borrow_mut()
+            .insert(name.into(), Rc::new(strategy))
+    });
+}
+
+/// Unregisters the strategy of the name, returning whether it was registered.
+pub fn unregister_strategy(name: &str) -> bool {
+    STRATEGIES.with(|strategies| strategies.borrow_mut().remove(name).is_some())
+}
+
+pub(crate) fn registered(name: &str) -> Option<Rc<dyn SelectionStrategy>> {
+    STRATEGIES.with(|strategies| strategies.borrow().get(name).cloned())
+}
+
+fn candidate<D>(output: &ExtOutput<D, (String, String)>, index: Option<usize>) -> Candidate {
+    ExtOutput {
+        value: output.value,
+        assets: output.assets.clone(),
+        data: index,
+    }
+}
+
+/// Whether the candidates are the originals reordered, each once and unchanged.
+fn is_permutation<'c>(
+    candidates: impl IntoIterator<Item = &'c Candidate>,
+    originals: &[Candidate],
+) -> bool {
+    let mut seen = vec![false; originals.len()];
+    let mut count = 0;
+
+    candidates.into_iter().all(|candidate| {
+        count += 1;
+        candidate.data.is_some_and(|index| {
+            originals.get(index) == Some(candidate) && !std::mem::replace(&mut seen[index], true)
+        })
+    }) && count == originals.len()
+}
+
+/**
+Selects for the output plus threshold by the strategy, as `utxo::select` returns.
+
+The inputs are reordered so the selected come first.
+Returns `None` if the inputs are not enough, or the strategy never accepts.
+*/
+pub(crate) fn select_by<'i, D: Clone>(
+    strategy: &dyn SelectionStrategy,
+    inputs: Outputs<'i, D>,
+    output: &ExtOutput<D, (String, String)>,
+    threshold: &ExtOutput<D, (String, String)>,
+) -> Option<Selected<'i, D>> {
+    let goal = candidate(&output.checked_add(threshold)?, None);
+    let originals: Vec<Candidate> = inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| candidate(input, Some(index)))
+        .collect();
+    let mut candidates = originals.clone();
+
+    strategy.order(&mut candidates, &goal);
+
+    if !is_permutation(&candidates, &originals) {
+        candidates.clone_from(&originals);
+    }
+
+    let mut total = Candidate::zero();
+    let mut count = 0;
+
+    while count == 0
+        || total.checked_sub(&goal).is_none()
+        || !strategy.accept(&candidates[..count], &total, &goal)
+    {
+        total = total.checked_add(candidates.get(count)?)?;
+        count += 1;
+    }
+
+    let mut unselected = candidates.split_off(count);
+    let mut selected = candidates;
+    let accepted = (selected.clone(), unselected.clone());
+
+    strategy.improve(&mut selected, &mut unselected, &goal);
+
+    let intact = is_permutation(selected.iter().chain(unselected.iter()), &originals);
+    let covers = try_sum(&selected).is_some_and(|total| total.checked_sub(&goal).is_some());
+
+    if selected.is_empty() || !intact || !covers {
+        (selected, unselected) = accepted;
+    }
+
+    let order: Vec<usize> = selected
+        .iter()
+        .chain(unselected.iter())
+        .map(|candidate| candidate.data.expect("Unreachable"))
+        .collect();
+
+    arrange(inputs, &order, selected.len(), output)
+}
+
+/// Reorders the inputs so those of the indices are first, returning them as selected for the output.
+pub(crate) fn arrange_selected<'i, D: Clone>(
+    inputs: Outputs<'i, D>,
+    selected: &[usize],
+    output: &ExtOutput<D, (String, String)>,
+) -> Option<Selected<'i, D>> {
+    let mut is_selected = vec![false; inputs.len()];
+    selected.iter().for_each(|index| is_selected[*index] = true);
+    let mut order = selected.to_vec();
+    order.extend((0..inputs.len()).filter(|index| !is_selected[*index]));
+
+    arrange(inputs, &order, selected.len(), output)
+}
+
+/**
+Reorders the inputs by the indices, returning the first `count` as selected for the output.
+
+The indices must be those of all the inputs, each once.
+*/
+pub(crate) fn arrange<'i, D: Clone>(
+    inputs: Outputs<'i, D>,
+    order: &[usize],
+    count: usize,
+    output: &ExtOutput<D, (String, String)>,
+) -> Option<Selected<'i, D>> {
+    let reordered: Vec<ExtOutput<D, (String, String)>> =
+        order.iter().map(|index| inputs[*index].clone()).collect();
+    inputs.clone_from_slice(&reordered);
+
+    let (selected, unselected) = inputs.split_at_mut(count);
+    let excess = try_sum(selected)?.checked_sub(output)?;
+
+    Some((selected, unselected, excess))
+}
+
+impl Scenario {
+    /**
+    Replays the selection with the strategy instead of the algorithm of `select`.
+
+    Returns `None` if the inputs are not enough for the outputs plus threshold,
+    the strategy never accepts, or the outputs overflowed.
+    */
+    pub fn replay_with(&self, strategy: &dyn SelectionStrategy) -> Option<ScenarioResult> {
+        let (mut inputs, total_output, threshold) = self.to_ext_outputs()?;
+
+        select_by(strategy, &mut inputs[..], &total_output, &threshold).map(
+            |(selected, unselected, excess)| ScenarioResult {
+                selected: selected.iter().filter_map(|i| i.data).collect(),
+                unselected: unselected.iter().filter_map(|i| i.data).collect(),
+                excess: (&excess).into(),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        options::{SelectOptions, Strategy},
+        scenario::{Scenario, Value},
+        selection::select,
+        strategy::{register_strategy, unregister_strategy, Candidate, SelectionStrategy},
+        Output,
+    };
+    use utxo::Select;
+    use wasm_bindgen_test::*;
+
+    struct SmallestFirst;
+
+    impl SelectionStrategy for SmallestFirst {
+        fn order(&self, candidates: &mut [Candidate], _goal: &Candidate) {
+            candidates.sort_by_key(|candidate| candidate.value);
+        }
+    }
+
+    /// Keeps selecting until the total is twice the goal.
+    struct Double;
+
+    impl SelectionStrategy for Double {
+        fn accept(&self, _selected: &[Candidate], total: &Candidate, goal: &Candidate) -> bool {
+            total.value >= goal.value * 2
+        }
+    }
+
+    /// Drops all the selected, which is never valid.
+    struct Discard;
+
+    impl SelectionStrategy for Discard {
+        fn improve(
+            &self,
+            selected: &mut Vec<Candidate>,
+            _unselected: &mut Vec<Candidate>,
+            _goal: &Candidate,
+        ) {
+            selected.clear();
+        }
+    }
+
+    /// Changes the candidates rather than only reordering them, which is never valid.
+    struct Tamper;
+
+    impl SelectionStrategy for Tamper {
+        fn order(&self, candidates: &mut [Candidate], _goal: &Candidate) {
+            candidates[1] = candidates[0].clone();
+            candidates[2].value = u32::MAX.into();
+        }
+
+        fn improve(
+            &self,
+            selected: &mut Vec<Candidate>,
+            _unselected: &mut Vec<Candidate>,
+            _goal: &Candidate,
+        ) {
+            selected[0].data = None;
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_select_by() {
+        let scenario = Scenario {
+            inputs: [3000, 1000, 7000, 2000].map(Value::from).to_vec(),
+            outputs: vec![Value::from(2500)],
+            threshold: Value::from(500),
+            ..Scenario::default()
+        };
+
+        let result = scenario.replay_with(&SmallestFirst).unwrap();
+        assert_eq!(result.selected, vec![1, 3]);
+        assert_eq!(result.unselected, vec![0, 2]);
+        assert_eq!(result.excess, Value::from(500));
+
+        let result = scenario.replay_with(&Double).unwrap();
+        assert_eq!(result.selected, vec![0, 1, 2]);
+
+        let result = scenario.replay_with(&Discard).unwrap();
+        assert_eq!(result.selected, vec![0]);
+        assert_eq!(result.unselected, vec![1, 2, 3]);
+
+        let result = scenario.replay_with(&Tamper).unwrap();
+        assert_eq!(result.selected, vec![0]);
+        assert_eq!(result.unselected, vec![1, 2, 3]);
+        assert_eq!(result.excess, Value::from(500));
+
+        let scenario = Scenario {
+            outputs: vec![Value::from(20000)],
+            ..scenario
+        };
+        assert_eq!(scenario.replay_with(&SmallestFirst), None);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_registered() {
+        let inputs: Vec<Output> = [3000, 1000, 7000]
+            .map(|value| Output {
+                value,
+                ..Output::zero()
+            })
+            .to_vec();
+        let options = SelectOptions {
+            strategy: Strategy::Registered("smallestFirst".into()),
+            ..SelectOptions::default()
+        };
+        let select = || select(inputs.clone(), &inputs[1], &Output::zero(), &options);
+
+        assert!(select().is_none());
+
+        register_strategy("smallestFirst", SmallestFirst);
+        assert_eq!(select().unwrap().selected.len(), 1);
+        assert_eq!(select().unwrap().selected[0].value, 1000);
+
+        assert!(unregister_strategy("smallestFirst"));
+        assert!(!unregister_strategy("smallestFirst"));
+    }
+}
@@ -0,0 +1,61 @@
+/*!
+Oldest first
+
+With the `oldestFirst` strategy, the inputs are selected from the earliest `slot` of the outputs,
+the slot or block height each was created at, as the hot wallets of exchanges commonly spend.
+Inputs without a `slot` are selected last, and those of the same in the order passed.
+*/
+use crate::{get_amount, ErrorCode, Output};
+
+/// Sorts the inputs by their `slot`, from the earliest, those without last.
+pub(crate) fn sort(inputs: &mut Vec<Output>) -> Result<(), ErrorCode> {
+    let slots = inputs
+        .iter()
+        .map(|input| match input.data {
+            Some(data) => get_amount(data, "slot"),
+            None => Ok(None),
+        })
+        .collect::<Result<Vec<Option<u64>>, ErrorCode>>()?;
+
+    let mut order: Vec<usize> = (0..inputs.len()).collect();
+    order.sort_by_key(|index| slots[*index].unwrap_or(u64::MAX));
+    *inputs = order
+        .into_iter()
+        .map(|index| inputs[index].clone())
+        .collect();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{strategy::oldest_first::sort, to_outputs, JsOutput, Output};
+    use js_sys::Reflect;
+    use std::collections::BTreeMap;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_oldest_first() {
+        let js_inputs: Vec<JsOutput> = [(1000, Some(500u64)), (2000, None), (3000, Some(100))]
+            .into_iter()
+            .map(|(value, slot)| {
+                let output: JsOutput = Output {
+                    value,
+                    assets: BTreeMap::new(),
+                    data: None,
+                }
+                .into();
+                if let Some(slot) = slot {
+                    Reflect::set(&output, &"slot".into(), &JsValue::from(slot)).unwrap();
+                }
+                output
+            })
+            .collect();
+        let mut inputs = to_outputs(&js_inputs).unwrap();
+
+        sort(&mut inputs).unwrap();
+        let lovelaces: Vec<u64> = inputs.iter().map(|input| input.value).collect();
+        assert_eq!(lovelaces, vec![3000, 1000, 2000]);
+    }
+}
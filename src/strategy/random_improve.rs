@@ -0,0 +1,178 @@
+/*!
+Random-Improve of CIP-2
+
+Inputs are selected at random until they cover the goal: for each of its assets
+from the inputs holding the asset, then for its lovelace from any input.
+The selection is then improved with more random inputs as long as they bring
+the lovelace closer to twice that of the goal without exceeding three times,
+so the change is about the size of the payment and the UTxOs spread over time.
+Inputs bringing assets not in the goal are never added to improve.
+*/
+use crate::{
+    rng::Rng,
+    strategy::{Candidate, SelectionStrategy},
+};
+use std::cell::RefCell;
+
+pub(crate) struct RandomImprove {
+    rng: RefCell<Rng>,
+}
+
+impl RandomImprove {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self {
+            rng: RefCell::new(Rng::new(seed)),
+        }
+    }
+}
+
+impl SelectionStrategy for RandomImprove {
+    fn order(&self, candidates: &mut [Candidate], goal: &Candidate) {
+        self.rng.borrow_mut().shuffle(candidates);
+
+        // The selected are moved to the front in the order they are selected.
+        let mut count = 0;
+
+        for (key, quantity) in goal.assets.iter().filter(|(_, q)| **q > 0) {
+            let mut total: u64 = candidates[..count]
+                .iter()
+                .filter_map(|candidate| candidate.assets.get(key).copied())
+                .fold(0u64, u64::saturating_add);
+            let mut index = count;
+
+            while total < *quantity && index < candidates.len() {
+                match candidates[index].assets.get(key).copied() {
+                    Some(held) if held > 0 => {
+                        total = total.saturating_add(held);
+                        candidates[count..=index].rotate_right(1);
+                        count += 1;
+                    }
+                    _ => {}
+                }
+                index += 1;
+            }
+        }
+
+        let mut total: u64 = candidates[..count]
+            .iter()
+            .map(|c| c.value)
+            .fold(0u64, u64::saturating_add);
+        let mut index = count;
+
+        while total < goal.value && index < candidates.len() {
+            total = total.saturating_add(candidates[index].value);
+            candidates[count..=index].rotate_right(1);
+            count += 1;
+            index += 1;
+        }
+    }
+
+    fn improve(
+        &self,
+        selected: &mut Vec<Candidate>,
+        unselected: &mut Vec<Candidate>,
+        goal: &Candidate,
+    ) {
+        let ideal = goal.value.saturating_mul(2);
+        let max = goal.value.saturating_mul(3);
+        let mut total: u64 = selected
+            .iter()
+            .map(|c| c.value)
+            .fold(0u64, u64::saturating_add);
+        let distance = |total: u64| total.abs_diff(ideal);
+        let mut rest = Vec::with_capacity(unselected.len());
+
+        for candidate in unselected.drain(..) {
+            let improved = total.saturating_add(candidate.value);
+            let no_new_assets = candidate
+                .assets
+                .iter()
+                .all(|(key, q)| *q == 0 || goal.assets.get(key).is_some_and(|q| *q > 0));
+
+            if no_new_assets && improved <= max && distance(improved) < distance(total) {
+                total = improved;
+                selected.push(candidate);
+            } else {
+                rest.push(candidate);
+            }
+        }
+
+        *unselected = rest;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        scenario::{AssetValue, Scenario, Value},
+        strategy::random_improve::RandomImprove,
+    };
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_random_improve() {
+        let token = |lovelace, quantity| Value {
+            lovelace,
+            assets: vec![AssetValue {
+                policy_id: "policy1".into(),
+                asset_name: "aname1".into(),
+                quantity,
+            }],
+        };
+        let mut inputs: Vec<Value> = (1..=20).map(|i| Value::from(i * 1000)).collect();
+        inputs.push(token(1000, 10));
+        let scenario = Scenario {
+            inputs,
+            outputs: vec![token(10000, 5)],
+            threshold: Value::from(0),
+            ..Scenario::default()
+        };
+
+        let result = scenario.replay_with(&RandomImprove::new(1)).unwrap();
+        assert!(result.selected.contains(&20));
+        assert_eq!(result.excess.assets[0].quantity, 5);
+
+        let selected: u64 = result
+            .selected
+            .iter()
+            .map(|i| scenario.inputs[*i].lovelace)
+            .sum();
+        assert!((10000..=30000).contains(&selected));
+
+        // The same seed selects the same, another most likely not.
+        assert_eq!(
+            scenario.replay_with(&RandomImprove::new(1)).as_ref(),
+            Some(&result)
+        );
+        let others = (2..10)
+            .filter_map(|seed| scenario.replay_with(&RandomImprove::new(seed)))
+            .filter(|other| other.selected != result.selected)
+            .count();
+        assert!(others > 0);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_random_improve_saturates() {
+        let tokens = |lovelace, quantities: [u64; 2]| Value {
+            lovelace,
+            assets: quantities
+                .iter()
+                .zip(["aname1", "aname2"])
+                .map(|(quantity, name)| AssetValue {
+                    policy_id: "policy1".into(),
+                    asset_name: name.into(),
+                    quantity: *quantity,
+                })
+                .collect(),
+        };
+        let scenario = Scenario {
+            inputs: vec![tokens(u64::MAX, [1, u64::MAX]); 2],
+            outputs: vec![tokens(1000, [2, 1])],
+            threshold: Value::from(0),
+            ..Scenario::default()
+        };
+
+        let result = scenario.replay_with(&RandomImprove::new(1));
+        assert!(result.is_none());
+    }
+}
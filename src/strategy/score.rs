@@ -0,0 +1,75 @@
+/*!
+Order by the scores of a callback
+
+With the `score` option, each input is scored once by the JS callback of the option,
+and the inputs are selected from the highest score, those of equal scores in the order passed.
+This lets dApps prefer their own inputs, e.g. those at an address, without a strategy in Rust.
+*/
+use crate::{ErrorCode, Output};
+use js_sys::Function;
+use wasm_bindgen::JsValue;
+
+/**
+Sorts the inputs by the scores of the callback, from the highest.
+
+Fails if the callback throws, or returns anything but a finite number for an input.
+*/
+pub(crate) fn sort(inputs: &mut Vec<Output>, score: &Function) -> Result<(), ErrorCode> {
+    let scores = inputs
+        .iter()
+        .map(|input| {
+            let data = input.data.ok_or(ErrorCode::InvalidType)?;
+            score
+                .call1(&JsValue::UNDEFINED, data)
+                .ok()
+                .and_then(|score| score.as_f64())
+                .filter(|score| score.is_finite())
+                .ok_or(ErrorCode::InvalidType)
+        })
+        .collect::<Result<Vec<f64>, ErrorCode>>()?;
+
+    let mut order: Vec<usize> = (0..inputs.len()).collect();
+    order.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+    *inputs = order
+        .into_iter()
+        .map(|index| inputs[index].clone())
+        .collect();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{strategy::score::sort, to_outputs, JsOutput, Output};
+    use js_sys::Function;
+    use std::collections::BTreeMap;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_sort_by_score() {
+        let js_inputs: Vec<JsOutput> = [3000, 9000, 1000, 5000]
+            .into_iter()
+            .map(|value| {
+                Output {
+                    value,
+                    assets: BTreeMap::new(),
+                    data: None,
+                }
+                .into()
+            })
+            .collect();
+        let mut inputs = to_outputs(&js_inputs).unwrap();
+        let lovelaces = |inputs: &[Output]| inputs.iter().map(|i| i.value).collect::<Vec<u64>>();
+
+        // The smallest first, with 3000 and 1000 of the same score in the order passed.
+        let smallest =
+            Function::new_with_args("output", "return Math.min(-3000, -Number(output.lovelace))");
+        sort(&mut inputs, &smallest).unwrap();
+        assert_eq!(lovelaces(&inputs), vec![3000, 1000, 5000, 9000]);
+
+        let invalid = Function::new_with_args("output", "return 'high'");
+        assert!(sort(&mut inputs, &invalid).is_err());
+        let throwing = Function::new_with_args("output", "throw new Error('no score')");
+        assert!(sort(&mut inputs, &throwing).is_err());
+    }
+}
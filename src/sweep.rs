@@ -0,0 +1,159 @@
+/*!
+Sweep of a wallet
+
+Migrating a wallet or consolidating all its UTxOs spends every one of them to a single output,
+so there is nothing to select: the output is the sum of the UTxOs less the fee of the transaction.
+*/
+use crate::{
+    collect_js_outputs,
+    fee::{JsFeeParams, LinearFee, TxShape},
+    to_outputs, ErrorCode, JsOutput, JsOutputArray,
+};
+use js_sys::{Array, Object, Reflect};
+use utxo::{try_sum, ExtOutput};
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type SweepResult = {
+  inputs: Array<Output>
+  output: Output
+  fee?: bigint
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SweepResult")]
+    pub type SweepResult;
+}
+
+/// The output of a sweep and the fee, if any.
+type Swept<D> = (ExtOutput<D, (String, String)>, Option<u64>);
+
+/**
+Sums the inputs to one output, less the fee of spending them all to it if the fee is given.
+
+Returns the output and the fee, an error if the sum overflowed or is less than the fee.
+*/
+pub(crate) fn sweep_inputs<D>(
+    inputs: &[ExtOutput<D, (String, String)>],
+    fee: Option<&LinearFee>,
+) -> Result<Swept<D>, ErrorCode> {
+    let total = try_sum(inputs).ok_or(ErrorCode::OutputsOverflowed)?;
+    let Some(fee) = fee else {
+        return Ok((total, None));
+    };
+
+    let shape = TxShape {
+        inputs: inputs.len() as u64,
+        outputs: 1,
+        assets: total.assets.values().filter(|q| **q > 0).count() as u64,
+        witnesses: inputs.len() as u64,
+    };
+    let fee = fee.fee(shape.size()).ok_or(ErrorCode::OutputsOverflowed)?;
+    let output = ExtOutput {
+        value: total
+            .value
+            .checked_sub(fee)
+            .ok_or(ErrorCode::InsufficientInputs)?,
+        assets: total.assets,
+        data: None,
+    };
+
+    Ok((output, Some(fee)))
+}
+
+/**
+Sweep all the UTxOs to one output
+
+Selects every UTxO of the inputs, for wallet migration and consolidation, and returns them
+as `inputs` with the single `output` holding all their lovelace and assets.
+When the fee parameters are given, the fee of the transaction spending them to the output
+is estimated from its size and deducted from the output, and returned as `fee`.
+The output has no address, to be set to the destination.
+
+Raises errors when the types used are wrong, there is any value overflowed,
+or the UTxOs are not enough for the fee.
+*/
+#[wasm_bindgen]
+pub fn sweep(
+    inputs: &JsOutputArray,
+    fee_params: Option<JsFeeParams>,
+) -> Result<SweepResult, JsError> {
+    let js_inputs = collect_js_outputs(inputs)?;
+    let outputs = to_outputs(&js_inputs)?;
+    let fee = fee_params.as_ref().map(LinearFee::try_from).transpose()?;
+    let (output, fee) = sweep_inputs(&outputs, fee.as_ref())?;
+
+    let selected = Array::new();
+    for input in js_inputs.iter() {
+        selected.push(input);
+    }
+
+    let output: JsOutput = output.into();
+    let result = Object::new();
+    Reflect::set(&result, &"inputs".into(), &selected).expect("Unreachable");
+    Reflect::set(&result, &"output".into(), &output).expect("Unreachable");
+
+    if let Some(fee) = fee {
+        Reflect::set(&result, &"fee".into(), &fee.into()).expect("Unreachable");
+    }
+
+    Ok(result.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fee::{LinearFee, TxShape},
+        sweep::sweep_inputs,
+        ErrorCode,
+    };
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    fn output(value: u64, quantity: u64) -> ExtOutput<(), (String, String)> {
+        let mut output = ExtOutput {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        output.insert_asset(("policy1".into(), "aname1".into()), quantity);
+        output
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_sweep_inputs() {
+        let inputs = vec![
+            output(2_000_000, 5),
+            output(3_000_000, 0),
+            output(1_500_000, 7),
+        ];
+        let fee = LinearFee {
+            coefficient: 44,
+            constant: 155381,
+        };
+
+        let (total, none) = sweep_inputs(&inputs, None).unwrap();
+        assert_eq!(total, output(6_500_000, 12));
+        assert_eq!(none, None);
+
+        let shape = TxShape {
+            inputs: 3,
+            outputs: 1,
+            assets: 1,
+            witnesses: 3,
+        };
+        let expected = fee.fee(shape.size()).unwrap();
+        let (swept, paid) = sweep_inputs(&inputs, Some(&fee)).unwrap();
+        assert_eq!(paid, Some(expected));
+        assert_eq!(swept, output(6_500_000 - expected, 12));
+
+        assert_eq!(
+            sweep_inputs(&[output(100_000, 1)], Some(&fee)),
+            Err(ErrorCode::InsufficientInputs)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+/*!
+Totals of values in 128 bits
+
+The sum of many UTxOs may be beyond 64 bits, e.g. for a token of quantities near the maximum,
+while what is left of it after the outputs is not. Totals are accumulated in 128 bits,
+and only the values returned are checked to fit in 64.
+*/
+use crate::ErrorCode;
+use std::collections::BTreeMap;
+use utxo::ExtOutput;
+
+pub(crate) struct Total<K> {
+    pub(crate) lovelace: u128,
+    pub(crate) assets: BTreeMap<K, u128>,
+}
+
+impl<K: Ord + Clone> Total<K> {
+    /// The total of the outputs, which cannot overflow for fewer than 2^64 of them.
+    pub(crate) fn of<D>(outputs: &[ExtOutput<D, K>]) -> Self {
+        let mut total = Self {
+            lovelace: 0,
+            assets: BTreeMap::new(),
+        };
+
+        for output in outputs {
+            total.add(output);
+        }
+
+        total
+    }
+
+    pub(crate) fn add<D>(&mut self, output: &ExtOutput<D, K>) {
+        self.lovelace += u128::from(output.value);
+        for (key, quantity) in output.assets.iter() {
+            *self.assets.entry(key.clone()).or_default() += u128::from(*quantity);
+        }
+    }
+
+    /// The total less the output, `OutputsOverflowed` if it does not fit in 64 bits.
+    fn less<D>(&self, output: &ExtOutput<D, K>) -> Result<ExtOutput<D, K>, ErrorCode> {
+        let fit =
+            |quantity: u128| u64::try_from(quantity).map_err(|_| ErrorCode::OutputsOverflowed);
+        let mut result = ExtOutput {
+            value: fit(self.lovelace.saturating_sub(u128::from(output.value)))?,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+
+        for (key, quantity) in self.assets.iter() {
+            let taken = output.assets.get(key).copied().unwrap_or(0);
+            result.insert_asset(
+                key.clone(),
+                fit(quantity.saturating_sub(u128::from(taken)))?,
+            );
+        }
+
+        Ok(result)
+    }
+
+    /// The total as an output, `OutputsOverflowed` if any value is beyond 64 bits.
+    pub(crate) fn to_output<D>(&self) -> Result<ExtOutput<D, K>, ErrorCode> {
+        self.less(&ExtOutput {
+            value: 0,
+            assets: BTreeMap::new(),
+            data: None,
+        })
+    }
+
+    /**
+    What the total holds beyond the output.
+
+    Returns `InsufficientInputs` if the total is less than the output for the lovelace
+    or any asset, and `OutputsOverflowed` if the excess is beyond 64 bits.
+    */
+    pub(crate) fn excess<D>(&self, output: &ExtOutput<D, K>) -> Result<ExtOutput<D, K>, ErrorCode> {
+        let held = |key: &K| self.assets.get(key).copied().unwrap_or(0);
+        let covers = self.lovelace >= u128::from(output.value)
+            && output
+                .assets
+                .iter()
+                .all(|(key, quantity)| held(key) >= u128::from(*quantity));
+
+        if !covers {
+            return Err(ErrorCode::InsufficientInputs);
+        }
+
+        self.less(output)
+    }
+
+    /// What the total lacks of the goal, the lovelace and each asset it does not cover.
+    pub(crate) fn shortfall<D>(&self, goal: &ExtOutput<D, K>) -> ExtOutput<D, K> {
+        let lacks = |total: u128, quantity: u64| u128::from(quantity).saturating_sub(total) as u64;
+        let mut result = ExtOutput {
+            value: lacks(self.lovelace, goal.value),
+            assets: BTreeMap::new(),
+            data: None,
+        };
+
+        for (key, quantity) in goal.assets.iter() {
+            let held = self.assets.get(key).copied().unwrap_or(0);
+            result.insert_asset(key.clone(), lacks(held, *quantity));
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{total::Total, ErrorCode};
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen_test::*;
+
+    fn value(value: u64, quantity: u64) -> ExtOutput<(), String> {
+        let mut output = ExtOutput {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        output.insert_asset("token".into(), quantity);
+        output
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_total() {
+        let inputs = [value(10, u64::MAX), value(20, u64::MAX)];
+        let total = Total::of(&inputs);
+        assert_eq!(total.assets["token"], 2 * u128::from(u64::MAX));
+        assert_eq!(total.to_output::<()>(), Err(ErrorCode::OutputsOverflowed));
+
+        // The sum is beyond 64 bits, what is left after the output is not.
+        assert_eq!(total.excess(&value(5, u64::MAX)), Ok(value(25, u64::MAX)));
+        assert_eq!(
+            total.excess(&value(5, 1)),
+            Err(ErrorCode::OutputsOverflowed)
+        );
+        assert_eq!(
+            total.excess(&value(31, 0)),
+            Err(ErrorCode::InsufficientInputs)
+        );
+
+        assert_eq!(total.shortfall(&value(40, u64::MAX)), value(10, 0));
+        assert_eq!(Total::of(&inputs[..1]).to_output(), Ok(value(10, u64::MAX)));
+    }
+}
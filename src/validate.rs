@@ -0,0 +1,283 @@
+/*!
+Validation of outputs
+
+The selection fails on the first invalid field of the outputs it is given. To validate
+the input of a user before, e.g. a form of a payment, all the problems of an output are listed
+at once, including those the selection tolerates, such as an asset appearing twice
+or an amount given as a `number` above `Number.MAX_SAFE_INTEGER` out of the strict BigInt mode.
+The inputs repeating an `OutPoint` are not outputs of issues but checked by the selection.
+*/
+use crate::{get_field, iterate, to_amount, ErrorCode, ErrorKind};
+use js_sys::{Array, Object, Reflect};
+use std::collections::BTreeMap;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type OutputIssue = {
+  field: string
+  code: ErrorKind
+  message: string
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Array<OutputIssue>")]
+    pub type JsOutputIssueArray;
+}
+
+/// The hex digits of a policy ID, the hash of 28 bytes.
+const POLICY_ID_LENGTH: usize = 56;
+
+/// The hex digits of the longest asset name, of 32 bytes.
+const MAX_ASSET_NAME_LENGTH: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Issue {
+    pub(crate) field: String,
+    pub(crate) kind: ErrorKind,
+    pub(crate) message: String,
+}
+
+impl Issue {
+    fn new(field: &str, kind: ErrorKind, message: &str) -> Self {
+        Self {
+            field: field.into(),
+            kind,
+            message: message.into(),
+        }
+    }
+
+    fn to_js(&self) -> JsValue {
+        let result = Object::new();
+        Reflect::set(&result, &"field".into(), &self.field.as_str().into()).expect("Unreachable");
+        Reflect::set(&result, &"code".into(), &self.kind.code().into()).expect("Unreachable");
+        Reflect::set(&result, &"message".into(), &self.message.as_str().into())
+            .expect("Unreachable");
+        result.into()
+    }
+}
+
+fn is_hex(text: &str) -> bool {
+    text.len().is_multiple_of(2) && text.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/// The issue of a lovelace or quantity, of the kind given unless it is a number above the safe,
+/// whether the strict BigInt mode is enabled or not.
+fn amount_issue(value: &JsValue, field: &str, kind: ErrorKind) -> Option<Issue> {
+    const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+    let unsafe_number = || {
+        Some(Issue::new(
+            field,
+            ErrorKind::UnsafeNumber,
+            ErrorCode::UnsafeNumber.message(),
+        ))
+    };
+
+    match to_amount(value) {
+        Ok(_)
+            if value
+                .as_f64()
+                .is_some_and(|number| number > MAX_SAFE_INTEGER) =>
+        {
+            unsafe_number()
+        }
+        Ok(_) => None,
+        Err(ErrorCode::UnsafeNumber) => unsafe_number(),
+        Err(code) => Some(Issue::new(field, kind, code.message())),
+    }
+}
+
+/// The issues of an asset at the field, e.g. `assets[0]`.
+fn asset_issues(asset: &JsValue, field: &str) -> Vec<Issue> {
+    if !asset.is_object() {
+        return vec![Issue::new(
+            field,
+            ErrorKind::BadAsset,
+            "Asset should be an object",
+        )];
+    }
+
+    let string = |key: &str| {
+        get_field(asset, key)
+            .ok()
+            .and_then(|value| value.as_string())
+    };
+    let mut issues = Vec::new();
+
+    match string("policyId") {
+        Some(policy_id) if policy_id.len() == POLICY_ID_LENGTH && is_hex(&policy_id) => {}
+        _ => issues.push(Issue::new(
+            &format!("{field}.policyId"),
+            ErrorKind::BadAsset,
+            "Policy ID should be 56 hex digits",
+        )),
+    }
+
+    match string("assetName") {
+        Some(name) if name.len() <= MAX_ASSET_NAME_LENGTH && is_hex(&name) => {}
+        _ => issues.push(Issue::new(
+            &format!("{field}.assetName"),
+            ErrorKind::BadAsset,
+            "Asset name should be hex of at most 32 bytes",
+        )),
+    }
+
+    let quantity = get_field(asset, "quantity").unwrap_or_default();
+    issues.extend(amount_issue(
+        &quantity,
+        &format!("{field}.quantity"),
+        ErrorKind::BadAsset,
+    ));
+
+    issues
+}
+
+/// All the issues of an output, none if it is valid.
+pub(crate) fn output_issues(output: &JsValue) -> Vec<Issue> {
+    if !output.is_object() {
+        return vec![Issue::new(
+            "",
+            ErrorKind::BadType,
+            "Output should be an object",
+        )];
+    }
+
+    let lovelace = get_field(output, "lovelace").unwrap_or_default();
+    let mut issues: Vec<Issue> = amount_issue(&lovelace, "lovelace", ErrorKind::BadAmount)
+        .into_iter()
+        .collect();
+    let assets = get_field(output, "assets").unwrap_or_default();
+
+    if assets.is_undefined() || assets.is_null() {
+        return issues;
+    }
+
+    let Ok(assets) = iterate(&assets) else {
+        issues.push(Issue::new(
+            "assets",
+            ErrorKind::BadType,
+            "Assets should be iterable",
+        ));
+        return issues;
+    };
+    let mut seen: BTreeMap<(String, String), usize> = BTreeMap::new();
+
+    for (index, asset) in assets.iter().enumerate() {
+        let field = format!("assets[{index}]");
+        issues.extend(asset_issues(asset, &field));
+
+        let string = |key: &str| {
+            get_field(asset, key)
+                .ok()
+                .and_then(|value| value.as_string())
+        };
+        if let (Some(policy_id), Some(asset_name)) = (string("policyId"), string("assetName")) {
+            let first = *seen.entry((policy_id, asset_name)).or_insert(index);
+
+            if first != index {
+                issues.push(Issue::new(
+                    &field,
+                    ErrorKind::BadAsset,
+                    &format!("Asset at {field} duplicates assets[{first}]"),
+                ));
+            }
+        }
+    }
+
+    issues
+}
+
+/**
+Validate an output
+
+Returns the issues of the output, each the `field` at fault, a `code` and a `message`,
+or none if it is valid, e.g. to check the input of a user before selection.
+The `lovelace` and each `quantity` must be non-negative integers within 64 bits,
+each policy ID 56 hex digits and each asset name hex of at most 32 bytes,
+and no asset may appear twice. A `number` above `Number.MAX_SAFE_INTEGER`
+is an issue even out of the strict BigInt mode, as it may have lost its precision.
+*/
+#[wasm_bindgen(js_name = validateOutput)]
+pub fn validate_output(output: &JsValue) -> JsOutputIssueArray {
+    output_issues(output)
+        .iter()
+        .map(Issue::to_js)
+        .collect::<Array>()
+        .unchecked_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{set_strict_bigint, validate::output_issues, ErrorKind};
+    use js_sys::{Array, Object, Reflect};
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn asset(policy_id: &str, asset_name: &str, quantity: JsValue) -> JsValue {
+        let asset = Object::new();
+        Reflect::set(&asset, &"policyId".into(), &policy_id.into()).unwrap();
+        Reflect::set(&asset, &"assetName".into(), &asset_name.into()).unwrap();
+        Reflect::set(&asset, &"quantity".into(), &quantity).unwrap();
+        asset.into()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_output_issues() {
+        let policy_id = "ab".repeat(28);
+        let output = Object::new();
+        Reflect::set(&output, &"lovelace".into(), &JsValue::from(1000u64)).unwrap();
+        let assets = Array::of1(&asset(&policy_id, "cafe", JsValue::from(5u64)));
+        Reflect::set(&output, &"assets".into(), &assets).unwrap();
+        assert!(output_issues(&output).is_empty());
+
+        Reflect::set(&output, &"lovelace".into(), &1.5.into()).unwrap();
+        assets.push(&asset("abc", "xyz", JsValue::from(-1)));
+        assets.push(&asset(&policy_id, "cafe", JsValue::from(1u64)));
+        let fields: Vec<(String, ErrorKind)> = output_issues(&output)
+            .into_iter()
+            .map(|issue| (issue.field, issue.kind))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("lovelace".into(), ErrorKind::BadAmount),
+                ("assets[1].policyId".into(), ErrorKind::BadAsset),
+                ("assets[1].assetName".into(), ErrorKind::BadAsset),
+                ("assets[1].quantity".into(), ErrorKind::BadAsset),
+                ("assets[2]".into(), ErrorKind::BadAsset),
+            ]
+        );
+
+        assert_eq!(output_issues(&JsValue::NULL)[0].kind, ErrorKind::BadType);
+
+        // Both duplicates of an asset name the first.
+        assets.push(&asset(&policy_id, "cafe", JsValue::from(2u64)));
+        let messages: Vec<String> = output_issues(&output)
+            .into_iter()
+            .filter(|issue| issue.message.contains("duplicates"))
+            .map(|issue| issue.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Asset at assets[2] duplicates assets[0]",
+                "Asset at assets[3] duplicates assets[0]",
+            ]
+        );
+
+        let output = Object::new();
+        Reflect::set(&output, &"lovelace".into(), &9007199254740993.0.into()).unwrap();
+        for strict in [false, true] {
+            set_strict_bigint(strict);
+            let issues = output_issues(&output);
+            assert_eq!(issues.len(), 1);
+            assert_eq!(issues[0].kind, ErrorKind::UnsafeNumber);
+        }
+        set_strict_bigint(false);
+    }
+}
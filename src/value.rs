@@ -0,0 +1,210 @@
+/*!
+Arithmetic of values
+
+The lovelace and assets of outputs are added and compared in JS as they are by selection,
+with the quantities checked, instead of reimplementing multi-asset arithmetic with maps of bigints.
+*/
+use crate::{Asset, ErrorCode, JsAsset, JsOutput, Output};
+use js_sys::{Array, Object, Reflect};
+use utxo::{ExtOutput, Select};
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+export type SubtractResult =
+  | { ok: true, value: Output }
+  | { ok: false, missing: Output }
+
+export type Comparison = {
+  fulfills: boolean
+  lovelace: boolean
+  failing: Array<Asset>
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SubtractResult")]
+    pub type SubtractResult;
+
+    #[wasm_bindgen(typescript_type = "Comparison")]
+    pub type Comparison;
+}
+
+type Value<D> = ExtOutput<D, (String, String)>;
+
+/**
+Add two values
+
+Returns a new output of the lovelace and assets of both, without their other fields.
+Use `sum` for more than two.
+
+Raises errors when the types used are wrong, or there is any value overflowed.
+*/
+#[wasm_bindgen(js_name = addValues)]
+pub fn add_values(a: &JsOutput, b: &JsOutput) -> Result<JsOutput, JsError> {
+    let a: Output = a.try_into()?;
+    let b: Output = b.try_into()?;
+
+    Ok(a.checked_add(&b)
+        .ok_or(ErrorCode::OutputsOverflowed)?
+        .into())
+}
+
+/**
+Subtracts `b` from `a`.
+
+Returns the difference, otherwise what `a` lacks of `b`, the lovelace and assets that went negative.
+*/
+pub(crate) fn subtract<D: Clone>(a: &Value<D>, b: &Value<D>) -> Result<Value<D>, Value<D>> {
+    a.checked_sub(b).ok_or_else(|| b.saturating_sub(a))
+}
+
+/**
+Subtract a value from another
+
+Returns `{ ok: true, value }` of the lovelace and assets of `a` less those of `b`.
+Returns `{ ok: false, missing }` when `b` is more than `a`, `missing` holding
+the lovelace and each asset that went negative, by how much, instead of raising an error.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = subtractValues)]
+pub fn subtract_values(a: &JsOutput, b: &JsOutput) -> Result<SubtractResult, JsError> {
+    let a: Output = a.try_into()?;
+    let b: Output = b.try_into()?;
+    let result = Object::new();
+
+    let (ok, key, output) = match subtract(&a, &b) {
+        Ok(value) => (true, "value", value),
+        Err(missing) => (false, "missing", missing),
+    };
+    let output: JsOutput = output.into();
+    Reflect::set(&result, &"ok".into(), &ok.into()).expect("Unreachable");
+    Reflect::set(&result, &key.into(), &output).expect("Unreachable");
+
+    Ok(result.unchecked_into())
+}
+
+/**
+Compare a value with another
+
+Returns whether `a` covers `b` for the lovelace and every asset as `fulfills`,
+whether it covers the lovelace as `lovelace`, and the assets it does not cover as `failing`,
+each with the quantity missing, e.g. to validate a selection or a payment request.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = compareValues)]
+pub fn compare_values(a: &JsOutput, b: &JsOutput) -> Result<Comparison, JsError> {
+    let a: Output = a.try_into()?;
+    let b: Output = b.try_into()?;
+    let missing = subtract(&a, &b).err().unwrap_or_else(Output::zero);
+
+    let failing: Array = missing
+        .assets
+        .iter()
+        .map(|((policy_id, asset_name), quantity)| {
+            JsValue::from(JsAsset::from(Asset::new(policy_id, asset_name, *quantity)))
+        })
+        .collect();
+    let fulfills = missing.value == 0 && missing.assets.is_empty();
+    let result = Object::new();
+    Reflect::set(&result, &"fulfills".into(), &fulfills.into()).expect("Unreachable");
+    Reflect::set(&result, &"lovelace".into(), &(missing.value == 0).into()).expect("Unreachable");
+    Reflect::set(&result, &"failing".into(), &failing).expect("Unreachable");
+
+    Ok(result.unchecked_into())
+}
+
+/**
+Whether a value covers another
+
+Returns `true` if `a` has at least the lovelace and every asset of `b`.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen]
+pub fn fulfills(a: &JsOutput, b: &JsOutput) -> Result<bool, JsError> {
+    let a: Output = a.try_into()?;
+    let b: Output = b.try_into()?;
+
+    Ok(subtract(&a, &b).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        value::{add_values, compare_values, fulfills, subtract},
+        JsOutput, Output,
+    };
+    use js_sys::Array;
+    use std::collections::BTreeMap;
+    use utxo::ExtOutput;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn value(value: u64, quantity: u64) -> ExtOutput<(), (String, String)> {
+        let mut output = ExtOutput {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        output.insert_asset(("policy1".into(), "aname1".into()), quantity);
+        output
+    }
+
+    fn output(value: u64, quantity: u64) -> JsOutput {
+        let mut output = Output {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        output.insert_asset(("policy1".into(), "aname1".into()), quantity);
+        output.into()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_add_values() {
+        let sum = add_values(&output(1000, 5), &output(2000, 0)).unwrap();
+        assert_eq!(sum.lovelace(), 3000);
+        assert_eq!(sum.assets().unchecked_into::<Array>().length(), 1);
+
+        assert!(add_values(&output(u64::MAX, 0), &output(1, 0)).is_err());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_subtract() {
+        assert_eq!(
+            subtract(&value(3000, 5), &value(1000, 2)),
+            Ok(value(2000, 3))
+        );
+        assert_eq!(subtract(&value(3000, 5), &value(1000, 7)), Err(value(0, 2)));
+
+        let mut b = value(4000, 0);
+        b.insert_asset(("policy2".into(), "aname2".into()), 9);
+        let mut missing = value(1000, 0);
+        missing.insert_asset(("policy2".into(), "aname2".into()), 9);
+        assert_eq!(subtract(&value(3000, 5), &b), Err(missing));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_compare_values() {
+        let comparison = compare_values(&output(1000, 5), &output(2000, 3)).unwrap();
+        let field = |key: &str| js_sys::Reflect::get(&comparison, &key.into()).unwrap();
+        assert_eq!(field("fulfills").as_bool(), Some(false));
+        assert_eq!(field("lovelace").as_bool(), Some(false));
+        assert_eq!(field("failing").unchecked_into::<Array>().length(), 0);
+
+        let comparison = compare_values(&output(3000, 5), &output(2000, 8)).unwrap();
+        let failing: Array = js_sys::Reflect::get(&comparison, &"failing".into())
+            .unwrap()
+            .unchecked_into();
+        assert_eq!(failing.length(), 1);
+
+        assert!(fulfills(&output(3000, 5), &output(2000, 5)).unwrap());
+        assert!(!fulfills(&output(3000, 5), &output(2000, 6)).unwrap());
+    }
+}
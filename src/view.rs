@@ -0,0 +1,326 @@
+/*!
+Views of selection results
+
+A `SelectResult` is a plain object, so anything computed from it in JS iterates its arrays again.
+A [`SelectResultView`] wraps it with the common follow-up computations done once in Rust.
+*/
+use crate::{
+    collect_js_outputs, get_amount, get_field, iterate, out_point::OutPoint, to_outputs, ErrorCode,
+    JsOutput, Output, SelectResult,
+};
+use js_sys::{Array, Object, Reflect};
+use utxo::try_sum;
+use wasm_bindgen::{prelude::*, JsCast};
+
+fn to_json_output(output: &Output) -> JsValue {
+    let result = Object::new();
+    let set = |target: &Object, key: &str, value: &JsValue| {
+        Reflect::set(target, &key.into(), value).expect("Unreachable");
+    };
+    let assets: Array = output
+        .assets
+        .iter()
+        .filter(|(_, quantity)| **quantity > 0)
+        .map(|((policy_id, asset_name), quantity)| {
+            let asset = Object::new();
+            set(&asset, "policyId", &policy_id.into());
+            set(&asset, "assetName", &asset_name.into());
+            set(&asset, "quantity", &quantity.to_string().into());
+            JsValue::from(asset)
+        })
+        .collect();
+
+    set(&result, "lovelace", &output.value.to_string().into());
+    set(&result, "assets", &assets);
+
+    if let Some(js_output) = output.data {
+        if let Some(address) = js_output.address() {
+            set(&result, "address", &address.into());
+        }
+
+        if let Some(out_point) = OutPoint::of(js_output) {
+            set(&result, "data", &out_point.to_js());
+        }
+    }
+
+    result.into()
+}
+
+/// The fields of UTxOs kept as they are by `toTransferable` when they are strings or numbers.
+const TRANSFERABLE_FIELDS: [&str; 5] = ["address", "slot", "datumHash", "datum", "scriptRef"];
+
+/// A copy of the UTxO of plain values, keeping only its `OutPoint` as `data`.
+fn to_transferable_output(js_output: &JsOutput) -> Result<JsValue, ErrorCode> {
+    let output: JsOutput = Output::try_from(js_output)?.into();
+
+    for key in TRANSFERABLE_FIELDS {
+        let value = Reflect::get(js_output, &key.into()).map_err(|_| ErrorCode::InvalidType)?;
+        if value.is_string() || value.is_bigint() || value.as_f64().is_some() {
+            Reflect::set(&output, &key.into(), &value).expect("Unreachable");
+        }
+    }
+
+    if let Some(out_point) = OutPoint::of(js_output) {
+        output.set_data(&out_point.to_js());
+    }
+
+    Ok(output.into())
+}
+
+/**
+The result with its UTxOs and excess, and those of its alternatives,
+copied by `to_transferable_output`.
+*/
+pub(crate) fn transferable(result: &SelectResult) -> Result<SelectResult, ErrorCode> {
+    let copy: Object = Object::assign(&Object::new(), result.unchecked_ref());
+    let excess = to_transferable_output(&result.excess())?;
+    Reflect::set(&copy, &"excess".into(), &excess).expect("Unreachable");
+
+    for key in [
+        "selected",
+        "unselected",
+        "referenceInputs",
+        "outputs",
+        "changeOutputs",
+    ] {
+        let js_outputs = get_field(result, key)?;
+        if js_outputs.is_undefined() {
+            continue;
+        }
+
+        let outputs = collect_js_outputs(js_outputs.unchecked_ref())?
+            .iter()
+            .map(to_transferable_output)
+            .collect::<Result<Array, ErrorCode>>()?;
+        Reflect::set(&copy, &key.into(), &outputs).expect("Unreachable");
+    }
+
+    let alternatives = get_field(result, "alternatives")?;
+    if !alternatives.is_undefined() {
+        let alternatives = iterate(&alternatives)?
+            .iter()
+            .map(|alternative| transferable(alternative.unchecked_ref()).map(JsValue::from))
+            .collect::<Result<Array, ErrorCode>>()?;
+        Reflect::set(&copy, &"alternatives".into(), &alternatives).expect("Unreachable");
+    }
+
+    Ok(copy.unchecked_into())
+}
+
+/**
+Copy a result to one of plain values, for `postMessage`
+
+Returns the result with every UTxO, the excess and any change outputs copied to plain objects
+of their `lovelace`, `assets`, `address`, `slot`, `datumHash`, `datum` and `scriptRef`,
+and `OutPoint` as `data`, so that it can be structured-cloned, e.g. computed in a Worker
+and posted to the page, even if the UTxOs passed in carry objects of other libraries as fields,
+which the excess collects from them.
+
+Raises errors when the types used are wrong.
+*/
+#[wasm_bindgen(js_name = toTransferable)]
+pub fn to_transferable(result: &SelectResult) -> Result<SelectResult, JsError> {
+    Ok(transferable(result)?)
+}
+
+/// A `SelectResult` with the computations on it.
+#[wasm_bindgen]
+pub struct SelectResultView {
+    result: SelectResult,
+    selected: Vec<JsOutput>,
+    unselected: Vec<JsOutput>,
+    excess: JsOutput,
+}
+
+#[wasm_bindgen]
+impl SelectResultView {
+    /**
+    Wrap a result of `select`
+
+    Raises errors when the types used are wrong.
+    */
+    #[wasm_bindgen(constructor)]
+    pub fn new(result: SelectResult) -> Result<SelectResultView, JsError> {
+        let selected = collect_js_outputs(&result.selected())?;
+        let unselected = collect_js_outputs(&result.unselected())?;
+        let excess = result.excess();
+        Output::try_from(&excess)?;
+        to_outputs(&selected)?;
+        to_outputs(&unselected)?;
+
+        Ok(Self {
+            result,
+            selected,
+            unselected,
+            excess,
+        })
+    }
+
+    /// The wrapped result.
+    #[wasm_bindgen(getter)]
+    pub fn result(&self) -> SelectResult {
+        self.result.clone().unchecked_into()
+    }
+
+    /// The `outPointKey`s of the selected UTxOs, skipping those without `OutPoint`s as `data`.
+    #[wasm_bindgen(js_name = selectedIds)]
+    pub fn selected_ids(&self) -> Vec<String> {
+        self.selected
+            .iter()
+            .filter_map(OutPoint::of)
+            .map(|out_point| out_point.key())
+            .collect()
+    }
+
+    /**
+    The sum of the selected UTxOs
+
+    Raises errors when the sum overflowed.
+    */
+    #[wasm_bindgen(js_name = totalSelected)]
+    pub fn total_selected(&self) -> Result<JsOutput, JsError> {
+        let selected = to_outputs(&self.selected)?;
+        let total = try_sum(&selected).ok_or(ErrorCode::OutputsOverflowed)?;
+
+        Ok(total.into())
+    }
+
+    /// The number of the selected UTxOs.
+    #[wasm_bindgen(js_name = inputCount)]
+    pub fn input_count(&self) -> usize {
+        self.selected.len()
+    }
+
+    /// Whether anything is in the excess, to pay the fee or return as change.
+    #[wasm_bindgen(js_name = hasChange)]
+    pub fn has_change(&self) -> bool {
+        Output::try_from(&self.excess).is_ok_and(|excess| {
+            excess.value > 0 || excess.assets.values().any(|quantity| *quantity > 0)
+        })
+    }
+
+    /**
+    The result safe for `JSON.stringify`
+
+    Lovelace and quantities are decimal strings, and each UTxO keeps only
+    its address and its `OutPoint` as `data`.
+    */
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> Result<JsValue, JsError> {
+        let to_array = |js_outputs: &[JsOutput]| -> Result<JsValue, ErrorCode> {
+            let outputs: Array = to_outputs(js_outputs)?.iter().map(to_json_output).collect();
+            Ok(outputs.into())
+        };
+        let excess = Output::try_from(&self.excess)?;
+        let result = Object::new();
+
+        Reflect::set(&result, &"selected".into(), &to_array(&self.selected)?).expect("Unreachable");
+        Reflect::set(&result, &"unselected".into(), &to_array(&self.unselected)?)
+            .expect("Unreachable");
+        Reflect::set(&result, &"excess".into(), &to_json_output(&excess)).expect("Unreachable");
+
+        if let Some(donated) = get_amount(&self.result, "donated")? {
+            Reflect::set(&result, &"donated".into(), &donated.to_string().into())
+                .expect("Unreachable");
+        }
+
+        Ok(result.unchecked_into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        options::DustTokens,
+        out_point::OutPoint,
+        to_outputs, to_select_result,
+        view::{transferable, SelectResultView},
+        JsOutput, Output,
+    };
+    use js_sys::{Array, Reflect, JSON};
+    use std::collections::BTreeMap;
+    use utxo::Select;
+    use wasm_bindgen_test::*;
+
+    fn js_output(value: u64, index: u32) -> JsOutput {
+        let output: JsOutput = Output {
+            value,
+            assets: BTreeMap::new(),
+            data: None,
+        }
+        .into();
+        output.set_data(&OutPoint::new(&"ab".repeat(32), index).unwrap().to_js());
+        output
+    }
+
+    #[wasm_bindgen_test]
+    fn test_select_result_view() {
+        let selected = [js_output(3000, 0), js_output(5000, 1)];
+        let unselected = [js_output(1000, 2)];
+        let excess = Output {
+            value: 500,
+            assets: BTreeMap::new(),
+            data: None,
+        };
+        let result = to_select_result(
+            &to_outputs(&selected).unwrap(),
+            &to_outputs(&unselected).unwrap(),
+            excess,
+            &DustTokens::default(),
+        );
+        let view = SelectResultView::new(result).unwrap();
+
+        assert_eq!(view.input_count(), 2);
+        assert!(view.has_change());
+        assert_eq!(
+            view.selected_ids(),
+            vec![
+                format!("{}#0", "ab".repeat(32)),
+                format!("{}#1", "ab".repeat(32))
+            ]
+        );
+        assert_eq!(view.total_selected().unwrap().lovelace(), 8000);
+
+        let json = JSON::stringify(&view.to_json().unwrap()).unwrap();
+        let parsed = JSON::parse(&String::from(json)).unwrap();
+        let excess = Reflect::get(&parsed, &"excess".into()).unwrap();
+        assert_eq!(Reflect::get(&excess, &"lovelace".into()).unwrap(), "500");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_transferable() {
+        let selected = [js_output(3000, 0)];
+        selected[0].set_address("addr_test1");
+        let wallet = js_sys::Function::new_no_args("return 1");
+        Reflect::set(&selected[0], &"wallet".into(), &wallet).unwrap();
+        let result = to_select_result(
+            &to_outputs(&selected).unwrap(),
+            &[],
+            Output::zero(),
+            &DustTokens::default(),
+        );
+
+        let copy = transferable(&result).unwrap();
+        let input = Array::from(&copy.selected()).get(0);
+        assert!(Reflect::get(&input, &"wallet".into())
+            .unwrap()
+            .is_undefined());
+        assert_eq!(
+            Reflect::get(&input, &"address".into()).unwrap(),
+            "addr_test1"
+        );
+        let data = Reflect::get(&input, &"data".into()).unwrap();
+        assert_eq!(Reflect::get(&data, &"index".into()).unwrap(), 0);
+
+        // The extra fields of the sole input are copied to the excess of the result.
+        assert!(Reflect::get(&result.excess(), &"wallet".into())
+            .unwrap()
+            .is_function());
+        assert!(Reflect::get(&copy.excess(), &"wallet".into())
+            .unwrap()
+            .is_undefined());
+        assert!(Reflect::get(&selected[0], &"wallet".into())
+            .unwrap()
+            .is_function());
+    }
+}